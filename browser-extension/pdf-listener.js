@@ -13,15 +13,35 @@ function handleNativeResponse(response) {
 
     console.log('BigMan AntiVirus: Received response from scanner:', response);
 
+    // Hosts before `verdict` was added only send `status`
+    const verdict = response && (response.verdict || String(response.status || '').toLowerCase());
+
     // If the Rust program found something suspicious, notify the user
-    if (response && response.status === 'SUSPICIOUS') {
+    if (verdict === 'suspicious') {
         chrome.notifications.create({
             type: 'basic',
             iconUrl: 'icons/128.png', // Fixed icon path to match manifest
             title: 'Suspicious PDF Detected!',
-            message: `A downloaded PDF was flagged. Reason: ${response.reason}`
+            message: response.actions_taken && response.actions_taken.includes('quarantined')
+                ? `A downloaded PDF was flagged and quarantined. Reason: ${response.reason}`
+                : `A downloaded PDF was flagged. Reason: ${response.reason}`
         });
+    } else if (verdict === 'error') {
+        // The file couldn't be scanned (missing, unreadable, too large); not a detection
+        console.warn('BigMan AntiVirus: Could not scan download:', response.reason);
+    }
+}
+
+/**
+ * Whether the native host knows how to scan this download.
+ * @param {chrome.downloads.DownloadItem} downloadItem - The completed download.
+ * @returns {boolean}
+ */
+function isScannableDownload(downloadItem) {
+    if (downloadItem.mime === 'application/pdf') {
+        return true;
     }
+    return /\.(exe|dll)$/i.test(downloadItem.filename);
 }
 
 /**
@@ -36,17 +56,17 @@ async function onDownloadChanged(downloadDelta) {
             const downloadItems = await chrome.downloads.search({ id: downloadDelta.id });
             const downloadItem = downloadItems[0];
             
-            // Check if the downloaded file is a PDF
-            if (downloadItem && downloadItem.mime === 'application/pdf') {
+            // Check if the downloaded file is a PDF or a Windows executable
+            if (downloadItem && isScannableDownload(downloadItem)) {
                 // Use the full file path from the filename property
                 const fullPath = downloadItem.filename;
-                console.log('BigMan AntiVirus: PDF download complete. Scanning file:', fullPath);
+                console.log('BigMan AntiVirus: Download complete. Scanning file:', fullPath);
 
                 // Send the file path to your Rust program for scanning
                 // 'com.bigman.pdf_scanner' must match the name in your native host manifest file
                 chrome.runtime.sendNativeMessage(
                     'com.bigman.pdf_scanner',
-                    { filePath: fullPath },
+                    { type: 'scanPdf', filePath: fullPath },
                     handleNativeResponse
                 );
             }
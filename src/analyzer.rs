@@ -1,81 +1,1495 @@
 use regex::Regex;
-use anyhow::Result;
+use anyhow::{Context, Result};
 use colored::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::LazyLock;
+use crate::ioc::IocList;
 
+/// How dangerous a single match is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Severity {
+    #[serde(alias = "info")]
+    Info,
+    #[serde(alias = "low")]
+    Low,
+    #[serde(alias = "medium")]
+    Medium,
+    #[serde(alias = "high")]
+    High,
+    #[serde(alias = "critical")]
+    Critical,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+impl Severity {
+    /// How much one match adds to `AnalysisResult::score`.
+    pub fn weight(self) -> u32 {
+        match self {
+            Severity::Info => 1,
+            Severity::Low => 5,
+            Severity::Medium => 15,
+            Severity::High => 40,
+            Severity::Critical => 100,
+        }
+    }
+}
+
+impl std::str::FromStr for Severity {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Severity> {
+        match s.to_ascii_lowercase().as_str() {
+            "info" => Ok(Severity::Info),
+            "low" => Ok(Severity::Low),
+            "medium" => Ok(Severity::Medium),
+            "high" => Ok(Severity::High),
+            "critical" => Ok(Severity::Critical),
+            _ => anyhow::bail!("Unknown severity '{}' (expected info, low, medium, high or critical)", s),
+        }
+    }
+}
+
+/// A detection rule, with the documentation shown by `explain-rule`.
+#[derive(Debug)]
+pub struct Rule {
+    /// Stable id, safe to reference from allowlists and feedback.
+    pub id: &'static str,
+    /// Regex matched against each line, or `None` for checks written in code.
+    pub pattern: Option<&'static str>,
+    pub description: &'static str,
+    pub severity: Severity,
+    pub category: &'static str,
+    /// Why a match matters.
+    pub rationale: &'static str,
+    /// What to do about a match.
+    pub remediation: &'static str,
+}
+
+/// Every analyzer rule, line patterns first in the order they are checked.
+pub const RULES: &[Rule] = &[
+    Rule {
+        id: "shell:curl-download",
+        pattern: Some(r"curl\s+[^\s]+"),
+        description: "Downloads files from the internet",
+        severity: Severity::Info,
+        category: "download",
+        rationale: "Fetching files is normal in install scripts, but it is also the first step of every dropper. Whatever is downloaded runs with your privileges if a later line executes it.",
+        remediation: "Check that the URL is the project's official HTTPS domain and that the file is verified (checksum or signature) before use.",
+    },
+    Rule {
+        id: "shell:wget-download",
+        pattern: Some(r"wget\s+[^\s]+"),
+        description: "Downloads files from the internet",
+        severity: Severity::Info,
+        category: "download",
+        rationale: "Fetching files is normal in install scripts, but it is also the first step of every dropper. Whatever is downloaded runs with your privileges if a later line executes it.",
+        remediation: "Check that the URL is the project's official HTTPS domain and that the file is verified (checksum or signature) before use.",
+    },
+    Rule {
+        id: "shell:curl-pipe-shell",
+        pattern: Some(r"curl\s+.*\|\s*(bash|sh|zsh)"),
+        description: "Downloads and immediately executes code (VERY DANGEROUS)",
+        severity: Severity::Critical,
+        category: "execution",
+        rationale: "Piping a download straight into a shell runs whatever the server sends, with no chance to read it first. The server can even send different content to a shell than to a browser.",
+        remediation: "Download the script to a file, read it (or pipe it into bigman), then run it yourself.",
+    },
+    Rule {
+        id: "shell:wget-pipe-shell",
+        pattern: Some(r"wget\s+.*\|\s*(bash|sh|zsh)"),
+        description: "Downloads and immediately executes code (VERY DANGEROUS)",
+        severity: Severity::Critical,
+        category: "execution",
+        rationale: "Piping a download straight into a shell runs whatever the server sends, with no chance to read it first. The server can even send different content to a shell than to a browser.",
+        remediation: "Download the script to a file, read it (or pipe it into bigman), then run it yourself.",
+    },
+    Rule {
+        id: "shell:rm-recursive",
+        pattern: Some(r"rm\s+-r[f]?"),
+        description: "Recursively deletes files/folders (can be destructive)",
+        severity: Severity::High,
+        category: "destruction",
+        rationale: "A recursive delete with a wrong or empty variable (`rm -rf $DIR/`) can wipe a home directory or the whole system.",
+        remediation: "Make sure the target is an explicit, expected path and that any variables in it can't be empty.",
+    },
+    Rule {
+        id: "shell:rm-wildcard",
+        pattern: Some(r"rm\s+.*\*"),
+        description: "Deletes files using wildcards (can delete more than intended)",
+        severity: Severity::Medium,
+        category: "destruction",
+        rationale: "Wildcards expand relative to the current directory, so the same line deletes different files depending on where it runs.",
+        remediation: "Check which directory the command runs in and what the pattern matches (`ls` it first).",
+    },
+    Rule {
+        id: "shell:sudo",
+        pattern: Some(r"sudo\s+"),
+        description: "Runs commands with administrator privileges",
+        severity: Severity::Medium,
+        category: "privilege",
+        rationale: "Anything run under sudo can change or read any file on the system, so a mistake or malicious command is no longer contained to your account.",
+        remediation: "Make sure each sudo command is needed and does what it says; prefer user-level installs where possible.",
+    },
+    Rule {
+        id: "shell:chmod",
+        pattern: Some(r"chmod\s+"),
+        description: "Changes file permissions",
+        severity: Severity::Low,
+        category: "permissions",
+        rationale: "Permission changes are common, but `chmod +x` on a downloaded file is how droppers prepare their payload.",
+        remediation: "Check which file is being changed and where it came from.",
+    },
+    Rule {
+        id: "shell:chmod-world-writable",
+        pattern: Some(r"chmod\s+(777|666)"),
+        description: "Makes files readable/writable by everyone (security risk)",
+        severity: Severity::Medium,
+        category: "permissions",
+        rationale: "World-writable files let any local user or compromised service change them, which turns them into a way to inject code.",
+        remediation: "Use the narrowest permissions that work, e.g. 755 for programs and 644 for data files.",
+    },
+    Rule {
+        id: "shell:base64-decode",
+        pattern: Some(r"base64\s+-d"),
+        description: "Decodes hidden/obfuscated content",
+        severity: Severity::Medium,
+        category: "obfuscation",
+        rationale: "Encoding a payload hides what it does from a casual read and from simple pattern scanners.",
+        remediation: "Decode the content yourself (without running it) and analyze the result.",
+    },
+    Rule {
+        id: "shell:echo-base64",
+        pattern: Some(r"echo\s+.*\|\s*base64"),
+        description: "Decodes hidden/obfuscated content",
+        severity: Severity::Medium,
+        category: "obfuscation",
+        rationale: "Encoding a payload hides what it does from a casual read and from simple pattern scanners.",
+        remediation: "Decode the content yourself (without running it) and analyze the result.",
+    },
+    Rule {
+        id: "shell:netcat-listen",
+        pattern: Some(r"nc\s+-l|netcat\s+-l"),
+        description: "Opens network port (potential backdoor)",
+        severity: Severity::High,
+        category: "network",
+        rationale: "A listening netcat is a classic bind shell: anyone who can reach the port may get a shell on your machine.",
+        remediation: "Don't run it unless you know exactly why the script needs an open port and who can connect.",
+    },
+    Rule {
+        id: "shell:crontab",
+        pattern: Some(r"crontab\s+"),
+        description: "Modifies scheduled tasks",
+        severity: Severity::Medium,
+        category: "persistence",
+        rationale: "Cron jobs keep running after the script exits and survive reboots, which is how malware stays installed.",
+        remediation: "Check what job is added and remove it with `crontab -e` if it isn't expected.",
+    },
+    Rule {
+        id: "shell:ssh-keys",
+        pattern: Some(r"\.ssh/"),
+        description: "Accesses SSH keys (remote access credentials)",
+        severity: Severity::High,
+        category: "credentials",
+        rationale: "Reading private keys lets an attacker log in to every server you can; writing `authorized_keys` gives them a way into this machine.",
+        remediation: "Installers almost never need `~/.ssh`; don't run the script unless you understand why it touches it.",
+    },
+    Rule {
+        id: "shell:etc-passwd",
+        pattern: Some(r"/etc/passwd"),
+        description: "Accesses user account information",
+        severity: Severity::Medium,
+        category: "reconnaissance",
+        rationale: "Listing accounts is a common early step of an attack, and writing to the file can create hidden users.",
+        remediation: "Check whether the script only reads it (e.g. `getent`) or modifies it.",
+    },
+    Rule {
+        id: "shell:systemctl-enable",
+        pattern: Some(r"systemctl\s+enable"),
+        description: "Enables system services",
+        severity: Severity::Medium,
+        category: "persistence",
+        rationale: "Enabled services start on every boot, often as root.",
+        remediation: "Check which unit is enabled and read its unit file (`systemctl cat <unit>`).",
+    },
+    Rule {
+        id: "powershell:invoke-expression",
+        pattern: Some(r"(?i)\bInvoke-Expression\b|(^|[|;(=\s])iex(\s|\(|$)"),
+        description: "Runs a string as PowerShell code",
+        severity: Severity::High,
+        category: "execution",
+        rationale: "`Invoke-Expression` (`IEX`) runs text built at run time, usually text that was just downloaded or decoded, so what actually executes never appears in the script.",
+        remediation: "Replace the `IEX` with `Write-Output` to see the code it would run, then analyze that.",
+    },
+    Rule {
+        id: "powershell:download-string",
+        pattern: Some(r"(?i)\.Download(String|File|Data)(Async)?\s*\("),
+        description: "Downloads files or code from the internet",
+        severity: Severity::Medium,
+        category: "download",
+        rationale: "`WebClient.DownloadString` is the PowerShell equivalent of `curl`; together with `IEX` it runs remote code without saving it to disk.",
+        remediation: "Check that the URL is the project's official HTTPS domain, and download the file to read it before anything runs it.",
+    },
+    Rule {
+        id: "powershell:disable-defender",
+        pattern: Some(r"(?i)Set-MpPreference\b.*-Disable(RealtimeMonitoring|BehaviorMonitoring|IOAVProtection|ScriptScanning|BlockAtFirstSeen)\b"),
+        description: "Turns off Microsoft Defender protection (VERY DANGEROUS)",
+        severity: Severity::Critical,
+        category: "defense-evasion",
+        rationale: "Legitimate installers have no reason to switch off antivirus; malware does it right before dropping its payload.",
+        remediation: "Don't run the script. If it already ran, turn real-time protection back on and run a full scan.",
+    },
+    Rule {
+        id: "powershell:encoded-command",
+        pattern: Some(r"(?i)\b(powershell|pwsh)(\.exe)?\b.*\s-e(c|n[a-z]*)?\s+['\x22]?[A-Za-z0-9+/]{20,}={0,2}"),
+        description: "Runs a base64-encoded PowerShell payload",
+        severity: Severity::High,
+        category: "obfuscation",
+        rationale: "`-EncodedCommand` (`-enc`, `-e`) hides the whole command in base64 (UTF-16LE), so nothing it does is readable in the script.",
+        remediation: "Decode the payload yourself without running it, e.g. `[Text.Encoding]::Unicode.GetString([Convert]::FromBase64String('...'))`, and analyze the result.",
+    },
+    Rule {
+        id: "powershell:registry-run-key",
+        pattern: Some(r"(?i)(\breg(\.exe)?\s+add\b|New-ItemProperty|Set-ItemProperty).*\\CurrentVersion\\Run(Once)?\b"),
+        description: "Adds a program to the Windows startup Run keys",
+        severity: Severity::High,
+        category: "persistence",
+        rationale: "Programs listed under `...\\CurrentVersion\\Run` start at every logon, which is how malware stays installed on Windows.",
+        remediation: "Check which program is added; remove the value with `reg delete` or regedit if it isn't expected.",
+    },
+    Rule {
+        id: "chain:download-exec",
+        pattern: None,
+        description: "Downloads a file and runs it (multi-step dropper, VERY DANGEROUS)",
+        severity: Severity::Critical,
+        category: "execution",
+        rationale: "Splitting download, chmod +x and run across lines avoids single-line patterns like `curl | sh`, but the effect is the same: unreviewed code runs.",
+        remediation: "Download the file, inspect or verify it, and only then run it yourself.",
+    },
+    Rule {
+        id: "timebomb:date-gated",
+        pattern: None,
+        description: "Possible time-triggered payload: a date/time check gates a destructive or network action",
+        severity: Severity::High,
+        category: "time-bomb",
+        rationale: "Scripts that only misbehave after a certain date pass every test run before it, then fire on all machines at once.",
+        remediation: "Read the conditional and the action it guards; legitimate scripts rarely need the current date to decide whether to delete files or contact a server.",
+    },
+    Rule {
+        id: "timebomb:scheduled",
+        pattern: None,
+        description: "Possible time-triggered payload: schedules a command to run later",
+        severity: Severity::Medium,
+        category: "time-bomb",
+        rationale: "`at`, `batch` and transient systemd timers run a command after the script has exited, when nobody is watching its output.",
+        remediation: "Check the scheduled command; list pending jobs with `atq` or `systemctl list-timers` and remove unexpected ones.",
+    },
+    Rule {
+        id: "timebomb:delayed-action",
+        pattern: None,
+        description: "Possible time-triggered payload: a long sleep precedes a destructive or network action",
+        severity: Severity::High,
+        category: "time-bomb",
+        rationale: "A long delay before the harmful step lets an installer finish and look clean before the payload runs.",
+        remediation: "Check why the script waits and what it does afterwards.",
+    },
+    Rule {
+        id: "unicode:bidi-control",
+        pattern: None,
+        description: "Reorders how text is displayed, so what you read is not what runs",
+        severity: Severity::High,
+        category: "unicode",
+        rationale: "Bidirectional control characters (\"Trojan Source\") make a terminal or editor show text in a different order than the shell executes it.",
+        remediation: "View the script with `cat -A` or a hex viewer and remove the control characters.",
+    },
+    Rule {
+        id: "unicode:zero-width",
+        pattern: None,
+        description: "Invisible characters can split keywords to hide them from scanners",
+        severity: Severity::High,
+        category: "unicode",
+        rationale: "Zero-width characters are invisible but change the bytes, so `cu\u{200B}rl` looks like curl yet slips past simple filters.",
+        remediation: "Retype the affected text or strip the characters before running anything.",
+    },
+    Rule {
+        id: "unicode:mixed-script",
+        pattern: None,
+        description: "Look-alike characters can impersonate a trusted name or domain",
+        severity: Severity::Medium,
+        category: "unicode",
+        rationale: "Cyrillic and Greek letters that look like Latin ones let `pаypal.com` pass for the real domain.",
+        remediation: "Compare the name byte by byte with the genuine one, or retype it.",
+    },
+    Rule {
+        id: "ioc:network",
+        pattern: None,
+        description: "References an indicator on the imported blocklist",
+        severity: Severity::High,
+        category: "ioc",
+        rationale: "The domain, IP or URL was imported from a threat report as known-malicious.",
+        remediation: "Don't run the script; check where it came from.",
+    },
+    Rule {
+        id: "secret:private-key",
+        pattern: Some(r"-----BEGIN ((RSA|DSA|EC|OPENSSH|ENCRYPTED|PGP) )?PRIVATE KEY( BLOCK)?-----"),
+        description: "Private key",
+        severity: Severity::Critical,
+        category: "secrets",
+        rationale: "Anyone with the file can impersonate the key's owner: log in over SSH, sign code or decrypt traffic.",
+        remediation: "Remove the key from the file and its history, then revoke it and generate a new one.",
+    },
+    Rule {
+        id: "secret:aws-access-key",
+        pattern: Some(r"\b(?P<value>(AKIA|ASIA)[0-9A-Z]{16})\b"),
+        description: "AWS access key ID",
+        severity: Severity::High,
+        category: "secrets",
+        rationale: "Access key IDs are half of an AWS credential pair; the secret key is usually nearby.",
+        remediation: "Deactivate the key in IAM, rotate it, and load credentials from the environment or a profile instead.",
+    },
+    Rule {
+        id: "secret:jwt",
+        pattern: Some(r"\b(?P<value>eyJ[A-Za-z0-9_-]{10,}\.eyJ[A-Za-z0-9_-]{10,}\.[A-Za-z0-9_-]{10,})"),
+        description: "JSON Web Token",
+        severity: Severity::High,
+        category: "secrets",
+        rationale: "A JWT is a bearer token: until it expires, whoever holds it is logged in as its subject.",
+        remediation: "Remove it and revoke the session; read tokens from the environment at run time.",
+    },
+    Rule {
+        id: "secret:password-assignment",
+        pattern: Some(r#"(?i)\b(password|passwd|pwd|secret|api[_-]?key|access[_-]?token|auth[_-]?token)\b["']?\s*[:=]\s*["']?(?P<value>[^\s"'$][^\s"']{3,})"#),
+        description: "Hard-coded password or API key",
+        severity: Severity::Medium,
+        category: "secrets",
+        rationale: "Credentials written into files end up in backups, version control and anyone's copy of the script.",
+        remediation: "Move the value to an environment variable or a secrets manager, and rotate it if it was shared.",
+    },
+    Rule {
+        id: "secret:high-entropy-token",
+        pattern: Some(r"(?P<value>[A-Za-z0-9+/_-]{32,}={0,2})"),
+        description: "Random-looking token",
+        severity: Severity::Medium,
+        category: "secrets",
+        rationale: "Long random strings are usually keys or tokens. Only ones with high character entropy are reported, so hex hashes and words don't count.",
+        remediation: "Check what the string is; if it is a credential, remove and rotate it.",
+    },
+];
+
+/// Tokens below this many bits of entropy per character (hex digests,
+/// repeated characters) aren't reported as secrets.
+const SECRET_MIN_ENTROPY: f64 = 4.5;
+
+/// Looks up a rule by its id.
+pub fn find_rule(id: &str) -> Option<&'static Rule> {
+    RULES.iter().find(|rule| rule.id == id)
+}
+
+fn rule(id: &str) -> &'static Rule {
+    find_rule(id).expect("built-in rule ids are listed in RULES")
+}
+
+/// One `[[rule]]` entry in a TOML rules file.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RuleDefinition {
+    pub id: Option<String>,
+    pub pattern: String,
+    pub description: String,
+    /// Defaults to medium when left out.
+    pub severity: Option<Severity>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RulesFile {
+    #[serde(default)]
+    rule: Vec<RuleDefinition>,
+}
+
+/// Reads the rule entries of a TOML rules file without compiling them.
+pub fn read_rules_file(path: &Path) -> Result<Vec<RuleDefinition>> {
+    let content = std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let file: RulesFile = toml::from_str(&content).with_context(|| format!("{} is not a valid rules file", path.display()))?;
+    Ok(file.rule)
+}
+
+/// Which operating system's rule families an analysis runs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Platform {
+    /// Shell idioms: `shell:`, `chain:` and `timebomb:` rules.
+    Unix,
+    /// PowerShell and cmd idioms: `powershell:` rules.
+    Windows,
+    #[default]
+    All,
+}
+
+impl std::str::FromStr for Platform {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Platform> {
+        match s.to_ascii_lowercase().as_str() {
+            "unix" => Ok(Platform::Unix),
+            "windows" => Ok(Platform::Windows),
+            "all" => Ok(Platform::All),
+            _ => anyhow::bail!("Unknown platform '{}' (expected windows, unix or all)", s),
+        }
+    }
+}
+
+impl Platform {
+    /// Whether a rule belongs to this platform. Rules tied to no platform
+    /// (Unicode, IOC, secrets, custom rules) always run.
+    pub fn includes(self, rule_id: &str) -> bool {
+        let family = if rule_id.starts_with("powershell:") {
+            Platform::Windows
+        } else if ["shell:", "chain:", "timebomb:"].iter().any(|prefix| rule_id.starts_with(prefix)) {
+            Platform::Unix
+        } else {
+            return true;
+        };
+        self == Platform::All || self == family
+    }
+}
+
+/// The line patterns an analysis runs: the built-in ones, a user's rules
+/// file, or both, limited to one platform's rule families.
+#[derive(Debug, Clone)]
+pub struct PatternSet {
+    rules: Vec<&'static Rule>,
+    platform: Platform,
+}
+
+impl Default for PatternSet {
+    fn default() -> PatternSet {
+        PatternSet::builtin()
+    }
+}
+
+impl PatternSet {
+    /// The built-in line patterns, secrets excluded (those are matched
+    /// separately so they are never echoed).
+    pub fn builtin() -> PatternSet {
+        PatternSet {
+            rules: RULES.iter().filter(|rule| rule.pattern.is_some() && rule.category != "secrets").collect(),
+            platform: Platform::All,
+        }
+    }
+
+    /// Keeps only the rules for `platform`.
+    pub fn for_platform(mut self, platform: Platform) -> PatternSet {
+        self.rules.retain(|rule| platform.includes(rule.id));
+        self.platform = platform;
+        self
+    }
+
+    /// Loads the rules in a TOML rules file, after the built-ins unless
+    /// `include_builtins` is false. Every pattern is compiled up front, so a
+    /// bad one is reported by name instead of being skipped during a scan.
+    pub fn load(path: &Path, include_builtins: bool) -> Result<PatternSet> {
+        let mut set = if include_builtins { PatternSet::builtin() } else { PatternSet { rules: Vec::new(), platform: Platform::All } };
+        for (i, def) in read_rules_file(path)?.into_iter().enumerate() {
+            let id = def.id.unwrap_or_else(|| format!("custom:rule-{}", i + 1));
+            Regex::new(&def.pattern)
+                .with_context(|| format!("{}: rule '{}' has an invalid pattern", path.display(), id))?;
+            if def.description.trim().is_empty() {
+                anyhow::bail!("{}: rule '{}' has an empty description", path.display(), id);
+            }
+            // Rules live for the rest of the process, like the built-in table,
+            // so threats can keep borrowing their id and description.
+            let rule: &'static Rule = Box::leak(Box::new(Rule {
+                id: Box::leak(id.into_boxed_str()),
+                pattern: Some(Box::leak(def.pattern.into_boxed_str())),
+                description: Box::leak(def.description.into_boxed_str()),
+                severity: def.severity.unwrap_or(Severity::Medium),
+                category: "custom",
+                rationale: "Custom rule loaded from a rules file.",
+                remediation: "See the rules file for what this rule is meant to catch.",
+            }));
+            set.rules.push(rule);
+        }
+        Ok(set)
+    }
+
+    fn compile(&self) -> Result<Vec<(&'static Rule, Regex)>> {
+        self.rules
+            .iter()
+            .filter_map(|rule| rule.pattern.map(|pattern| (*rule, pattern)))
+            .map(|(rule, pattern)| {
+                let regex = Regex::new(pattern).with_context(|| format!("Rule '{}' has an invalid pattern", rule.id))?;
+                Ok((rule, regex))
+            })
+            .collect()
+    }
+}
+
+/// Outcome of checking a ruleset without scanning anything.
+#[derive(Debug, Default, Serialize)]
+pub struct RuleValidation {
+    /// Rules that compiled.
+    pub loaded: usize,
+    /// Rules that can't be used, with the reason.
+    pub errors: Vec<String>,
+    /// Rules that work but will probably misfire.
+    pub warnings: Vec<String>,
+}
+
+/// Everyday commands a sensible pattern shouldn't match.
+const BENIGN_SAMPLE_LINES: &[&str] = &[
+    "ls -la",
+    "cd /tmp",
+    "echo hello world",
+    "mkdir -p build",
+    "export PATH=$HOME/bin:$PATH",
+    "# install dependencies",
+];
+
+/// Compiles each `(name, pattern)` and flags patterns that are broken or so
+/// broad they would fire on ordinary scripts.
+fn validate_patterns<'a>(patterns: impl Iterator<Item = (String, &'a str)>) -> RuleValidation {
+    let mut validation = RuleValidation::default();
+    let mut seen = HashSet::new();
+
+    for (name, pattern) in patterns {
+        if !seen.insert(name.clone()) {
+            validation.warnings.push(format!("{}: duplicate rule id", name));
+        }
+        let regex = match Regex::new(pattern) {
+            Ok(regex) => regex,
+            Err(e) => {
+                validation.errors.push(format!("{}: pattern does not compile: {}", name, e));
+                continue;
+            }
+        };
+        validation.loaded += 1;
+
+        if regex.is_match("") {
+            validation.warnings.push(format!("{}: pattern matches an empty line, so it fires on everything", name));
+            continue;
+        }
+        let benign_hits: Vec<&str> = BENIGN_SAMPLE_LINES.iter().copied().filter(|line| regex.is_match(line)).collect();
+        if benign_hits.len() >= 2 {
+            validation.warnings.push(format!("{}: pattern is very broad (matches `{}`)", name, benign_hits.join("`, `")));
+        } else if pattern.starts_with(".*") || pattern.ends_with(".*") {
+            validation.warnings.push(format!("{}: leading/trailing `.*` is redundant and widens what gets reported", name));
+        }
+    }
+
+    validation
+}
+
+/// Checks the built-in analyzer rules.
+pub fn validate_builtin_rules() -> RuleValidation {
+    validate_patterns(RULES.iter().filter_map(|rule| rule.pattern.map(|pattern| (rule.id.to_string(), pattern))))
+}
+
+/// Checks a TOML rules file. Fails only if the file can't be read or parsed;
+/// problems with individual rules are listed in the result.
+pub fn validate_rules_file(path: &Path) -> Result<RuleValidation> {
+    let definitions = read_rules_file(path)?;
+    let mut validation = validate_patterns(definitions.iter().enumerate().map(|(i, def)| {
+        let name = def.id.clone().unwrap_or_else(|| format!("rule #{}", i + 1));
+        (name, def.pattern.as_str())
+    }));
+    if definitions.is_empty() {
+        validation.warnings.push("File contains no [[rule]] entries".to_string());
+    }
+    for (i, def) in definitions.iter().enumerate() {
+        if def.description.trim().is_empty() {
+            let name = def.id.clone().unwrap_or_else(|| format!("rule #{}", i + 1));
+            validation.errors.push(format!("{}: description is empty", name));
+        }
+    }
+    Ok(validation)
+}
+
+/// Checks a IOC/signature list, either a `iocs.json`-style export or plain
+/// text with one or more indicators per line.
+fn validate_signature_list(path: &Path) -> Result<RuleValidation> {
+    let content = std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let text = if path.extension().is_some_and(|ext| ext == "json") {
+        let list: IocList = serde_json::from_str(&content).with_context(|| format!("{} is not a valid IOC list", path.display()))?;
+        [&list.sha256, &list.md5, &list.domains, &list.ipv4, &list.ipv6, &list.urls]
+            .into_iter()
+            .flatten()
+            .cloned()
+            .collect::<Vec<_>>()
+            .join("\n")
+    } else {
+        content
+    };
+
+    let summary = IocList::default().import_text(&text);
+    let mut validation = RuleValidation { loaded: summary.total_added(), ..Default::default() };
+    validation.errors.extend(summary.failed_lines.iter().map(|line| format!("not a recognised indicator: {}", line)));
+    if summary.duplicates > 0 {
+        validation.warnings.push(format!("{} duplicate indicator(s)", summary.duplicates));
+    }
+    Ok(validation)
+}
+
+/// Validates a ruleset by type: `.toml` analyzer rules, `.yar`/`.yara`
+/// YARA rules, or anything else as an IOC/signature list.
+pub fn validate_ruleset(path: &Path) -> Result<RuleValidation> {
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("toml") => validate_rules_file(path),
+        #[cfg(feature = "yara")]
+        Some("yar" | "yara") => crate::yara::validate_rules_file(path),
+        #[cfg(not(feature = "yara"))]
+        Some("yar" | "yara") => anyhow::bail!("YARA rules can't be validated: this build has no YARA support"),
+        _ => validate_signature_list(path),
+    }
+}
+
+/// One thing the analyzer flagged.
+#[derive(Debug, Clone, Serialize)]
+pub struct Threat {
+    /// Id of the rule that matched.
+    pub rule: &'static str,
+    /// 1-based line the match is on (for multi-line chains, the last step).
+    pub line: usize,
+    /// 1-based byte column of the match start within the original line, so
+    /// it can be used directly as an offset into the file.
+    pub column: usize,
+    pub matched: String,
+    pub description: String,
+    pub severity: Severity,
+    pub category: &'static str,
+    /// The line a pattern matched on, for highlighting. Only line patterns
+    /// set it; the other checks describe the whole match in `matched`.
+    #[serde(skip)]
+    pub source: Option<SourceLine>,
+}
+
+/// A line of (normalized or decoded) script text and where a match sits in it.
+#[derive(Debug, Clone)]
+pub struct SourceLine {
+    pub text: String,
+    /// Byte range of the match within `text`.
+    pub range: std::ops::Range<usize>,
+    /// The base64 literal `text` was decoded from, if any.
+    pub decoded_from: Option<String>,
+}
+
+impl Threat {
+    fn new(rule: &'static Rule, (line, column): (usize, usize), matched: impl Into<String>, description: impl Into<String>) -> Threat {
+        Threat {
+            rule: rule.id,
+            line,
+            column,
+            matched: matched.into(),
+            description: description.into(),
+            severity: rule.severity,
+            category: rule.category,
+            source: None,
+        }
+    }
+}
+
+impl fmt::Display for Threat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.matched, self.description)
+    }
+}
+
+/// A credential found in the text. Only a masked form of the value is kept,
+/// so results can be printed and saved without leaking it again.
+#[derive(Debug, Clone, Serialize)]
+pub struct SecretFinding {
+    /// Id of the rule that matched.
+    pub rule: &'static str,
+    /// What kind of secret it looks like, e.g. "AWS access key ID".
+    pub kind: &'static str,
+    /// 1-based line number.
+    pub line: usize,
+    pub redacted: String,
+    pub severity: Severity,
+}
+
+impl fmt::Display for SecretFinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {} ({})", self.line, self.kind, self.redacted)
+    }
+}
+
+#[derive(Debug, Serialize)]
 pub struct AnalysisResult {
+    /// False if there are threats or secrets.
     pub is_safe: bool,
-    pub threats: Vec<String>,
+    /// Sum of `Severity::weight` over every threat and secret, for callers
+    /// that fail above a threshold.
+    pub score: u32,
+    pub threats: Vec<Threat>,
+    /// Leaked credentials, reported apart from the dangerous commands.
+    pub secrets: Vec<SecretFinding>,
+}
+
+impl AnalysisResult {
+    /// One line such as `3 threats in 2 categories, 1 possible secret`.
+    pub fn summary(&self) -> String {
+        let categories: HashSet<&str> = self.threats.iter().map(|threat| threat.category).collect();
+        let mut summary = format!(
+            "{} threat{} in {} categor{}",
+            self.threats.len(),
+            if self.threats.len() == 1 { "" } else { "s" },
+            categories.len(),
+            if categories.len() == 1 { "y" } else { "ies" }
+        );
+        if !self.secrets.is_empty() {
+            summary += &format!(", {} possible secret{}", self.secrets.len(), if self.secrets.len() == 1 { "" } else { "s" });
+        }
+        summary
+    }
 }
 
+/// Analyzes a script with the built-in rules. Nothing is printed; see
+/// `render_result` for the terminal view.
 pub fn analyze_content(content: &str) -> Result<AnalysisResult> {
+    analyze_content_with(content, &PatternSet::builtin(), &IocList::load())
+}
+
+/// Analyzes a script with the given line patterns and blocklist.
+pub fn analyze_content_with(original: &str, patterns: &PatternSet, iocs: &IocList) -> Result<AnalysisResult> {
     let mut threats = Vec::new();
 
+    // Flag Unicode tricks, then strip the invisible characters so the
+    // pattern checks below see what the shell would actually run
+    threats.extend(check_unicode_tricks(original));
+    let content = &normalize_content(original);
+
     // Check for dangerous patterns
-    threats.extend(check_dangerous_patterns(content)?);
+    threats.extend(check_dangerous_patterns(content, original, patterns)?);
+
+    // Follow files through download -> chmod +x -> run across lines
+    threats.extend(check_download_exec_chains(content));
 
-    let is_safe = threats.is_empty();
+    // Actions held back until a date, a timer or a long sleep
+    threats.extend(check_time_gated_actions(content));
+
+    // The multi-line checks above are shell-specific, so drop them when
+    // only another platform's rules were asked for
+    threats.retain(|threat| patterns.platform.includes(threat.rule));
+
+    // Check for indicators from the imported blocklist
+    for ioc in iocs.find_network_matches(content) {
+        let position = find_position(original, &ioc.indicator);
+        threats.push(Threat::new(rule("ioc:network"), position, ioc.indicator.clone(), ioc.to_string()));
+    }
+
+    let secrets = check_secrets(content);
+
+    let is_safe = threats.is_empty() && secrets.is_empty();
+    let score = threats.iter().map(|threat| threat.severity.weight()).sum::<u32>()
+        + secrets.iter().map(|secret| secret.severity.weight()).sum::<u32>();
 
     Ok(AnalysisResult {
         is_safe,
+        score,
         threats,
+        secrets,
     })
 }
 
+/// Writes the colored terminal view of a result: each line a pattern
+/// matched, prefixed with `line:col` and with the match highlighted, the
+/// explanation under it, then any secrets.
+pub fn render_result(result: &AnalysisResult, out: &mut impl Write) -> io::Result<()> {
+    for threat in &result.threats {
+        let Some(ref source) = threat.source else {
+            continue;
+        };
+        // Create highlighted line with the match in red
+        let highlighted_line = format!("{}{}{}",
+            &source.text[..source.range.start],
+            source.text[source.range.clone()].red().bold(),
+            &source.text[source.range.end..]
+        );
+
+        // Print the full line with highlighting, prefixed with its position
+        match source.decoded_from {
+            Some(ref literal) => writeln!(out, "{}:{}: {} decodes to: {}", threat.line, threat.column, literal, highlighted_line)?,
+            None => writeln!(out, "{}:{}: {}", threat.line, threat.column, highlighted_line)?,
+        }
+
+        // Print the warning explanation below
+        writeln!(out, "  ⚠️  {}", threat.description.yellow())?;
+        writeln!(out)?;
+    }
+    for secret in &result.secrets {
+        writeln!(out, "🔑 {}", secret.to_string().yellow())?;
+    }
+    Ok(())
+}
+
+/// 1-based line and byte column of the first case-insensitive occurrence of
+/// `needle`, or the start of the text if it can't be found verbatim.
+fn find_position(content: &str, needle: &str) -> (usize, usize) {
+    let needle = needle.to_lowercase();
+    content
+        .lines()
+        .enumerate()
+        .find_map(|(index, line)| {
+            // Lowercasing can change byte lengths, so search ASCII-only
+            // lowercase to keep offsets valid in the original line.
+            line.to_ascii_lowercase().find(&needle).map(|start| (index + 1, start + 1))
+        })
+        .unwrap_or((1, 1))
+}
+
+/// Maps a byte offset in a normalized line back to a 1-based byte column in
+/// the original line, stepping over the invisible characters that
+/// `normalize_content` removed (each of them is several bytes long).
+fn original_column(original_line: &str, normalized_offset: usize) -> usize {
+    let mut kept = 0;
+    for (index, c) in original_line.char_indices() {
+        if zero_width_name(c).is_some() || bidi_control_name(c).is_some() {
+            continue;
+        }
+        if kept >= normalized_offset {
+            return index + 1;
+        }
+        kept += c.len_utf8();
+    }
+    original_line.len() + 1
+}
+
+/// Keeps the first four characters of a long secret (enough to recognise a
+/// key prefix) and masks the rest; short ones are masked entirely.
+fn redact(value: &str) -> String {
+    let len = value.chars().count();
+    let shown: String = value.chars().take(if len >= 16 { 4 } else { 0 }).collect();
+    format!("{}…, {} chars", shown, len)
+}
+
+/// Shannon entropy in bits per character.
+fn char_entropy(text: &str) -> f64 {
+    let mut counts: HashMap<char, usize> = HashMap::new();
+    for c in text.chars() {
+        *counts.entry(c).or_default() += 1;
+    }
+    let len = text.chars().count() as f64;
+    counts
+        .values()
+        .map(|&count| {
+            let p = count as f64 / len;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+/// The `secrets` rules with a pattern, compiled once, in `RULES` order.
+static SECRET_RULES: LazyLock<Vec<(&'static Rule, Regex)>> = LazyLock::new(|| {
+    RULES
+        .iter()
+        .filter(|rule| rule.category == "secrets")
+        .filter_map(|rule| Some((rule, Regex::new(rule.pattern?).expect("built-in secret patterns are valid"))))
+        .collect()
+});
+
+/// Runs the `secrets` rules. Each span of a line is reported once, by the
+/// most specific rule (the order in `RULES`). Only the `value` group is
+/// masked; rules without one match just a marker such as a PEM header.
+fn check_secrets(content: &str) -> Vec<SecretFinding> {
+    let mut secrets = Vec::new();
+    for (line_index, line) in content.lines().enumerate() {
+        let mut claimed: Vec<std::ops::Range<usize>> = Vec::new();
+        for (rule, regex) in SECRET_RULES.iter() {
+            for captures in regex.captures_iter(line) {
+                let whole = captures.get(0).expect("group 0 always matches");
+                let value = captures.name("value");
+                let span = value.map_or(whole.range(), |value| value.range());
+                if claimed.iter().any(|taken| taken.start < span.end && span.start < taken.end) {
+                    continue;
+                }
+                if rule.id == "secret:high-entropy-token" && char_entropy(&line[span.clone()]) < SECRET_MIN_ENTROPY {
+                    continue;
+                }
+                claimed.push(span);
+                secrets.push(SecretFinding {
+                    rule: rule.id,
+                    kind: rule.description,
+                    line: line_index + 1,
+                    redacted: value.map_or_else(|| whole.as_str().to_string(), |value| redact(value.as_str())),
+                    severity: rule.severity,
+                });
+            }
+        }
+    }
+    secrets
+}
 
-fn check_dangerous_patterns(content: &str) -> Result<Vec<String>> {
+
+/// `content` is the normalized text; `original` is only used to report
+/// columns the way an editor would see them.
+fn check_dangerous_patterns(content: &str, original: &str, patterns: &PatternSet) -> Result<Vec<Threat>> {
     let mut threats = Vec::new();
+    let original_lines: Vec<&str> = original.lines().collect();
+    let compiled = patterns.compile()?;
 
     // Look for any potentially risky commands and explain them clearly
-    let patterns = vec![
-        (r"curl\s+[^\s]+", "Downloads files from the internet"),
-        (r"wget\s+[^\s]+", "Downloads files from the internet"),
-        (r"curl\s+.*\|\s*(bash|sh|zsh)", "Downloads and immediately executes code (VERY DANGEROUS)"),
-        (r"wget\s+.*\|\s*(bash|sh|zsh)", "Downloads and immediately executes code (VERY DANGEROUS)"),
-        (r"rm\s+-r[f]?", "Recursively deletes files/folders (can be destructive)"),
-        (r"rm\s+.*\*", "Deletes files using wildcards (can delete more than intended)"),
-        (r"sudo\s+", "Runs commands with administrator privileges"),
-        (r"chmod\s+", "Changes file permissions"),
-        (r"chmod\s+(777|666)", "Makes files readable/writable by everyone (security risk)"),
-        (r"base64\s+-d", "Decodes hidden/obfuscated content"),
-        (r"echo\s+.*\|\s*base64", "Decodes hidden/obfuscated content"),
-        (r"nc\s+-l|netcat\s+-l", "Opens network port (potential backdoor)"),
-        (r"crontab\s+", "Modifies scheduled tasks"),
-        (r"\.ssh/", "Accesses SSH keys (remote access credentials)"),
-        (r"/etc/passwd", "Accesses user account information"),
-        (r"systemctl\s+enable", "Enables system services"),
-    ];
-
-    for (pattern, description) in &patterns {
-        if let Ok(regex) = Regex::new(pattern) {
-            for line in content.lines() {
-                if let Some(mat) = regex.find(line) {
-                    // Create highlighted line with the match in red
-                    let start = mat.start();
-                    let end = mat.end();
-                    let before = &line[..start];
-                    let matched = &line[start..end];
-                    let after = &line[end..];
-
-                    let highlighted_line = format!("{}{}{}",
-                        before,
-                        matched.red().bold(),
-                        after
-                    );
-
-                    // Print the full line with highlighting
-                    println!("{}", highlighted_line);
-
-                    // Print the warning explanation below
-                    println!("  ⚠️  {}", description.yellow());
-                    println!();
-
-                    threats.push(format!("{}: {}", matched, description));
-                }
+    for (rule, regex) in &compiled {
+        for (line_idx, line) in content.lines().enumerate() {
+            if let Some(mat) = regex.find(line) {
+                let line_no = line_idx + 1;
+                let column = original_lines.get(line_idx).map_or(mat.start() + 1, |original_line| original_column(original_line, mat.start()));
+                threats.push(report_match(rule, line, mat.range(), (line_no, column), None));
             }
         }
     }
 
+    // Then look again inside any base64 literals, which is how payloads
+    // like `$(echo Y3VybA== | base64 -d)` hide from the patterns above
+    for (line_idx, line) in content.lines().enumerate() {
+        let position = |start: usize| {
+            let column = original_lines.get(line_idx).map_or(start + 1, |original_line| original_column(original_line, start));
+            (line_idx + 1, column)
+        };
+        threats.extend(check_base64_literals(line, &compiled, &position, 1));
+    }
+
     Ok(threats)
 }
 
- 
\ No newline at end of file
+/// Turns a pattern match into a threat that remembers its line for
+/// `render_result`. `decoded` is the literal the line was decoded from, for
+/// matches found by the base64 pass.
+fn report_match(rule: &'static Rule, line: &str, range: std::ops::Range<usize>, position: (usize, usize), decoded: Option<&str>) -> Threat {
+    let description = match decoded {
+        Some(_) => format!("{} (decoded from base64)", rule.description),
+        None => rule.description.to_string(),
+    };
+    let mut threat = Threat::new(rule, position, &line[range.clone()], description);
+    threat.source = Some(SourceLine {
+        text: line.to_string(),
+        range,
+        decoded_from: decoded.map(str::to_string),
+    });
+    threat
+}
+
+/// How many layers of base64-inside-base64 are unwrapped.
+const MAX_BASE64_DEPTH: usize = 3;
+
+/// Shortest literal worth decoding; `Y3VybA==` ("curl") is eight.
+const MIN_BASE64_LEN: usize = 8;
+
+/// Decodes standard base64, with or without padding.
+pub fn decode_base64(text: &str) -> Option<Vec<u8>> {
+    let digits = text.trim_end_matches('=').as_bytes();
+    if digits.len() % 4 == 1 {
+        return None;
+    }
+    let mut bytes = Vec::with_capacity(digits.len() * 3 / 4);
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for &digit in digits {
+        let value = match digit {
+            b'A'..=b'Z' => digit - b'A',
+            b'a'..=b'z' => digit - b'a' + 26,
+            b'0'..=b'9' => digit - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => return None,
+        };
+        buffer = (buffer << 6) | u32::from(value);
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+        }
+    }
+    Some(bytes)
+}
+
+/// A run of base64 characters that isn't part of a longer word.
+static BASE64_LITERAL: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?:^|[^A-Za-z0-9+/=])([A-Za-z0-9+/]+={0,2})").expect("valid pattern"));
+
+/// Decodes each standalone base64 literal on `line` and re-runs the
+/// patterns with the decoded text put back in its place, so fragments such
+/// as a hidden `curl` still match the rest of the command. Only rules the
+/// undecoded line didn't already match, and only matches that touch the
+/// decoded text, are reported; everything is attributed to
+/// the literal's position. Literals that don't decode to readable UTF-8
+/// (paths, words, hashes) are ignored.
+fn check_base64_literals(line: &str, compiled: &[(&'static Rule, Regex)], position: &dyn Fn(usize) -> (usize, usize), depth: usize) -> Vec<Threat> {
+    let mut threats = Vec::new();
+    if depth > MAX_BASE64_DEPTH {
+        return threats;
+    }
+    for caps in BASE64_LITERAL.captures_iter(line) {
+        let literal = caps.get(1).expect("group 1 always matches");
+        if literal.len() < MIN_BASE64_LEN {
+            continue;
+        }
+        let Some(decoded) = decode_base64(literal.as_str())
+            .and_then(|bytes| String::from_utf8(bytes).ok())
+            .filter(|text| !text.trim().is_empty() && text.chars().all(|c| !c.is_control() || c.is_whitespace()))
+        else {
+            continue;
+        };
+        let literal_position = position(literal.start());
+
+        let expanded = format!("{}{}{}", &line[..literal.start()], decoded, &line[literal.end()..]);
+        let decoded_span = literal.start()..literal.start() + decoded.len();
+        let mut offset = 0;
+        for expanded_line in expanded.split('\n') {
+            for (rule, regex) in compiled.iter().filter(|(_, regex)| !regex.is_match(line)) {
+                let hit = regex
+                    .find_iter(expanded_line)
+                    .find(|mat| offset + mat.start() < decoded_span.end && decoded_span.start < offset + mat.end());
+                if let Some(mat) = hit {
+                    threats.push(report_match(rule, expanded_line, mat.range(), literal_position, Some(literal.as_str())));
+                }
+            }
+            offset += expanded_line.len() + 1;
+        }
+
+        // Payloads are often encoded more than once
+        for decoded_line in decoded.lines() {
+            threats.extend(check_base64_literals(decoded_line, compiled, &|_| literal_position, depth + 1));
+        }
+    }
+
+    threats
+}
+
+/// Where a tracked file was last seen in the download -> chmod -> run chain.
+struct TrackedFile {
+    name: String,
+    created_line: usize,
+    chmod_line: Option<usize>,
+}
+
+/// Splits a shell line into its commands at `;`, `&&`, `||` and `|`, and
+/// each command into whitespace-separated words with quotes removed.
+fn shell_commands(line: &str) -> Vec<Vec<String>> {
+    line.split([';', '|', '&'])
+        .map(|command| {
+            command
+                .split_whitespace()
+                .map(|word| word.trim_matches(|c| c == '"' || c == '\'').to_string())
+                .filter(|word| !word.is_empty())
+                .collect::<Vec<_>>()
+        })
+        .filter(|words| !words.is_empty())
+        .collect()
+}
+
+/// The last path component, which is how a file is usually referred to again
+/// after a `cd` (`./payload`).
+fn base_name(path: &str) -> &str {
+    path.trim_end_matches('/').rsplit('/').next().unwrap_or(path)
+}
+
+/// The file name a download tool would pick from a URL (`curl -O`, plain `wget`).
+fn url_file_name(url: &str) -> Option<&str> {
+    let path = url.split(['?', '#']).next()?;
+    let after_scheme = path.split_once("://").map_or(path, |(_, rest)| rest);
+    let (_, file) = after_scheme.split_once('/')?;
+    let name = base_name(file);
+    (!name.is_empty()).then_some(name)
+}
+
+/// Files written by a download or decode command (`curl -o x`, `wget url`,
+/// `base64 -d > x`).
+fn created_files(words: &[String]) -> Vec<String> {
+    let mut files = Vec::new();
+    let Some(tool) = words.first().map(|w| base_name(w)) else {
+        return files;
+    };
+    let url = words.iter().find(|w| w.contains("://"));
+
+    // Shell redirection of the command's output to a file.
+    let writes_via_redirect = matches!(tool, "curl" | "wget" | "base64" | "xxd" | "openssl" | "echo" | "printf");
+    for (idx, word) in words.iter().enumerate() {
+        if let Some(target) = word.strip_prefix(">>").or_else(|| word.strip_prefix('>')) {
+            if !writes_via_redirect {
+                break;
+            }
+            match target {
+                "" => files.extend(words.get(idx + 1).cloned()),
+                target => files.push(target.to_string()),
+            }
+        }
+    }
+
+    match tool {
+        "curl" => {
+            for (idx, word) in words.iter().enumerate() {
+                match word.as_str() {
+                    "-o" | "--output" => files.extend(words.get(idx + 1).cloned()),
+                    "-O" | "--remote-name" => files.extend(url.and_then(|u| url_file_name(u)).map(str::to_string)),
+                    w if w.starts_with("--output=") => files.push(w["--output=".len()..].to_string()),
+                    _ => {}
+                }
+            }
+        }
+        "wget" => {
+            let mut explicit = None;
+            for (idx, word) in words.iter().enumerate() {
+                match word.as_str() {
+                    "-O" => explicit = words.get(idx + 1).cloned(),
+                    w if w.starts_with("--output-document=") => explicit = Some(w["--output-document=".len()..].to_string()),
+                    w if w.starts_with("-O") && w.len() > 2 && !w.starts_with("-O-") => explicit = Some(w[2..].to_string()),
+                    w if w.starts_with("-qO") => explicit = Some(w[3..].to_string()),
+                    _ => {}
+                }
+            }
+            match explicit {
+                Some(file) => files.push(file),
+                None => files.extend(url.and_then(|u| url_file_name(u)).map(str::to_string)),
+            }
+        }
+        _ => {}
+    }
+
+    files.retain(|file| file != "-" && !file.is_empty() && file != "/dev/null");
+    files
+}
+
+/// Files made executable by `chmod +x`, `chmod u+x`, `chmod 755` and friends.
+fn chmodded_files(words: &[String]) -> Vec<String> {
+    let Some(pos) = words.iter().position(|w| w == "chmod") else {
+        return Vec::new();
+    };
+    let args: Vec<&String> = words[pos + 1..].iter().filter(|w| !w.starts_with('-')).collect();
+    let Some((mode, files)) = args.split_first() else {
+        return Vec::new();
+    };
+
+    let symbolic_exec = mode.contains('+') && mode.contains('x');
+    let numeric_exec = mode.len() >= 3
+        && mode.chars().all(|c| c.is_ascii_digit())
+        && mode.chars().rev().take(3).any(|c| c.to_digit(8).is_some_and(|d| d & 1 == 1));
+    if symbolic_exec || numeric_exec {
+        files.iter().map(|f| f.to_string()).collect()
+    } else {
+        Vec::new()
+    }
+}
+
+/// The file a command runs, either directly (`./x`, `/tmp/x`) or through an
+/// interpreter (`bash x`, `source x`).
+fn executed_file(words: &[String]) -> Option<&str> {
+    let mut words = words
+        .iter()
+        .map(String::as_str)
+        .skip_while(|w| matches!(*w, "sudo" | "nohup" | "exec" | "env"));
+    let first = words.next()?;
+    if matches!(first, "bash" | "sh" | "zsh" | "source" | "." | "python" | "python3" | "perl") {
+        words.find(|w| !w.starts_with('-'))
+    } else if first.starts_with("./") || first.starts_with('/') || first.starts_with('~') || first.starts_with('$') {
+        Some(first)
+    } else {
+        None
+    }
+}
+
+/// Tracks files that a script downloads (or decodes) on one line, makes
+/// executable on another and runs on a third. Each step is innocent on its
+/// own, so the single-line patterns only ever see the pieces.
+fn check_download_exec_chains(content: &str) -> Vec<Threat> {
+    let mut tracked: Vec<TrackedFile> = Vec::new();
+    let mut threats = Vec::new();
+
+    for (line_idx, line) in content.lines().enumerate() {
+        let line_no = line_idx + 1;
+        for words in shell_commands(line) {
+            for file in created_files(&words) {
+                let name = base_name(&file).to_string();
+                tracked.retain(|t| t.name != name);
+                tracked.push(TrackedFile { name, created_line: line_no, chmod_line: None });
+            }
+
+            for file in chmodded_files(&words) {
+                if let Some(t) = tracked.iter_mut().find(|t| t.name == base_name(&file)) {
+                    t.chmod_line = Some(line_no);
+                }
+            }
+
+            if let Some(file) = executed_file(&words) {
+                let name = base_name(file);
+                if let Some(pos) = tracked.iter().position(|t| t.name == name) {
+                    let t = tracked.remove(pos);
+                    let (matched, description) = match t.chmod_line {
+                        Some(chmod_line) => (
+                            format!("{}: download (line {}) -> chmod +x (line {}) -> run (line {})", t.name, t.created_line, chmod_line, line_no),
+                            "Downloads a file, makes it executable and runs it (multi-step dropper, VERY DANGEROUS)",
+                        ),
+                        None => (
+                            format!("{}: download (line {}) -> run (line {})", t.name, t.created_line, line_no),
+                            "Downloads a file and runs it (multi-step dropper, VERY DANGEROUS)",
+                        ),
+                    };
+                    threats.push(Threat::new(rule("chain:download-exec"), (line_no, 1), matched, description));
+                }
+            }
+        }
+    }
+
+    threats
+}
+
+/// Commands a time bomb would hold back: destroying data, stopping the
+/// machine, or reaching out over the network.
+static GATED_ACTION: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\b(rm\s+-[a-zA-Z]*[rf]|dd\s+\S*of=|mkfs(\.\w+)?\s|shred\s|wipefs\s|truncate\s|shutdown\b|reboot\b|halt\b|poweroff\b|kill\s+-9|pkill\s|curl\s|wget\s|nc\s|netcat\s|ncat\s|ssh\s|scp\s|rsync\s)").expect("valid pattern")
+});
+
+/// A condition, as in `if [ $(date +%s) -gt ... ]`, that tests the clock.
+static DATE_CHECK: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(\bif\b|\belif\b|\bwhile\b|\buntil\b|\[\[?|\btest\b).*(\$\(\s*date\b|`\s*date\b|\$EPOCHSECONDS|\$SECONDS)").expect("valid pattern")
+});
+
+/// A command handed to `at`, `batch` or a `systemd-run` timer.
+static SCHEDULER: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(^|[|;&]\s*|\s)(at\s+(-\w+\s+)*(now\s*\+|midnight|noon|teatime|tomorrow|\d{1,2}(:\d{2})?\s*(am|pm)?\b)|batch\s*$|systemd-run\s.*--on-(calendar|active|boot)\b)")
+        .expect("valid pattern")
+});
+
+static SLEEP: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\bsleep\s+([0-9.]+[smhd]?)\b").expect("valid pattern"));
+
+/// Shell keywords that open and close a block, for following the body of
+/// a date check.
+static BLOCK_OPENER: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^\s*(if|while|until|case)\b").expect("valid pattern"));
+static BLOCK_CLOSER: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^\s*(fi|done|esac)\b").expect("valid pattern"));
+
+/// Lines searched after a conditional or sleep for the action it gates.
+const GATED_ACTION_WINDOW: usize = 20;
+
+/// Shortest sleep that counts as deliberately delaying the next step.
+const SUSPICIOUS_SLEEP_SECS: u64 = 60;
+
+/// Parses `sleep` durations such as `600`, `10m` or `1.5h` into seconds.
+fn sleep_seconds(arg: &str) -> Option<u64> {
+    let (number, unit) = match arg.char_indices().find(|(_, c)| !c.is_ascii_digit() && *c != '.') {
+        Some((i, _)) => arg.split_at(i),
+        None => (arg, "s"),
+    };
+    let value: f64 = number.parse().ok()?;
+    let scale = match unit {
+        "s" => 1.0,
+        "m" => 60.0,
+        "h" => 3600.0,
+        "d" => 86400.0,
+        _ => return None,
+    };
+    Some((value * scale) as u64)
+}
+
+/// Finds the first gated action from `start` (the rest of the trigger line,
+/// then the following lines) until the block opened on the trigger line
+/// closes or the window runs out.
+fn find_gated_action<'a>(lines: &[&'a str], index: usize, start: usize, track_block: bool) -> Option<(usize, &'a str)> {
+    if let Some(mat) = GATED_ACTION.find(&lines[index][start..]) {
+        return Some((index, lines[index][start + mat.start()..].trim()));
+    }
+    let mut depth = 1;
+    for (offset, line) in lines.iter().enumerate().skip(index + 1).take(GATED_ACTION_WINDOW) {
+        if track_block {
+            if BLOCK_CLOSER.is_match(line) {
+                depth -= 1;
+                if depth == 0 {
+                    return None;
+                }
+            } else if BLOCK_OPENER.is_match(line) {
+                depth += 1;
+            }
+        }
+        if let Some(mat) = GATED_ACTION.find(line) {
+            return Some((offset, line[mat.start()..].trim()));
+        }
+    }
+    None
+}
+
+/// Flags actions held back until a date/time, a scheduler or a long sleep:
+/// `if [ $(date +%s) -gt ... ]; then rm -rf ...`, `echo ... | at midnight`,
+/// `sleep 3600; curl ...`.
+fn check_time_gated_actions(content: &str) -> Vec<Threat> {
+    let mut threats = Vec::new();
+    let lines: Vec<&str> = content.lines().collect();
+    for (index, line) in lines.iter().enumerate() {
+        let line_no = index + 1;
+
+        if let Some(condition) = DATE_CHECK.find(line) {
+            if let Some((action_index, gated)) = find_gated_action(&lines, index, condition.end(), true) {
+                threats.push(Threat::new(
+                    rule("timebomb:date-gated"),
+                    (line_no, condition.start() + 1),
+                    format!("`{}` (line {}) gates `{}` (line {})", line.trim(), line_no, gated, action_index + 1),
+                    rule("timebomb:date-gated").description,
+                ));
+            }
+        }
+
+        if let Some(scheduled) = SCHEDULER.find(line) {
+            threats.push(Threat::new(
+                rule("timebomb:scheduled"),
+                (line_no, scheduled.start() + 1),
+                format!("`{}` (line {})", line.trim(), line_no),
+                format!("{} ({})", rule("timebomb:scheduled").description, scheduled.as_str().trim_start_matches(['|', ';', '&']).trim()),
+            ));
+        }
+
+        for caps in SLEEP.captures_iter(line) {
+            let is_long = sleep_seconds(&caps[1]).is_some_and(|secs| secs >= SUSPICIOUS_SLEEP_SECS);
+            let Some(whole) = caps.get(0).filter(|_| is_long) else {
+                continue;
+            };
+            if let Some((action_index, gated)) = find_gated_action(&lines, index, whole.end(), false) {
+                threats.push(Threat::new(
+                    rule("timebomb:delayed-action"),
+                    (line_no, whole.start() + 1),
+                    format!("`{}` (line {}) delays `{}` (line {})", whole.as_str(), line_no, gated, action_index + 1),
+                    rule("timebomb:delayed-action").description,
+                ));
+                break;
+            }
+        }
+    }
+
+    threats
+}
+
+/// Invisible characters that can split a keyword (`cu\u{200B}rl`) without
+/// changing how it looks.
+pub fn zero_width_name(c: char) -> Option<&'static str> {
+    match c {
+        '\u{00AD}' => Some("SOFT HYPHEN"),
+        '\u{200B}' => Some("ZERO WIDTH SPACE"),
+        '\u{200C}' => Some("ZERO WIDTH NON-JOINER"),
+        '\u{200D}' => Some("ZERO WIDTH JOINER"),
+        '\u{2060}' => Some("WORD JOINER"),
+        '\u{FEFF}' => Some("ZERO WIDTH NO-BREAK SPACE"),
+        _ => None,
+    }
+}
+
+/// Bidirectional control characters that reorder how text is displayed.
+pub fn bidi_control_name(c: char) -> Option<&'static str> {
+    match c {
+        '\u{061C}' => Some("ARABIC LETTER MARK"),
+        '\u{200E}' => Some("LEFT-TO-RIGHT MARK"),
+        '\u{200F}' => Some("RIGHT-TO-LEFT MARK"),
+        '\u{202A}' => Some("LEFT-TO-RIGHT EMBEDDING"),
+        '\u{202B}' => Some("RIGHT-TO-LEFT EMBEDDING"),
+        '\u{202C}' => Some("POP DIRECTIONAL FORMATTING"),
+        '\u{202D}' => Some("LEFT-TO-RIGHT OVERRIDE"),
+        '\u{202E}' => Some("RIGHT-TO-LEFT OVERRIDE"),
+        '\u{2066}' => Some("LEFT-TO-RIGHT ISOLATE"),
+        '\u{2067}' => Some("RIGHT-TO-LEFT ISOLATE"),
+        '\u{2068}' => Some("FIRST STRONG ISOLATE"),
+        '\u{2069}' => Some("POP DIRECTIONAL ISOLATE"),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Script {
+    Latin,
+    Cyrillic,
+    Greek,
+}
+
+impl Script {
+    fn of(c: char) -> Option<Script> {
+        match c {
+            'a'..='z' | 'A'..='Z' | '\u{00C0}'..='\u{024F}' => Some(Script::Latin),
+            '\u{0400}'..='\u{052F}' => Some(Script::Cyrillic),
+            '\u{0370}'..='\u{03FF}' => Some(Script::Greek),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Script::Latin => "Latin",
+            Script::Cyrillic => "Cyrillic",
+            Script::Greek => "Greek",
+        }
+    }
+}
+
+/// Removes zero-width and bidi control characters so the literal patterns
+/// match what a shell would execute.
+fn normalize_content(content: &str) -> String {
+    content
+        .chars()
+        .filter(|&c| zero_width_name(c).is_none() && bidi_control_name(c).is_none())
+        .collect()
+}
+
+/// Flags bidi controls, zero-width characters and words/URLs that mix
+/// look-alike alphabets (a Cyrillic 'а' inside `pаypal.com`).
+fn check_unicode_tricks(content: &str) -> Vec<Threat> {
+    let mut threats = Vec::new();
+
+    for (line_idx, line) in content.lines().enumerate() {
+        let line_no = line_idx + 1;
+
+        let mut seen = Vec::new();
+        for (index, c) in line.char_indices() {
+            if let Some(name) = bidi_control_name(c) {
+                if !seen.contains(&c) {
+                    seen.push(c);
+                    let bidi = rule("unicode:bidi-control");
+                    threats.push(Threat::new(
+                        bidi,
+                        (line_no, index + 1),
+                        format!("U+{:04X} {} (line {})", c as u32, name, line_no),
+                        bidi.description,
+                    ));
+                }
+            }
+        }
+
+        for token in line.split(|c: char| c.is_whitespace() || matches!(c, '"' | '\'' | '`' | '(' | ')' | '|' | ';' | '&' | '<' | '>')) {
+            // `split` yields subslices, so the pointer difference is the byte offset
+            let column = token.as_ptr() as usize - line.as_ptr() as usize + 1;
+            if let Some(name) = token.chars().find_map(zero_width_name) {
+                let visible: String = token
+                    .chars()
+                    .map(|c| match zero_width_name(c) {
+                        Some(_) => format!("<U+{:04X}>", c as u32),
+                        None => c.to_string(),
+                    })
+                    .collect();
+                let zero_width = rule("unicode:zero-width");
+                threats.push(Threat::new(
+                    zero_width,
+                    (line_no, column),
+                    format!("{} contains {} (line {})", visible, name, line_no),
+                    zero_width.description,
+                ));
+            }
+
+            let mut scripts: Vec<Script> = Vec::new();
+            for script in token.chars().filter_map(Script::of) {
+                if !scripts.contains(&script) {
+                    scripts.push(script);
+                }
+            }
+            if scripts.len() > 1 {
+                let names: Vec<&str> = scripts.iter().map(|s| s.name()).collect();
+                let mixed = rule("unicode:mixed-script");
+                threats.push(Threat::new(
+                    mixed,
+                    (line_no, column),
+                    format!("{} mixes {} letters (line {})", normalize_content(token), names.join(" + "), line_no),
+                    mixed.description,
+                ));
+            }
+        }
+    }
+
+    threats
+}
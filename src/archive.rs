@@ -0,0 +1,210 @@
+// src/archive.rs
+
+use anyhow::{bail, Context, Result};
+use flate2::read::GzDecoder;
+use serde::Serialize;
+use std::io::{BufRead, BufReader, Cursor, Read};
+
+use crate::filename::NameSource;
+use crate::pdf_scanner::ScanContext;
+use crate::scanner::{scan_bytes, ScanOutcome};
+
+/// Zip keeps its directory at the end, so the whole archive has to be buffered.
+const MAX_ZIP_BYTES: u64 = 512 * 1024 * 1024;
+
+/// Entries bigger than this are reported instead of scanned.
+const MAX_ENTRY_BYTES: u64 = 64 * 1024 * 1024;
+
+/// Stop after this many entries.
+const MAX_ENTRIES: usize = 10_000;
+
+/// Stop once this much has been unpacked in total (guards against zip bombs).
+const MAX_TOTAL_BYTES: u64 = 1024 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum ArchiveFormat {
+    Tar,
+    TarGz,
+    Zip,
+}
+
+/// The outcome for one archive member: either a scan result or the reason it
+/// could not be scanned.
+#[derive(Debug, Clone, Serialize)]
+pub struct ArchiveEntryResult {
+    pub path: String,
+    pub size: u64,
+    pub outcome: Option<ScanOutcome>,
+    pub error: Option<String>,
+}
+
+impl ArchiveEntryResult {
+    fn scanned(path: String, content: &[u8], context: &ScanContext) -> Self {
+        Self {
+            outcome: Some(scan_bytes(&path, NameSource::Untrusted, content, context)),
+            size: content.len() as u64,
+            path,
+            error: None,
+        }
+    }
+
+    fn failed(path: String, size: u64, error: String) -> Self {
+        Self { path, size, outcome: None, error: Some(error) }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ArchiveScanReport {
+    pub format: ArchiveFormat,
+    pub entries: Vec<ArchiveEntryResult>,
+    /// Why scanning stopped before the end of the archive, if it did.
+    pub stopped_early: Option<String>,
+}
+
+impl ArchiveScanReport {
+    pub fn is_suspicious(&self) -> bool {
+        self.entries
+            .iter()
+            .any(|entry| entry.outcome.as_ref().is_some_and(|o| o.is_suspicious))
+    }
+}
+
+/// Tracks the entry and byte budgets shared by all formats.
+struct Budget {
+    entries: usize,
+    bytes: u64,
+}
+
+impl Budget {
+    /// Returns why the scan must stop before taking on another entry of `size` bytes.
+    fn exceeded_by(&mut self, size: u64) -> Option<String> {
+        if self.entries >= MAX_ENTRIES {
+            return Some(format!("Entry limit of {} reached", MAX_ENTRIES));
+        }
+        if self.bytes + size.min(MAX_ENTRY_BYTES) > MAX_TOTAL_BYTES {
+            return Some(format!("Total unpacked size limit of {} bytes reached", MAX_TOTAL_BYTES));
+        }
+        self.entries += 1;
+        self.bytes += size.min(MAX_ENTRY_BYTES);
+        None
+    }
+}
+
+/// Reads at most `MAX_ENTRY_BYTES` from an entry, failing if it holds more.
+fn read_entry(reader: impl Read) -> std::io::Result<Vec<u8>> {
+    let mut content = Vec::new();
+    reader.take(MAX_ENTRY_BYTES + 1).read_to_end(&mut content)?;
+    if content.len() as u64 > MAX_ENTRY_BYTES {
+        return Err(std::io::Error::other(format!("larger than the {} byte limit", MAX_ENTRY_BYTES)));
+    }
+    Ok(content)
+}
+
+/// Unpacks a tar, tar.gz or zip archive from a stream entirely in memory and
+/// scans every regular file in it. Nothing is written to disk.
+pub fn scan_archive_stream(reader: impl Read) -> Result<ArchiveScanReport> {
+    let context = &ScanContext::load();
+    let mut reader = BufReader::new(reader);
+    let magic = reader.fill_buf().context("Failed to read the archive")?.to_vec();
+
+    if magic.starts_with(b"PK\x03\x04") || magic.starts_with(b"PK\x05\x06") {
+        scan_zip(reader, context)
+    } else if magic.starts_with(&[0x1f, 0x8b]) {
+        scan_tar(GzDecoder::new(reader), ArchiveFormat::TarGz, context)
+    } else if magic.is_empty() {
+        bail!("No archive data on input")
+    } else {
+        scan_tar(reader, ArchiveFormat::Tar, context)
+    }
+}
+
+fn scan_tar(reader: impl Read, format: ArchiveFormat, context: &ScanContext) -> Result<ArchiveScanReport> {
+    let mut archive = tar::Archive::new(reader);
+    let mut report = ArchiveScanReport { format, entries: Vec::new(), stopped_early: None };
+    let mut budget = Budget { entries: 0, bytes: 0 };
+
+    let entries = archive.entries().context("Input is not a tar, tar.gz or zip archive")?;
+    for entry in entries {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                // A corrupt header means the rest of the stream can't be located.
+                report.stopped_early = Some(format!("Archive is corrupt or truncated: {}", e));
+                break;
+            }
+        };
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+
+        let path = entry.path().map_or_else(|_| "<invalid path>".to_string(), |p| p.display().to_string());
+        let size = entry.header().size().unwrap_or(0);
+        if let Some(reason) = budget.exceeded_by(size) {
+            report.stopped_early = Some(reason);
+            break;
+        }
+        if size > MAX_ENTRY_BYTES {
+            report.entries.push(ArchiveEntryResult::failed(path, size, format!("Not scanned: larger than the {} byte limit", MAX_ENTRY_BYTES)));
+            continue;
+        }
+
+        match read_entry(entry) {
+            Ok(content) => report.entries.push(ArchiveEntryResult::scanned(path, &content, context)),
+            Err(e) => {
+                report.entries.push(ArchiveEntryResult::failed(path, size, format!("Could not read entry: {}", e)));
+                report.stopped_early = Some(format!("Archive is corrupt or truncated: {}", e));
+                break;
+            }
+        }
+    }
+
+    if format == ArchiveFormat::Tar && report.entries.is_empty() && report.stopped_early.is_some() {
+        bail!("Input is not a tar, tar.gz or zip archive");
+    }
+    Ok(report)
+}
+
+fn scan_zip(reader: impl Read, context: &ScanContext) -> Result<ArchiveScanReport> {
+    let mut data = Vec::new();
+    reader.take(MAX_ZIP_BYTES + 1).read_to_end(&mut data).context("Failed to read the archive")?;
+    if data.len() as u64 > MAX_ZIP_BYTES {
+        bail!("Zip archives larger than {} bytes can't be scanned from a stream", MAX_ZIP_BYTES);
+    }
+
+    let mut archive = zip::ZipArchive::new(Cursor::new(data)).context("Zip archive is corrupt")?;
+    let mut report = ArchiveScanReport { format: ArchiveFormat::Zip, entries: Vec::new(), stopped_early: None };
+    let mut budget = Budget { entries: 0, bytes: 0 };
+
+    for index in 0..archive.len() {
+        let path = archive.name_for_index(index).unwrap_or("<unnamed entry>").to_string();
+        let file = match archive.by_index(index) {
+            Ok(file) => file,
+            Err(e) => {
+                report.entries.push(ArchiveEntryResult::failed(path, 0, format!("Could not decode entry: {}", e)));
+                continue;
+            }
+        };
+        if !file.is_file() {
+            continue;
+        }
+
+        let size = file.size();
+        if let Some(reason) = budget.exceeded_by(size) {
+            report.stopped_early = Some(reason);
+            break;
+        }
+        if size > MAX_ENTRY_BYTES {
+            report.entries.push(ArchiveEntryResult::failed(path, size, format!("Not scanned: larger than the {} byte limit", MAX_ENTRY_BYTES)));
+            continue;
+        }
+
+        // The declared size can lie, so the read is capped as well.
+        let entry = match read_entry(file) {
+            Ok(content) => ArchiveEntryResult::scanned(path, &content, context),
+            Err(e) => ArchiveEntryResult::failed(path, size, format!("Could not decode entry: {}", e)),
+        };
+        report.entries.push(entry);
+    }
+
+    Ok(report)
+}
@@ -0,0 +1,54 @@
+// src/audit.rs
+
+use serde::Serialize;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::config::data_path;
+
+/// One JSON object per line, appended and never rewritten. Full logs are
+/// compressed and set aside by `retention::enforce`.
+pub const AUDIT_LOG_FILE: &str = "audit.log";
+
+/// Where the audit log is written, in the per-user data directory.
+pub fn audit_log_path() -> PathBuf {
+    data_path(AUDIT_LOG_FILE)
+}
+
+/// Something BigMan did on the user's behalf.
+#[derive(Debug, Serialize)]
+pub struct AuditEvent<'a> {
+    pub timestamp: u64,
+    /// Which part of BigMan acted (e.g. `native-messaging`).
+    pub source: &'a str,
+    /// What it did (e.g. `flagged`, `quarantined`).
+    pub action: &'a str,
+    pub path: &'a str,
+    pub detail: &'a str,
+}
+
+/// Appends an event to the audit log.
+pub fn record(source: &str, action: &str, path: &str, detail: &str) -> io::Result<()> {
+    let event = AuditEvent {
+        timestamp: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs(),
+        source,
+        action,
+        path,
+        detail,
+    };
+    let mut line = serde_json::to_string(&event).map_err(io::Error::other)?;
+    line.push('\n');
+
+    let path = audit_log_path();
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    // A single write keeps concurrent appenders from interleaving lines.
+    file.write_all(line.as_bytes())
+}
@@ -0,0 +1,427 @@
+// src/clamav.rs
+
+use std::collections::VecDeque;
+
+use crate::usb_watch::mounted_filesystems;
+
+/// Kernel and runtime pseudo-filesystems that a full system scan always skips.
+pub const PSEUDO_FS_DIRS: &[&str] = &["/proc", "/sys", "/dev", "/run"];
+
+/// Filesystem types with no real files on them.
+const VIRTUAL_FS_TYPES: &[&str] = &[
+    "proc", "sysfs", "devtmpfs", "devpts", "cgroup", "cgroup2", "securityfs", "debugfs",
+    "tracefs", "configfs", "pstore", "bpf", "mqueue", "hugetlbfs", "fusectl", "binfmt_misc",
+    "autofs", "efivarfs", "selinuxfs", "rpc_pipefs", "nsfs",
+];
+
+/// Network filesystems: slow to walk, and the files belong to another machine.
+const NETWORK_FS_TYPES: &[&str] = &[
+    "nfs", "nfs4", "cifs", "smb3", "smbfs", "ncpfs", "9p", "ceph", "glusterfs", "davfs",
+    "afs", "fuse.sshfs", "fuse.rclone", "fuse.glusterfs", "fuse.davfs2",
+];
+
+/// Broad kind of threat, taken from the type part of a ClamAV signature name
+/// such as `Win.Trojan.Agent-1234` or `Pdf.Exploit.CVE_2018_4990-1`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SignatureCategory {
+    Ransomware,
+    Exploit,
+    Backdoor,
+    Trojan,
+    Worm,
+    Virus,
+    Downloader,
+    Coinminer,
+    Malware,
+    Phishing,
+    /// Potentially unwanted: adware, hacking tools, bundlers.
+    Pua,
+    /// Heuristic detections such as encrypted archives or spoofed links.
+    Heuristic,
+    /// Test signatures such as EICAR.
+    Test,
+    Other,
+}
+
+impl SignatureCategory {
+    fn from_type(kind: &str) -> SignatureCategory {
+        match kind.to_ascii_lowercase().as_str() {
+            "ransomware" => SignatureCategory::Ransomware,
+            "exploit" => SignatureCategory::Exploit,
+            "backdoor" | "rat" => SignatureCategory::Backdoor,
+            "trojan" => SignatureCategory::Trojan,
+            "worm" => SignatureCategory::Worm,
+            "virus" | "infector" => SignatureCategory::Virus,
+            "downloader" | "dropper" | "loader" => SignatureCategory::Downloader,
+            "coinminer" | "miner" => SignatureCategory::Coinminer,
+            "malware" | "packed" | "keylogger" | "spyware" | "rootkit" => SignatureCategory::Malware,
+            "phishing" => SignatureCategory::Phishing,
+            "adware" | "tool" | "packer" => SignatureCategory::Pua,
+            "test" => SignatureCategory::Test,
+            _ => SignatureCategory::Other,
+        }
+    }
+
+    /// Reads the category out of a signature name. Names are
+    /// `Platform.Type.Name-id`, except that `PUA.` and `Heuristics.` lead,
+    /// and third-party databases add a `.UNOFFICIAL` suffix.
+    pub fn of_signature(signature: &str) -> SignatureCategory {
+        let mut parts = signature.split('.');
+        let first = parts.next().unwrap_or_default();
+        match first.to_ascii_lowercase().as_str() {
+            "pua" => SignatureCategory::Pua,
+            "heuristics" | "broken" => SignatureCategory::Heuristic,
+            _ if signature.to_ascii_lowercase().contains("eicar") => SignatureCategory::Test,
+            _ => parts.next().map_or(SignatureCategory::Other, SignatureCategory::from_type),
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SignatureCategory::Ransomware => "Ransomware",
+            SignatureCategory::Exploit => "Exploit",
+            SignatureCategory::Backdoor => "Backdoor",
+            SignatureCategory::Trojan => "Trojan",
+            SignatureCategory::Worm => "Worm",
+            SignatureCategory::Virus => "Virus",
+            SignatureCategory::Downloader => "Downloader",
+            SignatureCategory::Coinminer => "Coin miner",
+            SignatureCategory::Malware => "Malware",
+            SignatureCategory::Phishing => "Phishing",
+            SignatureCategory::Pua => "Potentially unwanted",
+            SignatureCategory::Heuristic => "Heuristic",
+            SignatureCategory::Test => "Test signature",
+            SignatureCategory::Other => "Other",
+        }
+    }
+}
+
+/// One `<path>: <signature> FOUND` line from clamscan.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Detection {
+    pub path: String,
+    pub signature: String,
+    pub category: SignatureCategory,
+    /// Where `clamscan --move` put the file.
+    pub moved_to: Option<String>,
+}
+
+/// Everything useful in a clamscan run's output: each detection and the
+/// closing summary counts.
+#[derive(Debug, Clone, Default)]
+pub struct ClamScanReport {
+    pub summary: ClamScanSummary,
+    pub detections: Vec<Detection>,
+}
+
+impl ClamScanReport {
+    pub fn parse(output: &str) -> ClamScanReport {
+        let mut detections: Vec<Detection> = output
+            .lines()
+            .filter_map(|line| {
+                // The path may itself contain ": ", so split at the last one.
+                let (path, signature) = line.strip_suffix(" FOUND")?.rsplit_once(": ")?;
+                Some(Detection {
+                    path: path.to_string(),
+                    signature: signature.to_string(),
+                    category: SignatureCategory::of_signature(signature),
+                    moved_to: None,
+                })
+            })
+            .collect();
+        for line in output.lines() {
+            let Some((path, target)) = line.rsplit_once(": moved to '") else {
+                continue;
+            };
+            let target = target.trim_end().trim_end_matches('\'');
+            if let Some(detection) = detections.iter_mut().find(|d| d.path == path && d.moved_to.is_none()) {
+                detection.moved_to = Some(target.to_string());
+            }
+        }
+        ClamScanReport {
+            summary: ClamScanSummary::parse(output),
+            detections,
+        }
+    }
+
+    /// Detections grouped by category, most serious category first.
+    pub fn by_category(&self) -> Vec<(SignatureCategory, Vec<&Detection>)> {
+        let mut groups: Vec<(SignatureCategory, Vec<&Detection>)> = Vec::new();
+        for detection in &self.detections {
+            match groups.iter_mut().find(|(category, _)| *category == detection.category) {
+                Some((_, group)) => group.push(detection),
+                None => groups.push((detection.category, vec![detection])),
+            }
+        }
+        groups.sort_by_key(|(category, _)| *category);
+        groups
+    }
+}
+
+/// The `SCAN SUMMARY` block that `clamscan` prints at the end.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClamScanSummary {
+    pub scanned_files: Option<u64>,
+    pub infected_files: Option<u64>,
+    /// As printed, e.g. `12.34 MB`.
+    pub data_scanned: Option<String>,
+    /// As printed, e.g. `3.021 sec (0 m 3 s)`.
+    pub time: Option<String>,
+}
+
+impl ClamScanSummary {
+    pub fn parse(output: &str) -> ClamScanSummary {
+        let mut summary = ClamScanSummary::default();
+        for line in output.lines() {
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let value = value.trim();
+            match key.trim() {
+                "Scanned files" => summary.scanned_files = value.parse().ok(),
+                "Infected files" => summary.infected_files = value.parse().ok(),
+                "Data scanned" => summary.data_scanned = Some(value.to_string()),
+                "Time" => summary.time = Some(value.to_string()),
+                _ => {}
+            }
+        }
+        summary
+    }
+
+    /// One-line description, e.g. "Scan of /home complete: 3 infected, 12,400 scanned".
+    pub fn describe(&self, path: &str) -> String {
+        match (self.infected_files, self.scanned_files) {
+            (Some(infected), Some(scanned)) => format!(
+                "Scan of {} complete: {} infected, {} scanned",
+                path,
+                group_thousands(infected),
+                group_thousands(scanned)
+            ),
+            _ => format!("Scan of {} finished without a summary (check the output for errors)", path),
+        }
+    }
+
+    /// Whether clamscan got as far as printing its summary block.
+    pub fn is_present(&self) -> bool {
+        self.scanned_files.is_some() || self.infected_files.is_some()
+    }
+}
+
+pub fn group_thousands(n: u64) -> String {
+    let digits = n.to_string();
+    let mut grouped = String::new();
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    grouped
+}
+
+/// Directories a scan of `/` should skip: the fixed pseudo-filesystem
+/// directories plus anything currently mounted from a virtual or network
+/// filesystem outside them.
+pub fn full_system_exclusions() -> Vec<String> {
+    let mut excluded: Vec<String> = PSEUDO_FS_DIRS.iter().map(|dir| dir.to_string()).collect();
+    for (mount_point, fs_type) in mounted_filesystems() {
+        let skip = VIRTUAL_FS_TYPES.contains(&fs_type.as_str()) || NETWORK_FS_TYPES.contains(&fs_type.as_str());
+        let covered = excluded
+            .iter()
+            .any(|dir| mount_point == *dir || mount_point.starts_with(&format!("{}/", dir)));
+        if skip && mount_point != "/" && !covered {
+            excluded.push(mount_point);
+        }
+    }
+    excluded
+}
+
+/// A `--exclude-dir` argument matching `dir` and everything under it.
+/// clamscan takes POSIX extended regexes, so the path is escaped for those.
+pub fn exclude_dir_arg(dir: &str) -> String {
+    let mut escaped = String::new();
+    for c in dir.chars() {
+        if ".[]()*+?{}|^$\\".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    format!("--exclude-dir=^{}(/|$)", escaped)
+}
+
+/// What happened to one database in a freshclam run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DatabaseState {
+    Updated,
+    UpToDate,
+}
+
+/// One database line from freshclam, e.g.
+/// `daily.cld updated (version: 27431, sigs: 2069786, ...)`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DatabaseStatus {
+    pub name: String,
+    pub version: Option<u64>,
+    pub state: DatabaseState,
+}
+
+/// The outcome of a freshclam run, read from its output.
+#[derive(Debug, Clone, Default)]
+pub struct FreshclamReport {
+    pub databases: Vec<DatabaseStatus>,
+    pub warnings: Vec<String>,
+    pub errors: Vec<String>,
+}
+
+impl FreshclamReport {
+    pub fn parse(output: &str) -> FreshclamReport {
+        let mut report = FreshclamReport::default();
+        for line in output.split(['\n', '\r']).map(str::trim) {
+            if let Some(error) = line.strip_prefix("ERROR:") {
+                report.errors.push(error.trim().to_string());
+            } else if let Some(warning) = line.strip_prefix("WARNING:") {
+                report.warnings.push(warning.trim().to_string());
+            } else if let Some(status) = parse_database_status(line) {
+                report.databases.retain(|db| db.name != status.name);
+                report.databases.push(status);
+            }
+        }
+        report
+    }
+
+    /// One-line description, e.g. "2 databases updated, 1 already up to date".
+    pub fn describe(&self) -> String {
+        let updated = self.databases.iter().filter(|db| db.state == DatabaseState::Updated).count();
+        let current = self.databases.len() - updated;
+        let mut summary = format!("{} database(s) updated, {} already up to date", updated, current);
+        if !self.errors.is_empty() {
+            summary.push_str(&format!(", {} error(s)", self.errors.len()));
+        }
+        summary
+    }
+}
+
+fn parse_database_status(line: &str) -> Option<DatabaseStatus> {
+    let (name, rest) = line.split_once(' ')?;
+    if !name.contains('.') {
+        return None;
+    }
+    let state = if rest.starts_with("updated") {
+        DatabaseState::Updated
+    } else if rest.starts_with("database is up-to-date") || rest.starts_with("is up to date") {
+        DatabaseState::UpToDate
+    } else {
+        return None;
+    };
+    let version = rest
+        .split_once("version: ")
+        .and_then(|(_, after)| after.split(|c: char| !c.is_ascii_digit()).next())
+        .and_then(|digits| digits.parse().ok());
+    Some(DatabaseStatus {
+        name: name.to_string(),
+        version,
+        state,
+    })
+}
+
+/// Output lines kept for the live log of a running clamscan.
+const SCAN_LOG_LINES: usize = 200;
+
+/// Live progress of a running clamscan, fed one output line at a time.
+/// clamscan prints a line per file (`<path>: OK`, `<path>: <sig> FOUND`, ...)
+/// unless it only reports infected files.
+#[derive(Debug, Clone, Default)]
+pub struct ClamScanProgress {
+    pub scanned_files: u64,
+    pub infected_files: u64,
+    /// The latest output lines, oldest first.
+    pub log: VecDeque<String>,
+}
+
+impl ClamScanProgress {
+    pub fn update(&mut self, line: &str) {
+        let line = line.trim_end();
+        if line.is_empty() {
+            return;
+        }
+        if line.ends_with(" FOUND") {
+            self.infected_files += 1;
+            self.scanned_files += 1;
+        } else if line.ends_with(": OK") || line.ends_with(": Empty file") {
+            self.scanned_files += 1;
+        }
+        if self.log.len() == SCAN_LOG_LINES {
+            self.log.pop_front();
+        }
+        self.log.push_back(line.to_string());
+    }
+}
+
+/// Live progress of a running freshclam, fed one output line at a time.
+/// Progress bars are redrawn with `\r`, so callers split on that as well.
+#[derive(Debug, Clone, Default)]
+pub struct FreshclamProgress {
+    /// The database or patch currently downloading.
+    pub database: Option<String>,
+    pub percent: Option<f32>,
+    pub last_line: String,
+}
+
+impl FreshclamProgress {
+    pub fn update(&mut self, line: &str) {
+        let line = line.trim();
+        if line.is_empty() {
+            return;
+        }
+        self.last_line = line.to_string();
+
+        if let Some((name, _)) = line.split_once(" database available for download") {
+            self.start(name);
+        } else if let Some(target) = line.strip_prefix("Downloading ").or_else(|| line.strip_prefix("Retrieving ")) {
+            let file = target.rsplit('/').next().unwrap_or(target);
+            self.start(file.split_whitespace().next().unwrap_or(file));
+        } else if let Some(percent) = parse_progress_percent(line) {
+            self.percent = Some(percent);
+        }
+    }
+
+    fn start(&mut self, database: &str) {
+        self.database = Some(database.to_string());
+        self.percent = None;
+    }
+}
+
+/// Reads either `[ 42%]` or the `12.30MiB/58.97MiB` counter that newer
+/// freshclam versions print after the bar.
+fn parse_progress_percent(line: &str) -> Option<f32> {
+    for token in line.split_whitespace().rev() {
+        let token = token.trim_matches(|c| c == '[' || c == ']');
+        if let Some(number) = token.strip_suffix('%') {
+            if let Ok(percent) = number.parse::<f32>() {
+                return Some(percent.clamp(0.0, 100.0));
+            }
+        }
+        if let Some((done, total)) = token.split_once('/') {
+            if let (Some(done), Some(total)) = (parse_size(done), parse_size(total)) {
+                if total > 0.0 {
+                    return Some((done / total * 100.0).clamp(0.0, 100.0) as f32);
+                }
+            }
+        }
+    }
+    None
+}
+
+/// `58.97MiB` and friends, in bytes.
+fn parse_size(size: &str) -> Option<f64> {
+    let split = size.find(|c: char| !c.is_ascii_digit() && c != '.')?;
+    let (number, unit) = size.split_at(split);
+    let multiplier = match unit {
+        "B" => 1.0,
+        "KiB" | "KB" => 1024.0,
+        "MiB" | "MB" => 1024.0 * 1024.0,
+        "GiB" | "GB" => 1024.0 * 1024.0 * 1024.0,
+        _ => return None,
+    };
+    Some(number.parse::<f64>().ok()? * multiplier)
+}
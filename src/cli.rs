@@ -0,0 +1,337 @@
+// src/cli.rs
+
+use anyhow::{bail, Context, Result};
+use log::LevelFilter;
+use std::path::PathBuf;
+
+use crate::analyzer::{Platform, Severity};
+
+pub const USAGE: &str = "\
+Usage:
+  bigman                         Open the GUI
+  <script> | bigman [OPTIONS]    Analyze a script piped on stdin
+  bigman scan [OPTIONS] <path>...
+                                 Scan any files (PDF, script, executable or
+                                 other binary, detected from the content) and
+                                 print one verdict each; quoted globs such as
+                                 'downloads/**/*' are expanded
+  bigman scan-pdf [OPTIONS] <file>...
+  bigman scan-pdf [OPTIONS] --dir <folder>
+                                 Scan PDF files and print a verdict for each;
+                                 every result is also saved as JSON to the
+                                 scan history
+  bigman scan-archive [OPTIONS] <archive|->
+                                 Scan every file in a tar, tar.gz or zip archive,
+                                 read from stdin when given '-'
+  bigman explain-rule [<id>]     Show what an analyzer rule detects and why,
+                                 or list all rule ids
+  bigman validate-rules [<path>] Check a rules file (.toml analyzer rules, an IOC
+                                 list, or .yar/.yara rules in builds with the yara
+                                 feature, using the yara CLI on PATH) compiles,
+                                 without scanning; checks the built-in rules when
+                                 no path is given
+  bigman git-hook [OPTIONS] [<file>...]
+                                 Analyze the staged version of each text file
+                                 (or the given files); meant for a pre-commit
+                                 hook, see git-hooks/pre-commit
+
+Options:
+  --report-out <path>            Also write the full report as JSON to <path>
+  --json                         When analyzing stdin, print the full result as
+                                 a single JSON object instead of text
+  --rules <path.toml>            When analyzing stdin, also match the [[rule]]
+                                 entries (pattern, description, optional
+                                 severity) in a rules file
+  --no-builtins                  With --rules, use only the file's patterns
+  --platform <name>              When analyzing stdin, run only the windows
+                                 (PowerShell) or unix (shell) rules; default all
+  -q, --quiet                    scan, scan-pdf, scan-archive: print only the verdict
+                                 stdin: print nothing but --json, rely on the exit status
+  --summary                      stdin: print one line such as
+                                 '3 threats in 2 categories' (to stderr with --json)
+  -v, --verbose                  scan-pdf: also print pdfid/pdfinfo output
+                                 scan, scan-archive: also print clean files
+  --dir <folder>                 scan-pdf: scan every .pdf in the folder and print a
+                                 summary table
+  -r, --recursive                scan-pdf: with --dir, include subfolders
+  -j, --jobs <n>                 scan-pdf: scan n files at a time (default 1)
+  --min-severity <level>         git-hook: fail on threats at or above this level
+                                 (info, low, medium, high, critical; default high)
+  --log-level <level>            Log diagnostics at or above this level to stderr
+                                 (error, warn, info, debug, trace or off; default
+                                 warn); overrides RUST_LOG
+  -h, --help                     Show this help
+
+Exit status is 0 when everything is clean and 1 when anything was flagged.";
+
+/// Options for analyzing a script piped on stdin.
+#[derive(Debug, Default)]
+pub struct AnalyzeArgs {
+    pub report_out: Option<PathBuf>,
+    /// Print the analysis as one JSON object on stdout.
+    pub json: bool,
+    /// Extra line patterns to match.
+    pub rules: Option<PathBuf>,
+    /// Match only the patterns from `rules`.
+    pub no_builtins: bool,
+    pub platform: Platform,
+    /// `Quiet` prints nothing except `--json` output.
+    pub verbosity: Verbosity,
+    /// Print one summary line instead of the full report.
+    pub summary: bool,
+}
+
+/// How much a scan command prints.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Verbosity {
+    /// Just the verdict word.
+    Quiet,
+    /// Verdict plus the key findings.
+    #[default]
+    Normal,
+    /// Everything, including raw helper tool output.
+    Verbose,
+}
+
+/// Options for `scan`.
+#[derive(Debug, Default)]
+pub struct ScanArgs {
+    /// Files or glob patterns.
+    pub paths: Vec<String>,
+    pub verbosity: Verbosity,
+    pub report_out: Option<PathBuf>,
+}
+
+/// Options for `scan-pdf`.
+#[derive(Debug, Default)]
+pub struct ScanPdfArgs {
+    pub paths: Vec<String>,
+    /// Also scan the PDFs in this folder.
+    pub dir: Option<PathBuf>,
+    /// Descend into subfolders of `dir`.
+    pub recursive: bool,
+    /// Files scanned at once; 0 is treated as 1.
+    pub jobs: usize,
+    pub verbosity: Verbosity,
+    pub report_out: Option<PathBuf>,
+}
+
+/// Options for `scan-archive`.
+#[derive(Debug, Default)]
+pub struct ScanArchiveArgs {
+    /// Archive path, or `-` for stdin.
+    pub source: String,
+    pub verbosity: Verbosity,
+    pub report_out: Option<PathBuf>,
+}
+
+/// Options for `git-hook`.
+#[derive(Debug)]
+pub struct GitHookArgs {
+    /// Files to check on disk; empty means the files staged in git.
+    pub paths: Vec<String>,
+    pub min_severity: Severity,
+}
+
+impl Default for GitHookArgs {
+    fn default() -> Self {
+        Self {
+            paths: Vec::new(),
+            min_severity: Severity::High,
+        }
+    }
+}
+
+/// What the process was asked to do.
+#[derive(Debug)]
+pub enum Command {
+    /// No subcommand: analyze piped stdin, or open the GUI on a terminal.
+    Default(AnalyzeArgs),
+    Scan(ScanArgs),
+    ScanPdf(ScanPdfArgs),
+    ScanArchive(ScanArchiveArgs),
+    /// Document one analyzer rule, or list them all when no id is given.
+    ExplainRule(Option<String>),
+    /// Check a ruleset, or the built-in rules when no path is given.
+    ValidateRules(Option<PathBuf>),
+    GitHook(GitHookArgs),
+    Help,
+}
+
+/// Browsers launch native messaging hosts with the caller's origin
+/// (`chrome-extension://<id>/`, Chrome/Edge) or the path to the host manifest
+/// followed by the add-on id (Firefox). Anything else is a command line.
+pub fn is_native_messaging_launch(args: &[String]) -> bool {
+    args.first()
+        .is_some_and(|first| first.starts_with("chrome-extension://") || first.ends_with(".json"))
+}
+
+/// Removes `--log-level <level>` from anywhere in `args`, since it applies to
+/// every command, and returns the level if it was given.
+pub fn take_log_level(args: &mut Vec<String>) -> Result<Option<LevelFilter>> {
+    let Some(index) = args.iter().position(|arg| arg == "--log-level") else {
+        return Ok(None);
+    };
+    args.remove(index);
+    if index >= args.len() {
+        bail!("--log-level needs error, warn, info, debug, trace or off");
+    }
+    let value = args.remove(index);
+    let level = value
+        .parse()
+        .map_err(|_| anyhow::anyhow!("Unknown log level '{}': use error, warn, info, debug, trace or off", value))?;
+    Ok(Some(level))
+}
+
+/// Parses the arguments after the program name.
+pub fn parse_args(args: &[String]) -> Result<Command> {
+    match args.first().map(String::as_str) {
+        Some("scan") => parse_scan(&args[1..]),
+        Some("scan-pdf") => parse_scan_pdf(&args[1..]),
+        Some("scan-archive") => parse_scan_archive(&args[1..]),
+        Some("explain-rule") => parse_explain_rule(&args[1..]),
+        Some("validate-rules") => parse_validate_rules(&args[1..]),
+        Some("git-hook") => parse_git_hook(&args[1..]),
+        _ => parse_default(args),
+    }
+}
+
+fn parse_default(args: &[String]) -> Result<Command> {
+    let mut analyze = AnalyzeArgs::default();
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "--report-out" => analyze.report_out = Some(report_path(args.next())?),
+            "--json" => analyze.json = true,
+            "-q" | "--quiet" => analyze.verbosity = Verbosity::Quiet,
+            "--summary" => analyze.summary = true,
+            "--rules" => analyze.rules = Some(args.next().map(PathBuf::from).context("--rules needs a file path")?),
+            "--no-builtins" => analyze.no_builtins = true,
+            "--platform" => analyze.platform = args.next().context("--platform needs windows, unix or all")?.parse()?,
+            other => bail!("Unknown argument '{}'\n\n{}", other, USAGE),
+        }
+    }
+
+    if analyze.no_builtins && analyze.rules.is_none() {
+        bail!("--no-builtins needs a --rules file\n\n{}", USAGE);
+    }
+    Ok(Command::Default(analyze))
+}
+
+fn parse_scan(args: &[String]) -> Result<Command> {
+    let mut scan = ScanArgs::default();
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "-q" | "--quiet" => scan.verbosity = Verbosity::Quiet,
+            "-v" | "--verbose" => scan.verbosity = Verbosity::Verbose,
+            "--report-out" => scan.report_out = Some(report_path(args.next())?),
+            other if other.starts_with('-') => bail!("Unknown option '{}'\n\n{}", other, USAGE),
+            path => scan.paths.push(path.to_string()),
+        }
+    }
+
+    if scan.paths.is_empty() {
+        bail!("scan needs at least one file or pattern\n\n{}", USAGE);
+    }
+    Ok(Command::Scan(scan))
+}
+
+fn parse_scan_pdf(args: &[String]) -> Result<Command> {
+    let mut scan = ScanPdfArgs::default();
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "-q" | "--quiet" => scan.verbosity = Verbosity::Quiet,
+            "-v" | "--verbose" => scan.verbosity = Verbosity::Verbose,
+            "--report-out" => scan.report_out = Some(report_path(args.next())?),
+            "--dir" => scan.dir = Some(args.next().map(PathBuf::from).context("--dir needs a folder")?),
+            "-r" | "--recursive" => scan.recursive = true,
+            "-j" | "--jobs" => {
+                let jobs = args.next().context("--jobs needs a number")?;
+                scan.jobs = match jobs.parse() {
+                    Ok(jobs) if jobs > 0 => jobs,
+                    _ => bail!("--jobs needs a positive number, not '{}'", jobs),
+                };
+            }
+            other if other.starts_with('-') && other != "-" => bail!("Unknown option '{}'\n\n{}", other, USAGE),
+            path => scan.paths.push(path.to_string()),
+        }
+    }
+
+    if scan.paths.is_empty() && scan.dir.is_none() {
+        bail!("scan-pdf needs at least one file or --dir\n\n{}", USAGE);
+    }
+    if scan.recursive && scan.dir.is_none() {
+        bail!("--recursive only applies to --dir\n\n{}", USAGE);
+    }
+    Ok(Command::ScanPdf(scan))
+}
+
+fn parse_scan_archive(args: &[String]) -> Result<Command> {
+    let mut scan = ScanArchiveArgs::default();
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "-q" | "--quiet" => scan.verbosity = Verbosity::Quiet,
+            "-v" | "--verbose" => scan.verbosity = Verbosity::Verbose,
+            "--report-out" => scan.report_out = Some(report_path(args.next())?),
+            other if other.starts_with('-') && other != "-" => bail!("Unknown option '{}'\n\n{}", other, USAGE),
+            source if scan.source.is_empty() => scan.source = source.to_string(),
+            _ => bail!("scan-archive takes a single archive\n\n{}", USAGE),
+        }
+    }
+
+    if scan.source.is_empty() {
+        bail!("scan-archive needs an archive path, or '-' to read stdin\n\n{}", USAGE);
+    }
+    Ok(Command::ScanArchive(scan))
+}
+
+fn parse_explain_rule(args: &[String]) -> Result<Command> {
+    match args {
+        [] => Ok(Command::ExplainRule(None)),
+        [flag] if flag == "-h" || flag == "--help" => Ok(Command::Help),
+        [id] => Ok(Command::ExplainRule(Some(id.clone()))),
+        _ => bail!("explain-rule takes a single rule id\n\n{}", USAGE),
+    }
+}
+
+fn parse_validate_rules(args: &[String]) -> Result<Command> {
+    match args {
+        [] => Ok(Command::ValidateRules(None)),
+        [flag] if flag == "-h" || flag == "--help" => Ok(Command::Help),
+        [path] => Ok(Command::ValidateRules(Some(PathBuf::from(path)))),
+        _ => bail!("validate-rules takes a single rules file\n\n{}", USAGE),
+    }
+}
+
+fn parse_git_hook(args: &[String]) -> Result<Command> {
+    let mut hook = GitHookArgs::default();
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "--min-severity" => {
+                hook.min_severity = args.next().context("--min-severity needs a level")?.parse()?;
+            }
+            other if other.starts_with('-') => bail!("Unknown option '{}'\n\n{}", other, USAGE),
+            path => hook.paths.push(path.to_string()),
+        }
+    }
+
+    Ok(Command::GitHook(hook))
+}
+
+fn report_path(value: Option<&String>) -> Result<PathBuf> {
+    value.map(PathBuf::from).context("--report-out needs a file path")
+}
@@ -0,0 +1,337 @@
+// src/config.rs
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::ioc::IocList;
+
+/// Bumped whenever the bundle layout changes in a way older builds can't read.
+pub const CONFIG_BUNDLE_VERSION: u32 = 1;
+
+const SETTINGS_FILE: &str = "settings.json";
+
+/// Placeholder written in place of the exporting user's home directory.
+const HOME_PLACEHOLDER: &str = "$HOME";
+
+/// Options for the `clamscan` command.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ClamScanOptions {
+    pub recursive: bool,
+    pub verbose: bool,
+    pub infected_only: bool,
+    pub remove_infected: bool,
+    /// Move infected files into `quarantine_dir` (`--move`) instead of
+    /// leaving or deleting them. Takes precedence over `remove_infected`.
+    pub quarantine_infected: bool,
+    /// Empty means the default quarantine folder.
+    pub quarantine_dir: String,
+}
+
+impl ClamScanOptions {
+    /// Whether a scan with these options deletes what it flags.
+    pub fn deletes_infected(&self) -> bool {
+        self.remove_infected && !self.quarantine_infected
+    }
+}
+
+/// One environment variable override for the spawned ClamAV processes.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EnvVar {
+    pub name: String,
+    pub value: String,
+}
+
+/// Where to find ClamAV and how to launch it, for non-standard installations.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ClamAvSettings {
+    /// `clamscan` binary, either a bare name looked up on PATH or a full path.
+    pub clamscan_path: String,
+    /// `freshclam` binary, either a bare name looked up on PATH or a full path.
+    pub freshclam_path: String,
+    /// Applied on top of the inherited environment (e.g. `LD_LIBRARY_PATH`, `TMPDIR`).
+    pub env: Vec<EnvVar>,
+}
+
+impl Default for ClamAvSettings {
+    fn default() -> Self {
+        Self {
+            clamscan_path: "clamscan".to_string(),
+            freshclam_path: "freshclam".to_string(),
+            env: Vec::new(),
+        }
+    }
+}
+
+impl ClamAvSettings {
+    fn command(&self, program: &str) -> Command {
+        let mut cmd = Command::new(program);
+        for var in self.env.iter().filter(|var| !var.name.trim().is_empty()) {
+            cmd.env(var.name.trim(), &var.value);
+        }
+        cmd
+    }
+
+    /// A `clamscan` command with the configured binary and environment.
+    pub fn clamscan_command(&self) -> Command {
+        self.command(&self.clamscan_path)
+    }
+
+    /// A `freshclam` command with the configured binary and environment.
+    pub fn freshclam_command(&self) -> Command {
+        self.command(&self.freshclam_path)
+    }
+}
+
+/// What to do when removable media is mounted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum UsbScanMode {
+    /// Don't watch for removable media.
+    #[default]
+    Off,
+    /// Ask before scanning the new mount point.
+    Prompt,
+    /// Start a recursive scan as soon as the media is mounted.
+    AutoScan,
+}
+
+/// Local side effects when the browser integration flags a download. They
+/// run before the verdict is sent back, so each one has to be quick.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SuspiciousDownloadActions {
+    /// Move the file into the quarantine folder.
+    pub quarantine: bool,
+    /// Append an entry to the audit log.
+    pub audit_log: bool,
+    /// Raise a desktop notification.
+    pub notify: bool,
+}
+
+/// How much old audit and scan history to keep. Full files are compressed
+/// and set aside rather than truncated, and only those set-aside copies are
+/// ever deleted. A limit of 0 disables it.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetentionPolicy {
+    /// Set the audit log or scan archive aside once it reaches this size.
+    pub rotate_at_mb: u32,
+    /// Delete set-aside files older than this.
+    pub max_age_days: u32,
+    /// Delete the oldest set-aside files once each store's total exceeds this.
+    pub max_total_mb: u32,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        Self {
+            rotate_at_mb: 10,
+            max_age_days: 365,
+            max_total_mb: 500,
+        }
+    }
+}
+
+/// User-facing settings that travel with an exported configuration.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub scan_path: String,
+    pub clamscan_options: ClamScanOptions,
+    pub usb_scan_mode: UsbScanMode,
+    pub clamav: ClamAvSettings,
+    pub on_suspicious_download: SuspiciousDownloadActions,
+    pub retention: RetentionPolicy,
+    /// Where scratch files are written; empty for the system temp directory.
+    pub temp_dir: String,
+    /// PDFs bigger than this aren't scanned; 0 for no limit.
+    pub max_pdf_scan_mb: u32,
+    /// Other files are read whole to be scanned, so bigger ones than this
+    /// aren't; 0 for no limit.
+    pub max_scan_mb: u32,
+    /// Folders whose files are reported clean without being scanned, e.g.
+    /// where the user's own document scanner saves.
+    pub trusted_paths: Vec<String>,
+    /// Folder of `.yar` rules for builds with the `yara` feature; empty to
+    /// skip YARA matching.
+    pub yara_rules_dir: String,
+    /// PDF scans kept in the window's list, newest first; 0 for no limit.
+    pub max_pdf_results: u32,
+    /// Have the window raise a notification for suspicious PDFs that
+    /// arrive while it isn't focused.
+    pub notify_suspicious_pdfs: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            scan_path: "/home".to_string(),
+            clamscan_options: ClamScanOptions::default(),
+            usb_scan_mode: UsbScanMode::default(),
+            clamav: ClamAvSettings::default(),
+            on_suspicious_download: SuspiciousDownloadActions::default(),
+            retention: RetentionPolicy::default(),
+            temp_dir: String::new(),
+            max_pdf_scan_mb: 256,
+            max_scan_mb: 256,
+            trusted_paths: Vec::new(),
+            yara_rules_dir: String::new(),
+            max_pdf_results: 5000,
+            notify_suspicious_pdfs: true,
+        }
+    }
+}
+
+/// Everything needed to reproduce one machine's setup on another.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigBundle {
+    pub version: u32,
+    #[serde(default)]
+    pub settings: Settings,
+    #[serde(default)]
+    pub iocs: IocList,
+}
+
+fn home_dir() -> Option<String> {
+    std::env::var("HOME")
+        .or_else(|_| std::env::var("USERPROFILE"))
+        .ok()
+        .filter(|home| !home.is_empty())
+}
+
+/// Rewrites a path under the current user's home as `$HOME/...`.
+fn to_portable_path(path: &str) -> String {
+    match home_dir() {
+        Some(home) if path == home => HOME_PLACEHOLDER.to_string(),
+        Some(home) => match path.strip_prefix(&home) {
+            Some(rest) if rest.starts_with(['/', '\\']) => format!("{}{}", HOME_PLACEHOLDER, rest),
+            _ => path.to_string(),
+        },
+        None => path.to_string(),
+    }
+}
+
+/// Expands `$HOME` (or a leading `~`) to the importing user's home directory.
+fn from_portable_path(path: &str) -> String {
+    let Some(home) = home_dir() else {
+        return path.to_string();
+    };
+    if let Some(rest) = path.strip_prefix(HOME_PLACEHOLDER) {
+        format!("{}{}", home, rest)
+    } else if path == "~" || path.starts_with("~/") {
+        format!("{}{}", home, &path[1..])
+    } else {
+        path.to_string()
+    }
+}
+
+/// The per-user data directory: `$XDG_DATA_HOME/bigman`,
+/// `~/.local/share/bigman`, or `%LOCALAPPDATA%\bigman` on Windows. Falls
+/// back to the CWD when none of those are set. The browser starts the
+/// native messaging host in its own working directory, so everything the
+/// GUI and the host share must live here rather than in the CWD.
+pub fn user_data_dir() -> PathBuf {
+    let from_env = |name: &str| std::env::var_os(name).filter(|value| !value.is_empty()).map(PathBuf::from);
+    let data_dir = if cfg!(windows) {
+        from_env("LOCALAPPDATA")
+    } else {
+        from_env("XDG_DATA_HOME").or_else(|| from_env("HOME").map(|home| home.join(".local").join("share")))
+    };
+    match data_dir {
+        Some(dir) => dir.join("bigman"),
+        None => PathBuf::from("."),
+    }
+}
+
+/// `name` inside `user_data_dir()`.
+pub fn data_path(name: &str) -> PathBuf {
+    user_data_dir().join(name)
+}
+
+/// Writes `contents` to `path`, creating the data directory first.
+pub fn write_data_file(path: &Path, contents: impl AsRef<[u8]>) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, contents)
+}
+
+impl Settings {
+    /// Loads the saved settings, or the defaults if none exist yet.
+    pub fn load() -> Settings {
+        fs::read_to_string(data_path(SETTINGS_FILE))
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> std::io::Result<()> {
+        let json = serde_json::to_string_pretty(self).map_err(std::io::Error::other)?;
+        write_data_file(&data_path(SETTINGS_FILE), json)
+    }
+
+    fn map_paths(&mut self, map: fn(&str) -> String) {
+        self.scan_path = map(&self.scan_path);
+        self.clamav.clamscan_path = map(&self.clamav.clamscan_path);
+        self.clamav.freshclam_path = map(&self.clamav.freshclam_path);
+        self.temp_dir = map(&self.temp_dir);
+        self.yara_rules_dir = map(&self.yara_rules_dir);
+        for path in &mut self.trusted_paths {
+            *path = map(path);
+        }
+    }
+
+    /// The verdict for a file inside one of `trusted_paths`, or `None` if
+    /// it isn't in any. Both sides are canonicalized first, so `..` or a
+    /// symlink can't make an outside file look like it's inside; a path
+    /// that can't be resolved is never trusted.
+    pub fn trusted_path_reason(&self, path: &Path) -> Option<String> {
+        let path = fs::canonicalize(path).ok()?;
+        self.trusted_paths
+            .iter()
+            .filter(|folder| !folder.trim().is_empty())
+            .find(|folder| fs::canonicalize(folder.trim()).is_ok_and(|folder| path.starts_with(folder)))
+            .map(|folder| format!("Clean (allowlisted): inside the trusted folder {}.", folder.trim()))
+    }
+}
+
+/// Writes the settings and the IOC blocklist to a single versioned JSON file,
+/// with home-relative paths made portable.
+pub fn export_config(path: &Path, settings: &Settings) -> Result<()> {
+    let mut settings = settings.clone();
+    settings.map_paths(to_portable_path);
+
+    let bundle = ConfigBundle {
+        version: CONFIG_BUNDLE_VERSION,
+        settings,
+        iocs: IocList::load(),
+    };
+
+    let json = serde_json::to_string_pretty(&bundle).context("Failed to serialize configuration")?;
+    fs::write(path, json).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Reads a bundle written by `export_config`, expands portable paths for the
+/// current user and installs its IOC blocklist. Returns the bundle so the
+/// caller can apply the settings.
+pub fn import_config(path: &Path) -> Result<ConfigBundle> {
+    let content = fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let mut bundle: ConfigBundle = serde_json::from_str(&content)
+        .with_context(|| format!("{} is not a BigMan configuration export", path.display()))?;
+
+    if bundle.version > CONFIG_BUNDLE_VERSION {
+        bail!(
+            "Configuration was exported by a newer version (format {}, this build understands up to {})",
+            bundle.version,
+            CONFIG_BUNDLE_VERSION
+        );
+    }
+
+    bundle.settings.map_paths(from_portable_path);
+    bundle.iocs.save().context("Failed to save the imported IOC blocklist")?;
+    Ok(bundle)
+}
@@ -0,0 +1,94 @@
+// src/feedback.rs
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+
+use crate::config::{data_path, write_data_file};
+
+const FEEDBACK_FILE: &str = "feedback.json";
+
+/// A user's triage decision on a single finding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    FalsePositive,
+    TruePositive,
+}
+
+/// Accumulated verdicts for one rule.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct FeedbackCounts {
+    pub false_positives: u32,
+    pub true_positives: u32,
+}
+
+impl FeedbackCounts {
+    /// Scales a rule's default weight by the user's verdicts. Each net false
+    /// positive halves the weight, but it never drops below 1 so the rule
+    /// keeps reporting. Each net true positive raises it by a quarter, capped
+    /// at double the default.
+    pub fn adjust(&self, base_weight: u32) -> u32 {
+        let net = i64::from(self.true_positives) - i64::from(self.false_positives);
+        if net < 0 {
+            let halvings = net.unsigned_abs().min(31) as u32;
+            (base_weight >> halvings).max(1).min(base_weight)
+        } else {
+            let raised = u64::from(base_weight) * (4 + net as u64) / 4;
+            raised.min(u64::from(base_weight) * 2) as u32
+        }
+    }
+}
+
+/// Locally stored verdicts, keyed by rule id (e.g. `pdf:/OpenAction`).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FeedbackStore {
+    #[serde(default)]
+    rules: BTreeMap<String, FeedbackCounts>,
+}
+
+impl FeedbackStore {
+    /// Loads the saved verdicts, or an empty store if none exist yet.
+    pub fn load() -> FeedbackStore {
+        fs::read_to_string(data_path(FEEDBACK_FILE))
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> std::io::Result<()> {
+        let json = serde_json::to_string_pretty(self).map_err(std::io::Error::other)?;
+        write_data_file(&data_path(FEEDBACK_FILE), json)
+    }
+
+    pub fn record(&mut self, rule: &str, verdict: Verdict) {
+        let counts = self.rules.entry(rule.to_string()).or_default();
+        match verdict {
+            Verdict::FalsePositive => counts.false_positives += 1,
+            Verdict::TruePositive => counts.true_positives += 1,
+        }
+    }
+
+    /// The weight to use for `rule` given its default.
+    pub fn weight(&self, rule: &str, base_weight: u32) -> u32 {
+        self.rules
+            .get(rule)
+            .map_or(base_weight, |counts| counts.adjust(base_weight))
+    }
+
+    /// All rules that have received feedback.
+    pub fn entries(&self) -> impl Iterator<Item = (&String, &FeedbackCounts)> {
+        self.rules.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    pub fn reset_rule(&mut self, rule: &str) {
+        self.rules.remove(rule);
+    }
+
+    pub fn reset(&mut self) {
+        self.rules.clear();
+    }
+}
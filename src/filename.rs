@@ -0,0 +1,98 @@
+// src/filename.rs
+
+use crate::analyzer::{bidi_control_name, zero_width_name};
+
+/// Starts a run of text that is laid out right-to-left.
+fn starts_rtl_run(c: char) -> bool {
+    matches!(c, '\u{202B}' | '\u{202E}' | '\u{2067}')
+}
+
+/// Ends the innermost embedding, override or isolate.
+fn ends_run(c: char) -> bool {
+    matches!(c, '\u{202C}' | '\u{2069}')
+}
+
+fn control_name(c: char) -> String {
+    match c {
+        '\n' => "newline".to_string(),
+        '\r' => "carriage return".to_string(),
+        '\t' => "tab".to_string(),
+        '\0' => "NUL".to_string(),
+        _ => format!("U+{:04X}", c as u32),
+    }
+}
+
+/// Roughly how a file manager would show `name`: text after a right-to-left
+/// override is reversed up to the matching pop (or the end of the name).
+fn displayed_name(name: &str) -> String {
+    let mut displayed = String::new();
+    let mut chars = name.chars();
+    while let Some(c) = chars.next() {
+        if starts_rtl_run(c) {
+            let run: String = chars.by_ref().take_while(|&c| !ends_run(c)).collect();
+            displayed.extend(run.chars().rev());
+        } else if bidi_control_name(c).is_none() {
+            displayed.push(c);
+        }
+    }
+    displayed
+}
+
+fn extension(name: &str) -> Option<String> {
+    let (stem, ext) = name.rsplit_once('.')?;
+    (!stem.is_empty() && !ext.is_empty()).then(|| ext.to_ascii_lowercase())
+}
+
+/// Where a name being checked came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NameSource {
+    /// A path the user gave on the command line or picked, or a download
+    /// already on disk. Only its last component says anything about the file.
+    Local,
+    /// A name chosen by someone else, such as an archive entry or a name the
+    /// browser extension sent, that may be used to write the file out.
+    Untrusted,
+}
+
+/// Flags names that are dangerous in themselves, whatever the file holds:
+/// control characters such as embedded newlines, invisible characters, and
+/// bidi overrides that disguise the real extension. Untrusted names are also
+/// checked for `..` components that escape the target directory.
+pub fn check_file_name(path: &str, source: NameSource) -> Vec<String> {
+    let mut issues = Vec::new();
+
+    if source == NameSource::Untrusted && path.split(['/', '\\']).any(|component| component == "..") {
+        issues.push("Filename contains a path traversal component (..)".to_string());
+    }
+
+    let file_name = path.rsplit(['/', '\\']).next().unwrap_or(path);
+    let mut seen_controls = Vec::new();
+    for c in file_name.chars().filter(|c| c.is_control()) {
+        if !seen_controls.contains(&c) {
+            seen_controls.push(c);
+            issues.push(format!("Filename contains control character {}", control_name(c)));
+        }
+    }
+
+    let mut seen_invisible = Vec::new();
+    for name in file_name.chars().filter_map(zero_width_name) {
+        if !seen_invisible.contains(&name) {
+            seen_invisible.push(name);
+            issues.push(format!("Filename contains invisible character {}", name));
+        }
+    }
+
+    if let Some(bidi) = file_name.chars().find_map(bidi_control_name) {
+        let real = extension(&file_name.chars().filter(|&c| bidi_control_name(c).is_none()).collect::<String>());
+        let shown = extension(&displayed_name(file_name));
+        match (real, shown) {
+            (Some(real), Some(shown)) if real != shown => issues.push(format!(
+                "Filename contains RTL override masking .{} as .{}",
+                real, shown
+            )),
+            _ => issues.push(format!("Filename contains {} that reorders how it is displayed", bidi)),
+        }
+    }
+
+    issues
+}
@@ -1,12 +1,33 @@
 use eframe::egui;
-use std::process::{Command, Stdio};
+use std::io::Read;
+use std::process::{Child, Command, Stdio};
 use std::sync::mpsc;
 use std::thread;
 use std::mem;
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use serde::{Deserialize, Serialize};
 
 // Import our new modules
-use crate::pdf_scanner::PdfScanResult;
-use crate::ipc::start_ipc_server;
+use crate::pdf_scanner::{compact_scan_results, load_scan_results_in_background, save_scan_notes, scan_pdf_for_actions, PdfScanResult, ScanLoadProgress, PDFID_PROGRAM, PDFINFO_PROGRAM};
+use crate::ipc::{start_ipc_server, IpcScan};
+use crate::ioc::{find_urls, ImportSummary, IocList, UrlReputation};
+use crate::analyzer::{analyze_content, find_rule, validate_ruleset, AnalysisResult, RuleValidation, Severity};
+use crate::config::{data_path, export_config, import_config, write_data_file, EnvVar, Settings, UsbScanMode};
+use crate::usb_watch::{start_usb_watcher, RemovableMount};
+use crate::feedback::{FeedbackStore, Verdict};
+use crate::clamav::{exclude_dir_arg, full_system_exclusions, group_thousands, ClamScanProgress, ClamScanReport, ClamScanSummary, DatabaseState, FreshclamProgress, FreshclamReport, SignatureCategory};
+use crate::status::{scanner_availability, ScannerAvailability};
+use crate::quarantine::{self, QuarantineEntry};
+use crate::report::{format_timestamp, format_utc_timestamp, write_csv};
+use crate::theme::{self, ThemePreference};
+#[cfg(feature = "yara")]
+use crate::yara::{self, YaraMatch};
+#[cfg(feature = "yara")]
+use std::path::PathBuf;
+use crate::{audit, notify, retention};
 
 /// Represents the state of a long-running task (scan or update).
 enum Task {
@@ -18,67 +39,249 @@ enum Task {
     Complete(String),
 }
 
-/// Options for the `clamscan` command.
+/// A `Load Previous Scans` run streaming results in from disk.
+struct ScanLoader {
+    receiver: mpsc::Receiver<ScanLoadProgress>,
+    files_done: usize,
+    total: usize,
+    /// Content hashes (or scan ids) already in the list, and whether that
+    /// copy came from the archive (and so may be replaced by a newer one).
+    known_ids: HashMap<String, bool>,
+}
+
+/// Unresolved threats across every view, counted fresh each frame for the
+/// indicator in the header.
 #[derive(Default)]
-struct ClamScanOptions {
-    recursive: bool,
-    verbose: bool,
-    infected_only: bool,
-    remove_infected: bool,
+struct ThreatSummary {
+    infected_files: u64,
+    suspicious_pdfs: usize,
+    script_threats: usize,
+}
+
+impl ThreatSummary {
+    fn total(&self) -> u64 {
+        self.infected_files + self.suspicious_pdfs as u64 + self.script_threats as u64
+    }
 }
 
 /// Enum to manage which view is active
-#[derive(PartialEq)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
 enum ActiveView {
+    #[default]
+    Dashboard,
     ClamAV,
     PdfScanner,
+    Iocs,
+    Analyzer,
+    Quarantine,
+}
+
+/// Where the window's own state is kept between runs.
+const GUI_STATE_FILE: &str = "gui_state.json";
+
+/// Window state restored on the next launch. It is kept out of `Settings`
+/// because it doesn't belong in an exported configuration.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct GuiState {
+    pixels_per_point: Option<f32>,
+    active_view: ActiveView,
+    theme: ThemePreference,
+}
+
+impl GuiState {
+    /// The saved state, or the defaults if there is none or it can't be read.
+    fn load() -> GuiState {
+        std::fs::read_to_string(data_path(GUI_STATE_FILE))
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(&self) -> std::io::Result<()> {
+        let json = serde_json::to_string_pretty(self).map_err(std::io::Error::other)?;
+        write_data_file(&data_path(GUI_STATE_FILE), json)
+    }
 }
 
 /// The main application state.
 pub struct BigmanApp {
-    scan_path: String,
-    clamscan_options: ClamScanOptions,
+    settings: Settings,
     scan_task: Task,
     update_task: Task,
     // NEW state for the PDF scanner view
     active_view: ActiveView,
-    pdf_scan_results: Vec<PdfScanResult>,
-    ipc_receiver: Option<mpsc::Receiver<PdfScanResult>>,
+    pdf_scan_results: VecDeque<PdfScanResult>,
+    ipc_receiver: Option<mpsc::Receiver<IpcScan>>,
+    /// Why the IPC server couldn't start, shown as a banner.
+    ipc_unavailable: Option<String>,
+    /// Downloads that couldn't be scanned (path, reason). Shown apart from
+    /// the results, since they aren't detections.
+    pdf_scan_errors: VecDeque<(String, String)>,
     selected_scan_index: Option<usize>,
+    /// Only list PDF scans whose path or reason contains this.
+    pdf_filter: String,
+    pdf_suspicious_only: bool,
+    ioc_list: IocList,
+    ioc_input: String,
+    ioc_import_summary: Option<ImportSummary>,
+    ioc_status: Option<String>,
+    config_status: Option<String>,
+    usb_receiver: Option<mpsc::Receiver<RemovableMount>>,
+    pending_usb_mounts: Vec<RemovableMount>,
+    feedback: FeedbackStore,
+    feedback_status: Option<String>,
+    pdf_status: Option<String>,
+    scan_loader: Option<ScanLoader>,
+    analyzer_input: String,
+    analyzer_result: Option<AnalysisResult>,
+    analyzer_status: Option<String>,
+    url_reputations: HashMap<String, UrlReputation>,
+    /// Result of the last "Validate Rules File" check, with the file name.
+    rules_validation: Option<(String, RuleValidation)>,
+    /// Set from a notification click to bring the scan results into view.
+    show_scan_results: Arc<AtomicBool>,
+    /// Set from a notification click to bring the PDF scans into view.
+    show_pdf_results: Arc<AtomicBool>,
+    /// Suspicious PDFs not yet announced, held back while notifications
+    /// are rate limited.
+    pending_pdf_alerts: Vec<String>,
+    last_pdf_alert: Option<Instant>,
+    /// PDF scans received since the window opened, and how many were suspicious.
+    session_pdf_scans: usize,
+    session_suspicious_pdfs: usize,
+    /// The PDF to scan on demand, and the scan while it runs.
+    pdf_scan_path: String,
+    manual_pdf_scan: Option<mpsc::Receiver<IpcScan>>,
+    /// Output lines and progress of a running `freshclam`.
+    update_stream: Option<(StreamingCommand, FreshclamProgress)>,
+    /// Output lines and file counts of a running `clamscan`.
+    scan_stream: Option<(StreamingCommand, ClamScanProgress)>,
+    /// Parsed from the last finished `freshclam` run.
+    freshclam_report: Option<FreshclamReport>,
+    /// Detections parsed from the last finished ClamAV scan.
+    scan_report: Option<ClamScanReport>,
+    /// The exclusions shown while a full system scan awaits confirmation.
+    confirm_full_scan: Option<Vec<String>>,
+    /// The path (and whether to recurse) of a `--remove` scan awaiting confirmation.
+    confirm_remove_scan: Option<(String, bool)>,
+    /// Which optional helper programs were found at startup.
+    helpers: ScannerAvailability,
+    /// Sort the pdfid keyword table by count (highest first) instead of name.
+    pdfid_sort_by_count: bool,
+    /// The quarantine index, reloaded when the view is opened.
+    quarantine_entries: Vec<QuarantineEntry>,
+    quarantine_status: Option<String>,
+    /// The zoom level as of the last frame, saved on exit.
+    pixels_per_point: f32,
+    theme: ThemePreference,
+    /// A YARA scan of the scan path while it runs, and its last result.
+    #[cfg(feature = "yara")]
+    yara_task: Option<mpsc::Receiver<Result<Vec<YaraMatch>, String>>>,
+    #[cfg(feature = "yara")]
+    yara_result: Option<Result<Vec<YaraMatch>, String>>,
 }
 
 impl Default for BigmanApp {
     fn default() -> Self {
         Self {
-            scan_path: "/home".to_string(),
-            clamscan_options: ClamScanOptions::default(),
+            settings: Settings::load(),
             scan_task: Task::Idle,
             // Start with a helpful message for the user.
             update_task: Task::Complete(
                 "Database status is unknown. Click 'Update Database' to check for new definitions.".to_string(),
             ),
             // NEW default state
-            active_view: ActiveView::ClamAV,
-            pdf_scan_results: Vec::new(),
+            active_view: ActiveView::Dashboard,
+            pdf_scan_results: VecDeque::new(),
             ipc_receiver: None,
+            ipc_unavailable: None,
+            pdf_scan_errors: VecDeque::new(),
             selected_scan_index: None,
+            pdf_filter: String::new(),
+            pdf_suspicious_only: false,
+            ioc_list: IocList::load(),
+            ioc_input: String::new(),
+            ioc_import_summary: None,
+            ioc_status: None,
+            config_status: None,
+            usb_receiver: None,
+            pending_usb_mounts: Vec::new(),
+            feedback: FeedbackStore::load(),
+            feedback_status: None,
+            pdf_status: None,
+            scan_loader: None,
+            analyzer_input: String::new(),
+            analyzer_result: None,
+            analyzer_status: None,
+            url_reputations: HashMap::new(),
+            rules_validation: None,
+            show_scan_results: Arc::new(AtomicBool::new(false)),
+            show_pdf_results: Arc::new(AtomicBool::new(false)),
+            pending_pdf_alerts: Vec::new(),
+            last_pdf_alert: None,
+            session_pdf_scans: 0,
+            session_suspicious_pdfs: 0,
+            pdf_scan_path: String::new(),
+            manual_pdf_scan: None,
+            update_stream: None,
+            scan_stream: None,
+            freshclam_report: None,
+            scan_report: None,
+            confirm_full_scan: None,
+            confirm_remove_scan: None,
+            helpers: scanner_availability(&Settings::load().clamav),
+            pdfid_sort_by_count: true,
+            quarantine_entries: Vec::new(),
+            quarantine_status: None,
+            pixels_per_point: 1.0,
+            theme: ThemePreference::default(),
+            #[cfg(feature = "yara")]
+            yara_task: None,
+            #[cfg(feature = "yara")]
+            yara_result: None,
         }
     }
 }
 
 impl eframe::App for BigmanApp {
     /// Called each frame to update the GUI.
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+    fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        self.pixels_per_point = ctx.pixels_per_point();
+        self.theme.apply(ctx, frame.info().system_theme);
         // Check for new PDF scan results from IPC
-        if let Some(ref receiver) = self.ipc_receiver {
-            while let Ok(result) = receiver.try_recv() {
-                // Prepend to keep the latest result at the top
-                self.pdf_scan_results.insert(0, result);
+        let ipc_scans: Vec<IpcScan> = self.ipc_receiver.iter().flat_map(|receiver| receiver.try_iter()).collect();
+        for scan in ipc_scans {
+            self.add_pdf_scan(scan, true);
+        }
+        if let Some(receiver) = self.manual_pdf_scan.take() {
+            match receiver.try_recv() {
+                Ok(scan) => {
+                    // Show the result the user asked for.
+                    let scanned = scan.1.is_ok();
+                    self.add_pdf_scan(scan, false);
+                    if scanned {
+                        self.selected_scan_index = Some(0);
+                    }
+                }
+                Err(mpsc::TryRecvError::Empty) => self.manual_pdf_scan = Some(receiver),
+                Err(mpsc::TryRecvError::Disconnected) => self.pdf_status = Some("The scan stopped unexpectedly.".to_string()),
             }
         }
+        self.send_pdf_alerts(ctx);
+        if self.show_pdf_results.swap(false, Ordering::Relaxed) {
+            self.active_view = ActiveView::PdfScanner;
+        }
 
         // Check for results from any background tasks.
         self.check_for_task_completion();
+        if self.show_scan_results.swap(false, Ordering::Relaxed) {
+            self.active_view = ActiveView::ClamAV;
+        }
+        self.check_scan_loader(ctx);
+
+        // React to newly mounted USB drives if the user opted in.
+        self.check_for_removable_media(ctx);
 
         egui::CentralPanel::default().show(ctx, |ui| {
             self.draw_header_and_zoom(ui, ctx);
@@ -89,6 +292,9 @@ impl eframe::App for BigmanApp {
             ui.separator();
 
             match self.active_view {
+                ActiveView::Dashboard => {
+                    self.draw_dashboard_view(ui);
+                }
                 ActiveView::ClamAV => {
                     self.draw_database_section(ui);
                     ui.separator();
@@ -97,22 +303,144 @@ impl eframe::App for BigmanApp {
                 ActiveView::PdfScanner => {
                     self.draw_pdf_scanner_view(ui);
                 }
+                ActiveView::Iocs => {
+                    self.draw_ioc_view(ui);
+                }
+                ActiveView::Analyzer => {
+                    self.draw_analyzer_view(ui);
+                }
+                ActiveView::Quarantine => {
+                    self.draw_quarantine_view(ui);
+                }
             }
         });
 
+        self.draw_usb_scan_prompt(ctx);
+        self.draw_full_scan_confirmation(ctx);
+        self.draw_remove_scan_confirmation(ctx);
+
         // If a task is running, request a repaint to update the spinner.
         if matches!(self.scan_task, Task::InProgress(_)) || matches!(self.update_task, Task::InProgress(_)) {
             ctx.request_repaint();
         }
     }
+
+    /// Persist settings so the native messaging host sees the same
+    /// configuration, and the window state for the next launch.
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        let _ = self.settings.save();
+        let state = GuiState {
+            pixels_per_point: Some(self.pixels_per_point),
+            active_view: self.active_view,
+            theme: self.theme,
+        };
+        let _ = state.save();
+    }
 }
 
+/// At most one suspicious-PDF notification is shown per this interval;
+/// anything arriving in between is announced together afterwards.
+const PDF_ALERT_INTERVAL: Duration = Duration::from_secs(10);
+
 impl BigmanApp {
+    /// Lists a finished PDF scan, or its error. `announce` queues a
+    /// notification for suspicious downloads the user didn't start.
+    fn add_pdf_scan(&mut self, (file_path, outcome): IpcScan, announce: bool) {
+        match outcome {
+            // Prepend to keep the latest result at the top
+            Ok(result) => {
+                self.session_pdf_scans += 1;
+                if result.is_suspicious {
+                    self.session_suspicious_pdfs += 1;
+                }
+                // With `on_suspicious_download.notify` the native host has
+                // already raised a notification for this one.
+                let host_notified = self.settings.on_suspicious_download.notify;
+                if announce && result.is_suspicious && self.settings.notify_suspicious_pdfs && !host_notified {
+                    self.pending_pdf_alerts.push(result.file_path.clone());
+                }
+                self.pdf_scan_results.push_front(result);
+                self.selected_scan_index = self.selected_scan_index.map(|idx| idx + 1);
+            }
+            Err(e) => self.pdf_scan_errors.push_front((file_path, e.to_string())),
+        }
+        self.trim_pdf_results();
+    }
+
+    /// Drops the oldest PDF scans and errors beyond `max_pdf_results`,
+    /// clearing the selection if it was one of them.
+    fn trim_pdf_results(&mut self) {
+        let limit = match self.settings.max_pdf_results {
+            0 => return,
+            limit => limit as usize,
+        };
+        self.pdf_scan_results.truncate(limit);
+        self.pdf_scan_errors.truncate(limit);
+        if self.selected_scan_index.is_some_and(|idx| idx >= limit) {
+            self.selected_scan_index = None;
+        }
+    }
+
+    /// Scans the PDF at `pdf_scan_path` on a background thread; the result
+    /// joins the list like one from the browser.
+    fn start_manual_pdf_scan(&mut self, ctx: &egui::Context) {
+        let path = self.pdf_scan_path.trim().to_string();
+        let (sender, receiver) = mpsc::channel();
+        let ctx = ctx.clone();
+        thread::spawn(move || {
+            let outcome = scan_pdf_for_actions(&path);
+            let _ = sender.send((path, outcome));
+            ctx.request_repaint();
+        });
+        self.manual_pdf_scan = Some(receiver);
+        self.pdf_status = None;
+        self.selected_scan_index = None;
+    }
+
+    /// Announces suspicious PDFs with a desktop notification when the
+    /// window isn't focused, batching bursts into one notification.
+    fn send_pdf_alerts(&mut self, ctx: &egui::Context) {
+        if self.pending_pdf_alerts.is_empty() {
+            return;
+        }
+        if ctx.input(|i| i.viewport().focused) == Some(true) {
+            // They're on screen already.
+            self.pending_pdf_alerts.clear();
+            return;
+        }
+        if let Some(wait) = self.last_pdf_alert.and_then(|last| PDF_ALERT_INTERVAL.checked_sub(last.elapsed())) {
+            ctx.request_repaint_after(wait);
+            return;
+        }
+
+        let body = match self.pending_pdf_alerts.as_slice() {
+            [path] => path.clone(),
+            paths => format!("{} downloads, including {}", paths.len(), paths[0]),
+        };
+        self.pending_pdf_alerts.clear();
+        self.last_pdf_alert = Some(Instant::now());
+        let show_results = Arc::clone(&self.show_pdf_results);
+        let ctx = ctx.clone();
+        notify::send_desktop_notification_with_click("Suspicious PDF downloaded", &body, move || {
+            show_results.store(true, Ordering::Relaxed);
+            ctx.send_viewport_cmd(egui::ViewportCommand::Minimized(false));
+            ctx.send_viewport_cmd(egui::ViewportCommand::Focus);
+            ctx.request_repaint();
+        });
+    }
+
     /// NEW: Draws the toggle buttons to switch between views.
     fn draw_view_switcher(&mut self, ui: &mut egui::Ui) {
         ui.horizontal(|ui| {
+            ui.selectable_value(&mut self.active_view, ActiveView::Dashboard, "🏠 Dashboard");
             ui.selectable_value(&mut self.active_view, ActiveView::ClamAV, "🛡️ ClamAV Scanner");
             ui.selectable_value(&mut self.active_view, ActiveView::PdfScanner, "📄 Live PDF Scans");
+            ui.selectable_value(&mut self.active_view, ActiveView::Iocs, "🧾 IOC Blocklist");
+            ui.selectable_value(&mut self.active_view, ActiveView::Analyzer, "🔎 Script Analyzer");
+            if ui.selectable_value(&mut self.active_view, ActiveView::Quarantine, "☣ Quarantine").clicked() {
+                self.quarantine_entries = quarantine::load_index();
+                self.quarantine_status = None;
+            }
         });
     }
 
@@ -120,19 +448,227 @@ impl BigmanApp {
     fn draw_pdf_scanner_view(&mut self, ui: &mut egui::Ui) {
         ui.heading("Live PDF Download Scanner");
         ui.label("This view automatically displays results for PDFs downloaded while the app is running.");
+        if let Some(ref reason) = self.ipc_unavailable {
+            ui.colored_label(theme::warning(ui), format!("Live PDF scanning unavailable: {}.", reason));
+        }
+        ui.horizontal(|ui| {
+            ui.label("Optional helpers:");
+            for (program, found, hint) in [
+                (PDFID_PROGRAM, self.helpers.pdfid, "Install Didier Stevens' pdfid.py for keyword counts"),
+                (PDFINFO_PROGRAM, self.helpers.pdfinfo, "Install poppler-utils for document metadata"),
+            ] {
+                if found {
+                    ui.colored_label(theme::safe(ui), format!("✓ {}", program));
+                } else {
+                    ui.colored_label(theme::muted(ui), format!("✗ {} not found on PATH", program)).on_hover_text(hint);
+                }
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("Scan a PDF now:");
+            let scanning = self.manual_pdf_scan.is_some();
+            ui.add_enabled(!scanning, egui::TextEdit::singleline(&mut self.pdf_scan_path).hint_text("/path/to/file.pdf"));
+            if ui.add_enabled(!scanning, egui::Button::new("📁 Browse")).clicked() {
+                if let Some(path) = rfd::FileDialog::new().add_filter("PDF", &["pdf"]).pick_file() {
+                    self.pdf_scan_path = path.to_string_lossy().to_string();
+                }
+            }
+            if ui.add_enabled(!scanning && !self.pdf_scan_path.trim().is_empty(), egui::Button::new("🔍 Scan")).clicked() {
+                self.start_manual_pdf_scan(ui.ctx());
+            }
+            if scanning {
+                ui.spinner();
+            }
+        });
 
         ui.horizontal(|ui| {
             if ui.button("Clear Results").clicked() {
                 self.pdf_scan_results.clear();
                 self.selected_scan_index = None;
             }
-            if ui.button("Load Previous Scans").clicked() {
+            if ui.add_enabled(self.scan_loader.is_none(), egui::Button::new("Load Previous Scans")).clicked() {
                 self.load_previous_scans();
             }
+            if ui.button("Compact Scan History").on_hover_text("Archive older saved results into a single compressed file").clicked() {
+                self.pdf_status = Some(match compact_scan_results() {
+                    Ok(0) => "Nothing to compact.".to_string(),
+                    Ok(n) => format!("Archived {} older scan result(s).", n),
+                    Err(e) => format!("Could not compact scan history: {}", e),
+                });
+            }
+        });
+
+        ui.collapsing("When the browser extension flags a download", |ui| {
+            let actions = &mut self.settings.on_suspicious_download;
+            let mut changed = ui.checkbox(&mut actions.quarantine, "Move the file to quarantine").changed();
+            changed |= ui.checkbox(&mut actions.audit_log, "Record it in the audit log").changed();
+            changed |= ui.checkbox(&mut actions.notify, "Show a desktop notification").changed();
+            let host_notifies = actions.notify;
+            changed |= ui
+                .add_enabled(
+                    !host_notifies,
+                    egui::Checkbox::new(&mut self.settings.notify_suspicious_pdfs, "Notify from this window while it isn't focused"),
+                )
+                .on_hover_text("Bursts are grouped into one notification every 10 seconds")
+                .on_disabled_hover_text("Every flagged download already raises a notification")
+                .changed();
+            if changed {
+                self.pdf_status = self.settings.save().err().map(|e| format!("Could not save settings: {}", e));
+            }
+        });
+
+        ui.collapsing("Trusted folders", |ui| {
+            ui.label("Downloads inside these folders are reported clean without being scanned.");
+            let mut changed = false;
+            let mut remove = None;
+            for (idx, folder) in self.settings.trusted_paths.iter().enumerate() {
+                ui.horizontal(|ui| {
+                    ui.monospace(folder);
+                    if ui.small_button("✖").clicked() {
+                        remove = Some(idx);
+                    }
+                });
+            }
+            if let Some(idx) = remove {
+                self.settings.trusted_paths.remove(idx);
+                changed = true;
+            }
+            if ui.button("➕ Add Folder...").clicked() {
+                if let Some(folder) = rfd::FileDialog::new().pick_folder() {
+                    self.settings.trusted_paths.push(folder.display().to_string());
+                    changed = true;
+                }
+            }
+            if changed {
+                self.pdf_status = self.settings.save().err().map(|e| format!("Could not save settings: {}", e));
+            }
         });
 
+        ui.collapsing("Audit log and scan history retention", |ui| {
+            ui.label("Full files are compressed and set aside; only set-aside copies are deleted. 0 means no limit.");
+            let policy = &mut self.settings.retention;
+            let mut changed = false;
+            egui::Grid::new("retention_grid").num_columns(2).show(ui, |ui| {
+                ui.label("Set aside when larger than (MB):");
+                changed |= ui.add(egui::DragValue::new(&mut policy.rotate_at_mb).range(0..=10_000)).changed();
+                ui.end_row();
+                ui.label("Delete set-aside files older than (days):");
+                changed |= ui.add(egui::DragValue::new(&mut policy.max_age_days).range(0..=3650)).changed();
+                ui.end_row();
+                ui.label("Keep at most (MB per store):");
+                changed |= ui.add(egui::DragValue::new(&mut policy.max_total_mb).range(0..=100_000)).changed();
+                ui.end_row();
+            });
+            if changed {
+                self.pdf_status = self.settings.save().err().map(|e| format!("Could not save settings: {}", e));
+            }
+            if ui.button("Apply Now").clicked() {
+                let actions = retention::enforce(&self.settings.retention);
+                self.pdf_status = Some(if actions.is_empty() {
+                    "Nothing needed rotating or pruning.".to_string()
+                } else {
+                    actions.join("\n")
+                });
+            }
+        });
+
+        ui.collapsing("Temporary files and size limits", |ui| {
+            ui.label("PDFs found inside archives are unpacked into a private folder here while they are checked, then deleted.");
+            ui.horizontal(|ui| {
+                ui.label("Temp directory:");
+                let changed = ui
+                    .add(egui::TextEdit::singleline(&mut self.settings.temp_dir).hint_text("System default"))
+                    .changed();
+                let browsed = ui.button("Browse...").clicked()
+                    && rfd::FileDialog::new().pick_folder().is_some_and(|folder| {
+                        self.settings.temp_dir = folder.display().to_string();
+                        true
+                    });
+                if changed || browsed {
+                    self.pdf_status = self.settings.save().err().map(|e| format!("Could not save settings: {}", e));
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label("Scans kept in this list (0 for no limit):");
+                if ui.add(egui::DragValue::new(&mut self.settings.max_pdf_results).range(0..=1_000_000)).changed() {
+                    self.trim_pdf_results();
+                    self.pdf_status = self.settings.save().err().map(|e| format!("Could not save settings: {}", e));
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label("Largest PDF to scan (MB, 0 for no limit):");
+                if ui.add(egui::DragValue::new(&mut self.settings.max_pdf_scan_mb).range(0..=100_000)).changed() {
+                    self.pdf_status = self.settings.save().err().map(|e| format!("Could not save settings: {}", e));
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label("Largest other file to scan (MB, 0 for no limit):");
+                if ui.add(egui::DragValue::new(&mut self.settings.max_scan_mb).range(0..=100_000)).changed() {
+                    self.pdf_status = self.settings.save().err().map(|e| format!("Could not save settings: {}", e));
+                }
+            });
+        });
+
+        if let Some(ref loader) = self.scan_loader {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                let progress = if loader.total == 0 { 0.0 } else { loader.files_done as f32 / loader.total as f32 };
+                ui.add(egui::ProgressBar::new(progress)
+                    .desired_width(250.0)
+                    .text(format!("Loading previous scans... {}/{} files", loader.files_done, loader.total)));
+            });
+        }
+
+        if let Some(ref status) = self.pdf_status {
+            ui.label(status);
+        }
+
+        if !self.pdf_scan_errors.is_empty() {
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.label(format!("{} download(s) could not be scanned:", self.pdf_scan_errors.len()));
+                if ui.small_button("Dismiss").clicked() {
+                    self.pdf_scan_errors.clear();
+                }
+            });
+            for (file_path, error) in &self.pdf_scan_errors {
+                ui.colored_label(theme::muted(ui), format!("{}: {}", file_path, error));
+            }
+        }
+
+        self.draw_feedback_adjustments(ui);
+
         ui.separator();
 
+        ui.horizontal(|ui| {
+            ui.label("Filter:");
+            ui.add(egui::TextEdit::singleline(&mut self.pdf_filter).hint_text("Path or reason"));
+            ui.checkbox(&mut self.pdf_suspicious_only, "Suspicious only");
+        });
+        let filter = self.pdf_filter.trim().to_lowercase();
+        let suspicious_only = self.pdf_suspicious_only;
+        let is_shown = |result: &PdfScanResult| pdf_result_matches(result, &filter, suspicious_only);
+        if ui
+            .add_enabled(self.pdf_scan_results.iter().any(is_shown), egui::Button::new("📄 Export CSV"))
+            .on_hover_text("Save the scans listed below as a spreadsheet")
+            .clicked()
+        {
+            let shown: Vec<&PdfScanResult> = self.pdf_scan_results.iter().filter(|result| is_shown(result)).collect();
+            if let Some(status) = export_pdf_scans_csv(&shown) {
+                self.pdf_status = Some(status);
+            }
+        }
+        // Don't keep showing details for a scan the list no longer shows.
+        if self.selected_scan_index.and_then(|idx| self.pdf_scan_results.get(idx)).is_some_and(|result| !is_shown(result)) {
+            self.selected_scan_index = None;
+        }
+
+        // A verdict clicked in the details panel, applied once the panel is drawn.
+        let mut verdict: Option<(String, Verdict)> = None;
+        let mut notes_error = None;
+        let mut sort_by_count = self.pdfid_sort_by_count;
+
         // Split view: List on left, details on right
         ui.columns(2, |columns| {
             // Left column: List of scans
@@ -140,9 +676,11 @@ impl BigmanApp {
             egui::ScrollArea::vertical().id_source("pdf_scan_list").show(&mut columns[0], |ui| {
                 if self.pdf_scan_results.is_empty() {
                     ui.label("No PDFs scanned yet. Download a PDF to see results here.");
+                } else if !self.pdf_scan_results.iter().any(is_shown) {
+                    ui.label("No scans match the filter.");
                 } else {
-                    for (idx, result) in self.pdf_scan_results.iter().enumerate() {
-                        let color = if result.is_suspicious { egui::Color32::RED } else { egui::Color32::GREEN };
+                    for (idx, result) in self.pdf_scan_results.iter().enumerate().filter(|(_, result)| is_shown(result)) {
+                        let color = if result.is_suspicious { theme::danger(ui) } else { theme::safe(ui) };
 
                         let is_selected = self.selected_scan_index == Some(idx);
                         let response = ui.selectable_label(is_selected, format!("{} - {}",
@@ -155,6 +693,7 @@ impl BigmanApp {
                         }
 
                         ui.colored_label(color, &result.file_path);
+                        ui.small(format_timestamp(result.timestamp));
                         ui.separator();
                     }
                 }
@@ -164,27 +703,85 @@ impl BigmanApp {
             columns[1].label("Scan Details:");
             egui::ScrollArea::vertical().id_source("pdf_scan_details").show(&mut columns[1], |ui| {
                 if let Some(idx) = self.selected_scan_index {
-                    if let Some(result) = self.pdf_scan_results.get(idx) {
+                    if let Some(result) = self.pdf_scan_results.get_mut(idx) {
                         ui.heading("Scan Information");
                         ui.monospace(format!("Scan ID: {}", result.scan_id));
                         ui.monospace(format!("File: {}", result.file_path));
-                        ui.monospace(format!("Timestamp: {}", result.timestamp));
+                        ui.monospace(format!("Timestamp: {}", format_timestamp(result.timestamp)));
+                        if !result.detected_type.is_empty() {
+                            ui.monospace(format!("Detected type: {}", result.detected_type));
+                        }
                         ui.colored_label(
-                            if result.is_suspicious { egui::Color32::RED } else { egui::Color32::GREEN },
+                            if result.is_suspicious { theme::danger(ui) } else { theme::safe(ui) },
                             format!("Status: {}", result.reason)
                         );
+                        ui.monospace(format!("Risk score: {}", result.risk_score));
+
+                        if !result.findings.is_empty() {
+                            ui.separator();
+                            ui.label("Findings (your verdicts tune the weight of future detections):");
+                            for finding in &result.findings {
+                                ui.horizontal_wrapped(|ui| {
+                                    ui.monospace(format!("[{}] {}", finding.weight, finding.description));
+                                    if ui.small_button("👎 False positive").clicked() {
+                                        verdict = Some((finding.rule.clone(), Verdict::FalsePositive));
+                                    }
+                                    if ui.small_button("👍 Confirmed").clicked() {
+                                        verdict = Some((finding.rule.clone(), Verdict::TruePositive));
+                                    }
+                                });
+                            }
+                        }
+
+                        ui.separator();
+                        ui.label("Notes:");
+                        let notes = ui.add(egui::TextEdit::multiline(&mut result.notes)
+                            .desired_rows(3)
+                            .desired_width(f32::INFINITY)
+                            .hint_text("e.g. confirmed malicious, reported to IT, ticket #123"));
+                        if notes.changed() {
+                            notes_error = save_scan_notes(result).err().map(|e| format!("Could not save notes: {}", e));
+                        }
 
                         ui.separator();
 
-                        if let Some(ref pdfid) = result.pdfid_output {
-                            ui.collapsing("PDFiD Output", |ui| {
-                                ui.monospace(pdfid);
+                        if !result.pdfid_counts.is_empty() {
+                            ui.collapsing("PDFiD Keyword Counts", |ui| {
+                                let mut counts: Vec<(&String, &u32)> = result.pdfid_counts.iter().collect();
+                                if sort_by_count {
+                                    counts.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+                                } else {
+                                    counts.sort();
+                                }
+                                egui::Grid::new("pdfid_counts").num_columns(2).striped(true).show(ui, |ui| {
+                                    if ui.selectable_label(!sort_by_count, "Keyword").clicked() {
+                                        sort_by_count = false;
+                                    }
+                                    if ui.selectable_label(sort_by_count, "Count").clicked() {
+                                        sort_by_count = true;
+                                    }
+                                    ui.end_row();
+                                    for (keyword, count) in counts {
+                                        let color = if *count > 0 { theme::danger(ui) } else { theme::muted(ui) };
+                                        ui.colored_label(color, keyword);
+                                        ui.colored_label(color, count.to_string());
+                                        ui.end_row();
+                                    }
+                                });
                             });
                         }
 
-                        if let Some(ref metadata) = result.metadata {
-                            ui.collapsing("PDF Metadata", |ui| {
-                                ui.monospace(metadata);
+                        for (title, program, output) in [
+                            ("PDFiD Output", PDFID_PROGRAM, &result.pdfid_output),
+                            ("PDF Metadata", PDFINFO_PROGRAM, &result.metadata),
+                        ] {
+                            ui.collapsing(title, |ui| match output.text() {
+                                Some(text) if !text.trim().is_empty() => {
+                                    ui.monospace(text);
+                                }
+                                _ => {
+                                    ui.colored_label(theme::muted(ui), output.status(program));
+                                }
                             });
                         }
                     }
@@ -193,38 +790,477 @@ impl BigmanApp {
                 }
             });
         });
+
+        if let Some((rule, verdict)) = verdict {
+            self.feedback.record(&rule, verdict);
+            self.save_feedback();
+        }
+        if notes_error.is_some() {
+            self.pdf_status = notes_error;
+        }
+        self.pdfid_sort_by_count = sort_by_count;
+    }
+
+    /// Lists the per-rule weight adjustments learned from the user's verdicts.
+    fn draw_feedback_adjustments(&mut self, ui: &mut egui::Ui) {
+        if self.feedback.is_empty() {
+            return;
+        }
+
+        let mut reset_rule = None;
+        let mut reset_all = false;
+        ui.collapsing("Feedback adjustments", |ui| {
+            egui::Grid::new("feedback_adjustments").striped(true).show(ui, |ui| {
+                ui.strong("Rule");
+                ui.strong("False positives");
+                ui.strong("Confirmed");
+                ui.strong("Weight factor");
+                ui.label("");
+                ui.end_row();
+
+                for (rule, counts) in self.feedback.entries() {
+                    ui.monospace(rule);
+                    ui.label(counts.false_positives.to_string());
+                    ui.label(counts.true_positives.to_string());
+                    ui.label(format!("{:.2}x", f64::from(counts.adjust(100)) / 100.0));
+                    if ui.small_button("Reset").clicked() {
+                        reset_rule = Some(rule.clone());
+                    }
+                    ui.end_row();
+                }
+            });
+            if ui.button("Reset All").clicked() {
+                reset_all = true;
+            }
+            if let Some(ref status) = self.feedback_status {
+                ui.colored_label(theme::warning(ui), status);
+            }
+        });
+
+        if let Some(rule) = reset_rule {
+            self.feedback.reset_rule(&rule);
+            self.save_feedback();
+        }
+        if reset_all {
+            self.feedback.reset();
+            self.save_feedback();
+        }
+    }
+
+    fn save_feedback(&mut self) {
+        self.feedback_status = self.feedback.save().err().map(|e| format!("Could not save feedback: {}", e));
+    }
+
+    /// Draws the UI for importing indicators of compromise from a threat report.
+    fn draw_ioc_view(&mut self, ui: &mut egui::Ui) {
+        ui.heading("IOC Blocklist");
+        ui.label("Paste hashes, domains, IPs and URLs from a threat report. Downloaded PDFs and analyzed scripts are checked against this list.");
+
+        ui.horizontal_wrapped(|ui| {
+            for (kind, count) in self.ioc_list.counts() {
+                ui.monospace(format!("{}: {}", kind, count));
+            }
+        });
+
+        ui.horizontal(|ui| {
+            if ui.button("📋 Paste from Clipboard").clicked() {
+                match arboard::Clipboard::new().and_then(|mut clipboard| clipboard.get_text()) {
+                    Ok(text) => {
+                        self.ioc_input = text;
+                        self.ioc_status = None;
+                    }
+                    Err(e) => self.ioc_status = Some(format!("Could not read the clipboard: {}", e)),
+                }
+            }
+            if ui.add_enabled(!self.ioc_input.trim().is_empty(), egui::Button::new("➕ Import")).clicked() {
+                self.import_iocs();
+            }
+            if ui.add_enabled(!self.ioc_list.is_empty(), egui::Button::new("🗑 Clear Blocklist")).clicked() {
+                self.ioc_list = IocList::default();
+                self.ioc_import_summary = None;
+                self.ioc_status = self.ioc_list.save().err().map(|e| format!("Could not save the blocklist: {}", e));
+            }
+        });
+
+        if let Some(ref status) = self.ioc_status {
+            ui.colored_label(theme::warning(ui), status);
+        }
+
+        egui::ScrollArea::vertical().max_height(200.0).id_source("ioc_input").show(ui, |ui| {
+            ui.add(egui::TextEdit::multiline(&mut self.ioc_input)
+                .code_editor()
+                .desired_width(f32::INFINITY)
+                .hint_text("One or more indicators per line. Defanged forms like hxxp:// and evil[.]com are accepted."));
+        });
+
+        if let Some(ref summary) = self.ioc_import_summary {
+            ui.separator();
+            ui.label(format!("Imported {} new indicator(s), skipped {} duplicate(s).", summary.total_added(), summary.duplicates));
+            for (kind, count) in &summary.added {
+                ui.monospace(format!("  {}: {}", kind, count));
+            }
+            if !summary.failed_lines.is_empty() {
+                ui.colored_label(theme::danger(ui), format!("{} line(s) could not be parsed:", summary.failed_lines.len()));
+                egui::ScrollArea::vertical().max_height(150.0).id_source("ioc_failed_lines").show(ui, |ui| {
+                    for line in &summary.failed_lines {
+                        ui.monospace(line);
+                    }
+                });
+            }
+        }
+    }
+
+    /// Lists quarantined files, each of which can be restored or deleted.
+    fn draw_quarantine_view(&mut self, ui: &mut egui::Ui) {
+        ui.heading("Quarantine");
+        ui.label("Files moved aside by a scan instead of being deleted. Restore a false positive, or delete the rest for good.");
+
+        if ui.button("🔄 Refresh").clicked() {
+            self.quarantine_entries = quarantine::load_index();
+            self.quarantine_status = None;
+        }
+        if let Some(ref status) = self.quarantine_status {
+            ui.colored_label(theme::warning(ui), status);
+        }
+        if self.quarantine_entries.is_empty() {
+            ui.label("Nothing is quarantined.");
+            return;
+        }
+
+        let mut action = None;
+        egui::ScrollArea::vertical().id_source("quarantine_entries").show(ui, |ui| {
+            egui::Grid::new("quarantine_grid").striped(true).num_columns(3).show(ui, |ui| {
+                ui.strong("Original location");
+                ui.strong("Reason");
+                ui.label("");
+                ui.end_row();
+                for (idx, entry) in self.quarantine_entries.iter().enumerate() {
+                    ui.label(&entry.original_path).on_hover_text(format!("Stored at {}", entry.quarantined_path));
+                    ui.monospace(&entry.reason);
+                    ui.horizontal(|ui| {
+                        if ui.button("↩ Restore").clicked() {
+                            action = Some((idx, false));
+                        }
+                        if ui.button("🗑 Delete").on_hover_text("Delete the file permanently").clicked() {
+                            action = Some((idx, true));
+                        }
+                    });
+                    ui.end_row();
+                }
+            });
+        });
+
+        if let Some((idx, delete)) = action {
+            let entry = self.quarantine_entries[idx].clone();
+            let (verb, done, result) = if delete {
+                ("delete", "Deleted", quarantine::delete(&entry))
+            } else {
+                ("restore", "Restored", quarantine::restore(&entry))
+            };
+            self.quarantine_status = Some(match result {
+                Ok(()) => {
+                    let _ = audit::record("quarantine", &format!("{}d", verb), &entry.original_path, &entry.quarantined_path);
+                    format!("{} {}.", done, entry.original_path)
+                }
+                Err(e) => format!("Could not {} {}: {}", verb, entry.original_path, e),
+            });
+            self.quarantine_entries = quarantine::load_index();
+        }
+    }
+
+    /// Draws the UI for analyzing a pasted command or script.
+    fn draw_analyzer_view(&mut self, ui: &mut egui::Ui) {
+        ui.heading("Script Analyzer");
+        ui.label("Paste a command or install script to see what it would do before running it.");
+
+        ui.horizontal(|ui| {
+            if ui.add_enabled(!self.analyzer_input.trim().is_empty(), egui::Button::new("🔎 Analyze")).clicked() {
+                self.url_reputations.clear();
+                match analyze_content(&self.analyzer_input) {
+                    Ok(result) => {
+                        self.analyzer_result = Some(result);
+                        self.analyzer_status = None;
+                    }
+                    Err(e) => {
+                        self.analyzer_result = None;
+                        self.analyzer_status = Some(format!("Analysis failed: {}", e));
+                    }
+                }
+            }
+            if ui.button("✔ Validate Rules File...").on_hover_text("Check a .toml rules file or IOC list compiles, without scanning").clicked() {
+                self.validate_rules_file();
+            }
+            if ui.button("Clear").clicked() {
+                self.rules_validation = None;
+                self.analyzer_input.clear();
+                self.analyzer_result = None;
+                self.analyzer_status = None;
+                self.url_reputations.clear();
+            }
+        });
+
+        if let Some(ref status) = self.analyzer_status {
+            ui.colored_label(theme::warning(ui), status);
+        }
+
+        if let Some((ref name, ref validation)) = self.rules_validation {
+            let color = if validation.errors.is_empty() { theme::safe(ui) } else { theme::danger(ui) };
+            ui.colored_label(color, format!(
+                "{}: {} rule(s) loaded, {} error(s), {} warning(s)",
+                name,
+                validation.loaded,
+                validation.errors.len(),
+                validation.warnings.len()
+            ));
+            for error in &validation.errors {
+                ui.colored_label(theme::danger(ui), format!("  {}", error));
+            }
+            for warning in &validation.warnings {
+                ui.colored_label(theme::warning(ui), format!("  {}", warning));
+            }
+        }
+
+        egui::ScrollArea::vertical().max_height(200.0).id_source("analyzer_input").show(ui, |ui| {
+            ui.add(egui::TextEdit::multiline(&mut self.analyzer_input)
+                .code_editor()
+                .desired_width(f32::INFINITY)
+                .hint_text("curl -fsSL https://example.com/install.sh | sh"));
+        });
+
+        let Some(ref result) = self.analyzer_result else {
+            return;
+        };
+        ui.separator();
+        if result.is_safe {
+            ui.colored_label(theme::safe(ui), "✅ No dangerous patterns or secrets found.");
+            return;
+        }
+
+        let mut lookups = Vec::new();
+        egui::ScrollArea::vertical().id_source("analyzer_threats").show(ui, |ui| {
+            if !result.secrets.is_empty() {
+                ui.strong("🔑 Possible secrets (values are masked)");
+                for secret in &result.secrets {
+                    ui.horizontal_wrapped(|ui| {
+                        ui.colored_label(theme::warning(ui), format!("[{}]", secret.severity));
+                        ui.label(secret.to_string());
+                        ui.small(format!("ⓘ {}", secret.rule));
+                    });
+                }
+                ui.separator();
+            }
+            for threat in &result.threats {
+                let color = match threat.severity {
+                    Severity::Critical | Severity::High => theme::danger(ui),
+                    Severity::Medium => theme::warning(ui),
+                    Severity::Low => ui.visuals().text_color(),
+                    Severity::Info => theme::muted(ui),
+                };
+                ui.horizontal_wrapped(|ui| {
+                    ui.colored_label(color, format!("[{}]", threat.severity));
+                    let response = ui.label(&threat.description);
+                    if let Some(rule) = find_rule(threat.rule) {
+                        ui.small(format!("ⓘ {}", rule.id)).union(response).on_hover_ui(|ui| {
+                            ui.set_max_width(400.0);
+                            ui.strong(rule.id);
+                            ui.label(format!("Category: {}   Severity: {}", rule.category, rule.severity));
+                            if let Some(pattern) = rule.pattern {
+                                ui.monospace(format!("Pattern: {}", pattern));
+                            }
+                            ui.separator();
+                            ui.label(format!("Why it matters: {}", rule.rationale));
+                            ui.label(format!("What to do: {}", rule.remediation));
+                        });
+                    }
+                });
+                ui.add(egui::Label::new(egui::RichText::new(&threat.matched).monospace()).selectable(true));
+
+                // URLs are shown as plain selectable text, never as links, so
+                // a stray click can't open a malicious page.
+                for url in find_urls(&threat.matched) {
+                    ui.horizontal(|ui| {
+                        ui.label("🌐");
+                        ui.add(egui::Label::new(egui::RichText::new(&url).monospace()).selectable(true));
+                        if ui.small_button("📋 Copy").clicked() {
+                            ui.output_mut(|o| o.copied_text = url.clone());
+                        }
+                        if ui.small_button("🔍 Check reputation").clicked() {
+                            lookups.push(url.clone());
+                        }
+                    });
+                    if let Some(reputation) = self.url_reputations.get(&url) {
+                        let host = reputation.host.as_deref().unwrap_or("(unparseable host)");
+                        if reputation.matches.is_empty() {
+                            ui.label(format!("    Resolves to host {} — not on the blocklist.", host));
+                        } else {
+                            ui.colored_label(theme::danger(ui), format!("    Resolves to host {} — blocklisted:", host));
+                            for ioc in &reputation.matches {
+                                ui.colored_label(theme::danger(ui), format!("      {}", ioc));
+                            }
+                        }
+                    }
+                }
+                ui.add_space(4.0);
+            }
+        });
+
+        for url in lookups {
+            let reputation = self.ioc_list.check_url(&url);
+            self.url_reputations.insert(url, reputation);
+        }
+    }
+
+    /// Lets the user pick a rules file and checks it without scanning anything.
+    fn validate_rules_file(&mut self) {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("Rules", &["toml", "json", "txt"])
+            .pick_file()
+        else {
+            return;
+        };
+
+        match validate_ruleset(&path) {
+            Ok(validation) => {
+                self.analyzer_status = None;
+                self.rules_validation = Some((path.display().to_string(), validation));
+            }
+            Err(e) => {
+                self.rules_validation = None;
+                self.analyzer_status = Some(format!("❌ {:#}", e));
+            }
+        }
+    }
+
+    /// Adds the pasted indicators to the blocklist and saves it.
+    fn import_iocs(&mut self) {
+        let summary = self.ioc_list.import_text(&self.ioc_input);
+        self.ioc_status = self.ioc_list.save().err().map(|e| format!("Could not save the blocklist: {}", e));
+        if summary.failed_lines.is_empty() {
+            self.ioc_input.clear();
+        }
+        self.ioc_import_summary = Some(summary);
     }
 
     fn load_previous_scans(&mut self) {
-        use std::fs;
-        use std::path::Path;
-
-        let scan_dir = Path::new("pdf_scans");
-        if scan_dir.exists() {
-            if let Ok(entries) = fs::read_dir(scan_dir) {
-                for entry in entries.flatten() {
-                    if let Some(ext) = entry.path().extension() {
-                        if ext == "json" {
-                            if let Ok(content) = fs::read_to_string(entry.path()) {
-                                if let Ok(result) = serde_json::from_str::<PdfScanResult>(&content) {
-                                    // Check if not already in list
-                                    if !self.pdf_scan_results.iter().any(|r| r.scan_id == result.scan_id) {
-                                        self.pdf_scan_results.push(result);
+        self.scan_loader = Some(ScanLoader {
+            receiver: load_scan_results_in_background(),
+            files_done: 0,
+            total: 0,
+            known_ids: self.pdf_scan_results.iter().map(|r| (r.dedupe_key().to_string(), false)).collect(),
+        });
+    }
+
+    /// Merges any results the background loader has produced since the last frame.
+    fn check_scan_loader(&mut self, ctx: &egui::Context) {
+        let Some(ref mut loader) = self.scan_loader else {
+            return;
+        };
+
+        let mut added = false;
+        let mut finished = false;
+        loop {
+            match loader.receiver.try_recv() {
+                Ok(ScanLoadProgress::Started { total }) => loader.total = total,
+                Ok(ScanLoadProgress::Batch { results, files_done, from_archive }) => {
+                    loader.files_done += files_done;
+                    for result in results {
+                        let key = result.dedupe_key().to_string();
+                        match loader.known_ids.get(&key) {
+                            None => {
+                                loader.known_ids.insert(key, from_archive);
+                                self.pdf_scan_results.push_back(result);
+                                added = true;
+                            }
+                            // The same file scanned again: keep the newest scan, and
+                            // let a loose copy of an archived result replace it.
+                            Some(&was_archived) => {
+                                if let Some(existing) = self.pdf_scan_results.iter_mut().find(|r| r.dedupe_key() == key) {
+                                    if was_archived || result.timestamp > existing.timestamp {
+                                        *existing = result;
+                                        loader.known_ids.insert(key, from_archive);
+                                        added = true;
                                     }
                                 }
                             }
                         }
                     }
                 }
-                // Sort by timestamp, newest first
-                self.pdf_scan_results.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+                Ok(ScanLoadProgress::Finished) | Err(mpsc::TryRecvError::Disconnected) => {
+                    finished = true;
+                    break;
+                }
+                Err(mpsc::TryRecvError::Empty) => break,
             }
         }
+
+        if added {
+            // Sort by timestamp, newest first, keeping the same scan selected
+            let selected_id = self.selected_scan_index
+                .and_then(|idx| self.pdf_scan_results.get(idx))
+                .map(|r| r.scan_id.clone());
+            self.pdf_scan_results.make_contiguous().sort_by_key(|r| std::cmp::Reverse(r.timestamp));
+            self.selected_scan_index = selected_id
+                .and_then(|id| self.pdf_scan_results.iter().position(|r| r.scan_id == id));
+            self.trim_pdf_results();
+        }
+
+        if finished {
+            self.scan_loader = None;
+        } else {
+            ctx.request_repaint();
+        }
+    }
+
+    /// Draws the landing view: a tile per area with its current state, each
+    /// of which opens the view behind it.
+    fn draw_dashboard_view(&mut self, ui: &mut egui::Ui) {
+        ui.heading("Dashboard");
+
+        let clamav_scan = match (&self.scan_task, &self.scan_report) {
+            (Task::InProgress(_), _) => "Scanning...".to_string(),
+            (_, Some(report)) if report.summary.is_present() => format!(
+                "{} infected of {} scanned",
+                report.summary.infected_files.unwrap_or(0),
+                report.summary.scanned_files.unwrap_or(0)
+            ),
+            (Task::Complete(_), _) => "Finished without a summary".to_string(),
+            _ => "No scan yet".to_string(),
+        };
+        let threats = self.threat_summary();
+        let live_pdf = match self.ipc_unavailable {
+            Some(_) => "Unavailable",
+            None => "Listening",
+        };
+
+        let tiles = [
+            ("📄 PDFs scanned this session", self.session_pdf_scans.to_string(), false, ActiveView::PdfScanner),
+            ("⚠ Suspicious PDFs this session", self.session_suspicious_pdfs.to_string(), self.session_suspicious_pdfs > 0, ActiveView::PdfScanner),
+            ("🛡️ Last ClamAV scan", clamav_scan, threats.infected_files > 0, ActiveView::ClamAV),
+            ("☣ Quarantined files", self.quarantine_entries.len().to_string(), false, ActiveView::Quarantine),
+            ("🔎 Script threats", threats.script_threats.to_string(), threats.script_threats > 0, ActiveView::Analyzer),
+            ("📡 Live PDF scanning", live_pdf.to_string(), self.ipc_unavailable.is_some(), ActiveView::PdfScanner),
+            ("💾 USB drives", usb_scan_mode_label(self.settings.usb_scan_mode).to_string(), false, ActiveView::ClamAV),
+        ];
+
+        ui.horizontal_wrapped(|ui| {
+            for (title, value, alarming, view) in tiles {
+                let value = egui::RichText::new(value).heading().color(if alarming { theme::danger(ui) } else { ui.visuals().strong_text_color() });
+                let mut text = egui::text::LayoutJob::default();
+                egui::RichText::new(format!("{}\n", title)).append_to(&mut text, ui.style(), egui::FontSelection::Default, egui::Align::Min);
+                value.append_to(&mut text, ui.style(), egui::FontSelection::Default, egui::Align::Min);
+                if ui.add(egui::Button::new(text).min_size(egui::vec2(220.0, 70.0))).clicked() {
+                    self.active_view = view;
+                }
+            }
+        });
     }
 
     /// Draws the main header and zoom controls.
-    fn draw_header_and_zoom(&self, ui: &mut egui::Ui, ctx: &egui::Context) {
-        ui.heading("⚔ BigMan Antivirus Scanner");
+    fn draw_header_and_zoom(&mut self, ui: &mut egui::Ui, ctx: &egui::Context) {
+        ui.horizontal(|ui| {
+            ui.heading("⚔ BigMan Antivirus Scanner");
+            self.draw_threat_indicator(ui);
+        });
         ui.horizontal(|ui| {
             ui.label("Zoom:");
             if ui.button("-").clicked() {
@@ -235,6 +1271,101 @@ impl BigmanApp {
                 ctx.set_pixels_per_point((ctx.pixels_per_point() + 0.1).min(3.0));
             }
             ui.label("(Use Ctrl +/- or Ctrl+Scroll)");
+
+            ui.separator();
+            ui.label("Theme:");
+            egui::ComboBox::from_id_source("theme")
+                .selected_text(self.theme.label())
+                .show_ui(ui, |ui| {
+                    for theme in [ThemePreference::System, ThemePreference::Light, ThemePreference::Dark] {
+                        ui.selectable_value(&mut self.theme, theme, theme.label());
+                    }
+                });
+
+            ui.separator();
+            if ui.button("📤 Export Config").clicked() {
+                self.export_config();
+            }
+            if ui.button("📥 Import Config").clicked() {
+                self.import_config();
+            }
+        });
+
+        if let Some(ref status) = self.config_status {
+            ui.label(status);
+        }
+    }
+
+    /// Counts what is still flagged in memory: infected files from the last
+    /// ClamAV scan, suspicious PDFs, and high-severity script analyzer hits.
+    fn threat_summary(&self) -> ThreatSummary {
+        let infected_files = self
+            .scan_report
+            .as_ref()
+            .map_or(0, |report| report.summary.infected_files.unwrap_or(report.detections.len() as u64));
+        let script_threats = self.analyzer_result.as_ref().map_or(0, |result| {
+            result.threats.iter().filter(|threat| threat.severity >= Severity::High).count()
+        });
+        ThreatSummary {
+            infected_files,
+            suspicious_pdfs: self.pdf_scan_results.iter().filter(|result| result.is_suspicious).count(),
+            script_threats,
+        }
+    }
+
+    /// Shows the unresolved threat count next to the title, with a link per
+    /// source that jumps to the view holding those threats.
+    fn draw_threat_indicator(&mut self, ui: &mut egui::Ui) {
+        let summary = self.threat_summary();
+        if summary.total() == 0 {
+            ui.label(egui::RichText::new("✔ No active threats").color(theme::safe(ui)));
+            return;
+        }
+
+        ui.label(egui::RichText::new(format!("⚠ {} unresolved:", summary.total())).color(theme::danger(ui)).strong());
+        if summary.infected_files > 0 && ui.link(format!("{} infected", summary.infected_files)).clicked() {
+            self.active_view = ActiveView::ClamAV;
+        }
+        if summary.suspicious_pdfs > 0 && ui.link(format!("{} suspicious PDF(s)", summary.suspicious_pdfs)).clicked() {
+            self.active_view = ActiveView::PdfScanner;
+            self.pdf_filter.clear();
+            self.selected_scan_index = self.pdf_scan_results.iter().position(|result| result.is_suspicious);
+        }
+        if summary.script_threats > 0 && ui.link(format!("{} script threat(s)", summary.script_threats)).clicked() {
+            self.active_view = ActiveView::Analyzer;
+        }
+    }
+
+    /// Saves the current settings and blocklist to a user-chosen file.
+    fn export_config(&mut self) {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("JSON", &["json"])
+            .set_file_name("bigman-config.json")
+            .save_file()
+        else {
+            return;
+        };
+
+        self.config_status = Some(match export_config(&path, &self.settings) {
+            Ok(()) => format!("Configuration exported to {}", path.display()),
+            Err(e) => format!("❌ Export failed: {:#}", e),
+        });
+    }
+
+    /// Replaces the current settings and blocklist with an exported bundle.
+    fn import_config(&mut self) {
+        let Some(path) = rfd::FileDialog::new().add_filter("JSON", &["json"]).pick_file() else {
+            return;
+        };
+
+        self.config_status = Some(match import_config(&path) {
+            Ok(bundle) => {
+                self.settings = bundle.settings;
+                self.ioc_list = bundle.iocs;
+                let _ = self.settings.save();
+                format!("Configuration imported from {}", path.display())
+            }
+            Err(e) => format!("❌ Import failed: {:#}", e),
         });
     }
 
@@ -253,9 +1384,49 @@ impl BigmanApp {
             if let Task::InProgress(_) = self.update_task {
                 ui.spinner();
                 ui.label("Updating...");
+                if let Some((stream, _)) = &self.update_stream {
+                    if ui.add_enabled(!stream.is_cancelled(), egui::Button::new("✖ Cancel")).clicked() {
+                        stream.cancel();
+                    }
+                }
             }
         });
 
+        if let Some((stream, progress)) = &mut self.update_stream {
+            while let Ok(line) = stream.lines.try_recv() {
+                progress.update(&line);
+            }
+            let downloading = progress.database.as_deref().unwrap_or("Checking for updates");
+            match progress.percent {
+                Some(percent) => {
+                    ui.add(egui::ProgressBar::new(percent / 100.0)
+                        .desired_width(300.0)
+                        .text(format!("{}: {:.0}%", downloading, percent)));
+                }
+                None => {
+                    ui.label(downloading);
+                }
+            }
+            ui.small(&progress.last_line);
+        }
+
+        if let Some(report) = &self.freshclam_report {
+            if !report.databases.is_empty() || !report.errors.is_empty() {
+                ui.label(report.describe());
+                for db in &report.databases {
+                    let state = match db.state {
+                        DatabaseState::Updated => "updated",
+                        DatabaseState::UpToDate => "up to date",
+                    };
+                    let version = db.version.map_or_else(String::new, |version| format!(" (version {})", version));
+                    ui.label(format!("  {} {}{}", db.name, state, version));
+                }
+                for error in &report.errors {
+                    ui.colored_label(theme::danger(ui), format!("  {}", error));
+                }
+            }
+        }
+
         if let Task::Complete(result) = &self.update_task {
             ui.add_space(5.0);
             ui.label("Last Update Result:");
@@ -266,7 +1437,7 @@ impl BigmanApp {
             // Provide a helpful, non-intrusive tip for a very common configuration error.
             if result.contains("Can't open/parse the config file /etc/freshclam.conf") {
                 ui.add_space(5.0);
-                ui.colored_label(egui::Color32::YELLOW, "! Tip: This error often requires running `sudo freshclam` once to fix permissions, or commenting out the 'Example' line in /etc/freshclam.conf.");
+                ui.colored_label(theme::warning(ui), "! Tip: This error often requires running `sudo freshclam` once to fix permissions, or commenting out the 'Example' line in /etc/freshclam.conf.");
             }
         }
     }
@@ -280,67 +1451,451 @@ impl BigmanApp {
         // --- Scan Path ---
         ui.horizontal(|ui| {
             ui.label("Path to scan:");
-            ui.add_enabled(!is_task_running, egui::TextEdit::singleline(&mut self.scan_path));
+            ui.add_enabled(!is_task_running, egui::TextEdit::singleline(&mut self.settings.scan_path));
             if ui.add_enabled(!is_task_running, egui::Button::new("📁 Browse")).clicked() {
                 if let Some(path) = rfd::FileDialog::new().pick_folder() {
-                    self.scan_path = path.to_string_lossy().to_string();
+                    self.settings.scan_path = path.to_string_lossy().to_string();
                 }
             }
         });
         
         // --- Scan Options ---
         ui.label("Options:");
-        ui.checkbox(&mut self.clamscan_options.recursive, "Recursive scan (-r)");
-        ui.checkbox(&mut self.clamscan_options.infected_only, "Show infected files only (-i)");
-        ui.checkbox(&mut self.clamscan_options.verbose, "Verbose output (-v)");
-        ui.checkbox(&mut self.clamscan_options.remove_infected, "! Remove infected files (--remove)");
+        ui.checkbox(&mut self.settings.clamscan_options.recursive, "Recursive scan (-r)");
+        ui.checkbox(&mut self.settings.clamscan_options.infected_only, "Show infected files only (-i)");
+        ui.checkbox(&mut self.settings.clamscan_options.verbose, "Verbose output (-v)");
+        let options = &mut self.settings.clamscan_options;
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut options.quarantine_infected, "Quarantine infected files to... (--move)");
+            ui.add_enabled(options.quarantine_infected, egui::TextEdit::singleline(&mut options.quarantine_dir).hint_text(quarantine::quarantine_dir().display().to_string()));
+            if ui.add_enabled(options.quarantine_infected, egui::Button::new("📁 Browse")).clicked() {
+                if let Some(dir) = rfd::FileDialog::new().pick_folder() {
+                    options.quarantine_dir = dir.to_string_lossy().to_string();
+                }
+            }
+        });
+        ui.add_enabled(!options.quarantine_infected, egui::Checkbox::new(&mut options.remove_infected, "! Remove infected files (--remove)"))
+            .on_disabled_hover_text("Infected files are quarantined instead");
+
+        ui.horizontal(|ui| {
+            ui.label("When a USB drive is mounted:");
+            egui::ComboBox::from_id_source("usb_scan_mode")
+                .selected_text(usb_scan_mode_label(self.settings.usb_scan_mode))
+                .show_ui(ui, |ui| {
+                    for mode in [UsbScanMode::Off, UsbScanMode::Prompt, UsbScanMode::AutoScan] {
+                        ui.selectable_value(&mut self.settings.usb_scan_mode, mode, usb_scan_mode_label(mode));
+                    }
+                });
+        });
+
+        self.draw_clamav_settings(ui, is_task_running);
+        #[cfg(feature = "yara")]
+        self.draw_yara_section(ui);
 
         ui.add_space(10.0);
 
         // --- Action Buttons ---
         ui.horizontal(|ui| {
             if ui.add_enabled(!is_task_running, egui::Button::new("🔍 Start Scan")).clicked() {
-                self.start_scan();
+                self.start_scan(ui.ctx());
+            }
+            if ui.add_enabled(!is_task_running, egui::Button::new("🖥 Full System Scan"))
+                .on_hover_text("Scan / recursively, skipping /proc, /sys, /dev, /run and network shares")
+                .clicked()
+            {
+                self.confirm_full_scan = Some(full_system_exclusions());
             }
             if ui.button("🗑 Clear Results").clicked() {
                 self.scan_task = Task::Idle;
+                self.scan_report = None;
             }
 
             if let Task::InProgress(_) = self.scan_task {
                 ui.spinner();
                 ui.label("Scanning...");
+                if let Some((stream, _)) = &self.scan_stream {
+                    let cancel = ui
+                        .add_enabled(!stream.is_cancelled(), egui::Button::new("✖ Cancel"))
+                        .on_hover_text("Stop clamscan now; with 'Remove infected files' on, nothing more is deleted");
+                    if cancel.clicked() {
+                        stream.cancel();
+                    }
+                }
             }
         });
 
+        if let Some((stream, progress)) = &mut self.scan_stream {
+            while let Ok(line) = stream.lines.try_recv() {
+                progress.update(&line);
+            }
+            ui.label(format!("{} file(s) scanned, {} infected so far", progress.scanned_files, progress.infected_files));
+            egui::ScrollArea::vertical()
+                .max_height(200.0)
+                .stick_to_bottom(true)
+                .id_source("scan_log")
+                .show(ui, |ui| {
+                    for line in &progress.log {
+                        ui.monospace(line);
+                    }
+                });
+        }
+
         // --- Scan Results ---
         if let Task::Complete(result) = &self.scan_task {
             ui.add_space(5.0);
             ui.separator();
-            ui.label("Scan Results:");
-            egui::ScrollArea::vertical().max_height(f32::INFINITY).id_source("scan_results").show(ui, |ui| {
-                ui.monospace(result);
-            });
+            if let Some(summary) = self.scan_report.as_ref().map(|report| &report.summary).filter(|summary| summary.is_present()) {
+                draw_scan_summary(ui, summary);
+            }
+            if let Some(report) = self.scan_report.as_ref().filter(|report| !report.detections.is_empty()) {
+                ui.label("Detections by category (click a path to copy it):");
+                for (category, detections) in report.by_category() {
+                    let heading = egui::RichText::new(format!("{} ({})", category.label(), detections.len()))
+                        .color(category_color(ui, category))
+                        .strong();
+                    egui::CollapsingHeader::new(heading)
+                        .id_source(("detections", category))
+                        .default_open(true)
+                        .show(ui, |ui| {
+                            for detection in detections {
+                                ui.horizontal(|ui| {
+                                    ui.monospace(&detection.signature);
+                                    if ui.link(&detection.path).on_hover_text("Copy path").clicked() {
+                                        ui.output_mut(|output| output.copied_text = detection.path.clone());
+                                    }
+                                });
+                            }
+                        });
+                }
+                ui.separator();
+            }
+            egui::CollapsingHeader::new("Raw clamscan output")
+                .id_source("scan_raw_output")
+                .default_open(!self.scan_report.as_ref().is_some_and(|report| report.summary.is_present()))
+                .show(ui, |ui| {
+                    egui::ScrollArea::vertical().max_height(f32::INFINITY).id_source("scan_results").show(ui, |ui| {
+                        ui.monospace(result);
+                    });
+                });
+        }
+    }
+
+    /// Draws the YARA rules folder picker and a scan of the scan path
+    /// against those rules, listing the matches per file.
+    #[cfg(feature = "yara")]
+    fn draw_yara_section(&mut self, ui: &mut egui::Ui) {
+        if let Some(receiver) = self.yara_task.take() {
+            match receiver.try_recv() {
+                Ok(result) => self.yara_result = Some(result),
+                Err(mpsc::TryRecvError::Empty) => self.yara_task = Some(receiver),
+                Err(mpsc::TryRecvError::Disconnected) => self.yara_result = Some(Err("the scan stopped unexpectedly".to_string())),
+            }
         }
+
+        ui.collapsing("YARA rules", |ui| {
+            ui.label("Every .yar/.yara file in this folder is also matched against downloaded PDFs and `bigman scan`.");
+            ui.horizontal(|ui| {
+                ui.label("Rules folder:");
+                let changed = ui
+                    .add(egui::TextEdit::singleline(&mut self.settings.yara_rules_dir).hint_text("Not set"))
+                    .changed();
+                let browsed = ui.button("📁 Browse").clicked()
+                    && rfd::FileDialog::new().pick_folder().is_some_and(|folder| {
+                        self.settings.yara_rules_dir = folder.display().to_string();
+                        true
+                    });
+                if changed || browsed {
+                    let _ = self.settings.save();
+                }
+            });
+
+            let scanning = self.yara_task.is_some();
+            ui.horizontal(|ui| {
+                let ready = !scanning && !self.settings.yara_rules_dir.trim().is_empty();
+                if ui.add_enabled(ready, egui::Button::new("🔍 Scan Path with YARA")).clicked() {
+                    let rules_dir = PathBuf::from(self.settings.yara_rules_dir.trim());
+                    let target = PathBuf::from(&self.settings.scan_path);
+                    let (sender, receiver) = mpsc::channel();
+                    let ctx = ui.ctx().clone();
+                    thread::spawn(move || {
+                        let _ = sender.send(yara::scan_path(&rules_dir, &target));
+                        ctx.request_repaint();
+                    });
+                    self.yara_task = Some(receiver);
+                    self.yara_result = None;
+                }
+                if scanning {
+                    ui.spinner();
+                }
+            });
+
+            match &self.yara_result {
+                Some(Ok(matches)) if matches.is_empty() => {
+                    ui.colored_label(theme::safe(ui), "No rules matched.");
+                }
+                Some(Ok(matches)) => {
+                    let mut by_file: Vec<(&str, Vec<&str>)> = Vec::new();
+                    for m in matches {
+                        match by_file.iter_mut().find(|(path, _)| *path == m.path) {
+                            Some((_, rules)) => rules.push(&m.rule),
+                            None => by_file.push((&m.path, vec![&m.rule])),
+                        }
+                    }
+                    ui.colored_label(theme::danger(ui), format!("{} file(s) matched:", by_file.len()));
+                    egui::ScrollArea::vertical().max_height(200.0).id_source("yara_matches").show(ui, |ui| {
+                        for (path, rules) in by_file {
+                            ui.horizontal_wrapped(|ui| {
+                                ui.label(path);
+                                ui.monospace(rules.join(", "));
+                            });
+                        }
+                    });
+                }
+                Some(Err(e)) => {
+                    ui.colored_label(theme::warning(ui), format!("YARA scan failed: {}", e));
+                }
+                None => {}
+            }
+        });
+    }
+
+    /// Draws the binary path and environment overrides for ClamAV.
+    fn draw_clamav_settings(&mut self, ui: &mut egui::Ui, is_task_running: bool) {
+        ui.collapsing("Advanced ClamAV settings", |ui| {
+            ui.add_enabled_ui(!is_task_running, |ui| {
+                let clamav = &mut self.settings.clamav;
+                egui::Grid::new("clamav_binaries").show(ui, |ui| {
+                    for (label, path) in [("clamscan binary:", &mut clamav.clamscan_path), ("freshclam binary:", &mut clamav.freshclam_path)] {
+                        ui.label(label);
+                        ui.text_edit_singleline(path);
+                        if ui.button("📁 Browse").clicked() {
+                            if let Some(picked) = rfd::FileDialog::new().pick_file() {
+                                *path = picked.to_string_lossy().to_string();
+                            }
+                        }
+                        ui.end_row();
+                    }
+                });
+
+                ui.label("Environment overrides:");
+                let mut remove = None;
+                for (idx, var) in clamav.env.iter_mut().enumerate() {
+                    ui.horizontal(|ui| {
+                        ui.add(egui::TextEdit::singleline(&mut var.name).hint_text("NAME").desired_width(150.0));
+                        ui.label("=");
+                        ui.add(egui::TextEdit::singleline(&mut var.value).hint_text("value"));
+                        if ui.small_button("✖").clicked() {
+                            remove = Some(idx);
+                        }
+                    });
+                }
+                if let Some(idx) = remove {
+                    clamav.env.remove(idx);
+                }
+                if ui.button("➕ Add Variable").clicked() {
+                    clamav.env.push(EnvVar::default());
+                }
+            });
+        });
     }
 
     /// Kicks off a `clamscan` process in a background thread.
-    fn start_scan(&mut self) {
-        let mut cmd = Command::new("clamscan");
+    fn start_scan(&mut self, ctx: &egui::Context) {
+        let path = self.settings.scan_path.clone();
+        self.start_scan_or_confirm(ctx, &path, self.settings.clamscan_options.recursive);
+    }
+
+    /// Starts a scan of `path`, unless infected files would be deleted, in
+    /// which case the user has to confirm it first.
+    fn start_scan_or_confirm(&mut self, ctx: &egui::Context, path: &str, recursive: bool) {
+        if self.settings.clamscan_options.deletes_infected() {
+            self.confirm_remove_scan = Some((path.to_string(), recursive));
+        } else {
+            self.start_scan_of(ctx, path, recursive, &[]);
+        }
+    }
+
+    /// Scans `/` recursively, skipping pseudo, virtual and network filesystems.
+    fn start_full_system_scan(&mut self, ctx: &egui::Context) {
+        let excluded = full_system_exclusions();
+        self.start_scan_of(ctx, "/", true, &excluded);
+    }
+
+    /// Scans `path` with the configured options, forcing `-r` when asked and
+    /// skipping everything under `exclude_dirs`.
+    fn start_scan_of(&mut self, ctx: &egui::Context, path: &str, recursive: bool, exclude_dirs: &[String]) {
+        let mut cmd = self.settings.clamav.clamscan_command();
+
+        if recursive { cmd.arg("-r"); }
+        for dir in exclude_dirs { cmd.arg(exclude_dir_arg(dir)); }
+        self.scan_report = None;
+        if self.settings.clamscan_options.verbose { cmd.arg("-v"); }
+        if self.settings.clamscan_options.infected_only { cmd.arg("-i"); }
+        let options = &self.settings.clamscan_options;
+        if options.quarantine_infected {
+            match quarantine::prepare_dir(&options.quarantine_dir) {
+                Ok(dir) => {
+                    cmd.arg(format!("--move={}", dir.display()));
+                }
+                Err(e) => {
+                    self.scan_task = Task::Complete(format!("❌ Could not create the quarantine folder: {}", e));
+                    return;
+                }
+            }
+        } else if options.remove_infected {
+            cmd.arg("--remove");
+        }
+
+        cmd.arg(path);
+
+        let command_name = self.settings.clamav.clamscan_path.clone();
+        let ctx = ctx.clone();
+        let show_results = Arc::clone(&self.show_scan_results);
+        let path = path.to_string();
+        let (receiver, stream) = run_command_streaming(cmd, command_name, move |result| {
+            report_scan_completion(&ctx, &path, result, show_results);
+        });
+        self.scan_task = Task::InProgress(receiver);
+        self.scan_stream = stream.map(|stream| (stream, ClamScanProgress::default()));
+    }
+
+    /// Starts or stops the removable-media watcher to match the setting and
+    /// handles any drives it reported.
+    fn check_for_removable_media(&mut self, ctx: &egui::Context) {
+        if self.settings.usb_scan_mode == UsbScanMode::Off {
+            self.usb_receiver = None;
+            self.pending_usb_mounts.clear();
+            return;
+        }
+
+        let receiver = self.usb_receiver.get_or_insert_with(start_usb_watcher);
+        while let Ok(mount) = receiver.try_recv() {
+            if !self.pending_usb_mounts.contains(&mount) {
+                self.pending_usb_mounts.push(mount);
+            }
+        }
+
+        let is_task_running = matches!(self.scan_task, Task::InProgress(_)) || matches!(self.update_task, Task::InProgress(_));
+        let awaiting_confirmation = self.confirm_remove_scan.is_some();
+        if self.settings.usb_scan_mode == UsbScanMode::AutoScan && !is_task_running && !awaiting_confirmation && !self.pending_usb_mounts.is_empty() {
+            let mount = self.pending_usb_mounts.remove(0);
+            self.scan_removable_media(ctx, &mount);
+        }
 
-        if self.clamscan_options.recursive { cmd.arg("-r"); }
-        if self.clamscan_options.verbose { cmd.arg("-v"); }
-        if self.clamscan_options.infected_only { cmd.arg("-i"); }
-        if self.clamscan_options.remove_infected { cmd.arg("--remove"); }
+        // Keep polling the watcher while the window is otherwise idle.
+        ctx.request_repaint_after(std::time::Duration::from_secs(1));
+    }
+
+    /// Runs a recursive scan of a mounted drive and shows the results.
+    fn scan_removable_media(&mut self, ctx: &egui::Context, mount: &RemovableMount) {
+        self.active_view = ActiveView::ClamAV;
+        self.start_scan_or_confirm(ctx, &mount.mount_point, true);
+    }
+
+    /// Asks the user whether to scan the next drive that was mounted.
+    fn draw_usb_scan_prompt(&mut self, ctx: &egui::Context) {
+        if self.settings.usb_scan_mode != UsbScanMode::Prompt {
+            return;
+        }
+        let Some(mount) = self.pending_usb_mounts.first().cloned() else {
+            return;
+        };
+
+        let is_task_running = matches!(self.scan_task, Task::InProgress(_)) || matches!(self.update_task, Task::InProgress(_));
+        egui::Window::new("USB drive detected")
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(format!("{} was mounted at {}.", mount.device, mount.mount_point));
+                ui.label("Scan it for viruses now?");
+                ui.horizontal(|ui| {
+                    if ui.add_enabled(!is_task_running, egui::Button::new("🔍 Scan Drive")).clicked() {
+                        self.pending_usb_mounts.remove(0);
+                        self.scan_removable_media(ctx, &mount);
+                    }
+                    if ui.button("Ignore").clicked() {
+                        self.pending_usb_mounts.remove(0);
+                    }
+                    if is_task_running {
+                        ui.label("(waiting for the current task to finish)");
+                    }
+                });
+            });
+    }
+
+    /// Asks before starting a full system scan, listing what will be skipped.
+    fn draw_full_scan_confirmation(&mut self, ctx: &egui::Context) {
+        let Some(excluded) = self.confirm_full_scan.clone() else {
+            return;
+        };
+
+        egui::Window::new("Scan the entire filesystem?")
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label("This scans every file under / and can take hours on a large disk. The computer may be slow while it runs.");
+                ui.label("These locations will be skipped:");
+                for dir in &excluded {
+                    ui.monospace(dir);
+                }
+                if self.settings.clamscan_options.deletes_infected() {
+                    ui.colored_label(theme::danger(ui), "⚠ 'Remove infected files' is on: detections anywhere on the system will be deleted, and can't be recovered.");
+                }
+                ui.horizontal(|ui| {
+                    if ui.button("🖥 Start Full Scan").clicked() {
+                        self.confirm_full_scan = None;
+                        self.start_full_system_scan(ctx);
+                    }
+                    if ui.button("Cancel").clicked() {
+                        self.confirm_full_scan = None;
+                    }
+                });
+            });
+    }
 
-        cmd.arg(&self.scan_path);
+    /// Asks before a scan that passes `--remove`, since clamscan deletes
+    /// whatever it flags, false positives included.
+    fn draw_remove_scan_confirmation(&mut self, ctx: &egui::Context) {
+        let Some((path, recursive)) = self.confirm_remove_scan.clone() else {
+            return;
+        };
 
-        self.scan_task = Task::InProgress(run_command_in_thread(cmd, "clamscan"));
+        egui::Window::new("Delete infected files?")
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label("'Remove infected files' is on. Every file clamscan flags under this path will be deleted:");
+                ui.monospace(&path);
+                ui.colored_label(
+                    theme::danger(ui),
+                    "⚠ Deleted files can't be recovered, and a false positive deletes a clean file.",
+                );
+                ui.label("Turn on 'Quarantine infected files' to move them somewhere recoverable instead.");
+                ui.horizontal(|ui| {
+                    if ui.button("🗑 Scan and Delete").clicked() {
+                        self.confirm_remove_scan = None;
+                        self.start_scan_of(ctx, &path, recursive, &[]);
+                    }
+                    if ui.button("🔍 Scan Only").on_hover_text("Turn off 'Remove infected files' and scan").clicked() {
+                        self.confirm_remove_scan = None;
+                        self.settings.clamscan_options.remove_infected = false;
+                        self.start_scan_of(ctx, &path, recursive, &[]);
+                    }
+                    if ui.button("Cancel").clicked() {
+                        self.confirm_remove_scan = None;
+                    }
+                });
+            });
     }
 
-    /// Kicks off a `freshclam` process in a background thread.
+    /// Kicks off a `freshclam` process in a background thread, streaming
+    /// its output so download progress can be shown.
     fn start_database_update(&mut self) {
-        let cmd = Command::new("freshclam");
-        self.update_task = Task::InProgress(run_command_in_thread(cmd, "freshclam"));
+        let cmd = self.settings.clamav.freshclam_command();
+        let (receiver, stream) = run_command_streaming(cmd, self.settings.clamav.freshclam_path.clone(), |_| {});
+        self.update_task = Task::InProgress(receiver);
+        self.update_stream = stream.map(|stream| (stream, FreshclamProgress::default()));
+        self.freshclam_report = None;
     }
 
     /// Checks if any running tasks have finished and updates the state.
@@ -350,7 +1905,13 @@ impl BigmanApp {
         let scan_task = mem::replace(&mut self.scan_task, Task::Idle);
         if let Task::InProgress(rx) = scan_task {
             match rx.try_recv() {
-                Ok(result) => self.scan_task = Task::Complete(result),
+                Ok(result) => {
+                    self.scan_stream = None;
+                    self.scan_report = Some(ClamScanReport::parse(&result));
+                    self.scan_task = Task::Complete(result);
+                    // `report_scan_completion` has indexed anything it quarantined.
+                    self.quarantine_entries = quarantine::load_index();
+                }
                 Err(mpsc::TryRecvError::Empty) => self.scan_task = Task::InProgress(rx), // Not done, put it back
                 Err(mpsc::TryRecvError::Disconnected) => self.scan_task = Task::Complete("Task thread terminated unexpectedly.".to_string()),
             }
@@ -361,7 +1922,11 @@ impl BigmanApp {
         let update_task = mem::replace(&mut self.update_task, Task::Idle);
         if let Task::InProgress(rx) = update_task {
             match rx.try_recv() {
-                Ok(result) => self.update_task = Task::Complete(result),
+                Ok(result) => {
+                    self.update_stream = None;
+                    self.freshclam_report = Some(FreshclamReport::parse(&result));
+                    self.update_task = Task::Complete(result);
+                }
                 Err(mpsc::TryRecvError::Empty) => self.update_task = Task::InProgress(rx),
                 Err(mpsc::TryRecvError::Disconnected) => self.update_task = Task::Complete("Task thread terminated unexpectedly.".to_string()),
             }
@@ -371,38 +1936,271 @@ impl BigmanApp {
     }
 }
 
-/// A generic helper to run a `Command` in a background thread.
-/// It returns a `Receiver` that will eventually contain the formatted output.
-fn run_command_in_thread(mut command: Command, command_name: &'static str) -> mpsc::Receiver<String> {
+/// Asks where to save `results` as CSV and writes them there. Returns the
+/// status to show, or `None` if the user cancelled the dialog.
+fn export_pdf_scans_csv(results: &[&PdfScanResult]) -> Option<String> {
+    let path = rfd::FileDialog::new()
+        .add_filter("CSV", &["csv"])
+        .set_file_name("bigman-pdf-scans.csv")
+        .save_file()?;
+    let rows = results.iter().map(|result| {
+        vec![
+            format_utc_timestamp(result.timestamp),
+            result.file_path.clone(),
+            if result.is_suspicious { "SUSPICIOUS" } else { "CLEAN" }.to_string(),
+            result.risk_score.to_string(),
+            result.reason.clone(),
+            result.sha256.clone(),
+        ]
+    });
+    let header = ["timestamp_utc", "path", "verdict", "risk_score", "reason", "sha256"];
+    Some(match write_csv(&path, &header, rows) {
+        Ok(()) => format!("Exported {} scan(s) to {}", results.len(), path.display()),
+        Err(e) => format!("❌ Export failed: {:#}", e),
+    })
+}
+
+/// Whether a PDF scan passes the list filter. `filter` is lowercase.
+fn pdf_result_matches(result: &PdfScanResult, filter: &str, suspicious_only: bool) -> bool {
+    if suspicious_only && !result.is_suspicious {
+        return false;
+    }
+    filter.is_empty() || result.file_path.to_lowercase().contains(filter) || result.reason.to_lowercase().contains(filter)
+}
+
+/// The counts from clamscan's summary block, with infections in red.
+fn draw_scan_summary(ui: &mut egui::Ui, summary: &ClamScanSummary) {
+    egui::Frame::group(ui.style()).show(ui, |ui| {
+        egui::Grid::new("scan_summary").num_columns(2).show(ui, |ui| {
+            let infected = summary.infected_files.unwrap_or(0);
+            ui.label("Infected files:");
+            let text = egui::RichText::new(summary.infected_files.map_or("?".to_string(), group_thousands)).strong();
+            ui.label(if infected > 0 { text.color(theme::danger(ui)) } else { text.color(theme::safe(ui)) });
+            ui.end_row();
+            ui.label("Scanned files:");
+            ui.label(summary.scanned_files.map_or("?".to_string(), group_thousands));
+            ui.end_row();
+            if let Some(data) = &summary.data_scanned {
+                ui.label("Data scanned:");
+                ui.label(data);
+                ui.end_row();
+            }
+            if let Some(time) = &summary.time {
+                ui.label("Time:");
+                ui.label(time);
+                ui.end_row();
+            }
+        });
+    });
+}
+
+/// Red for threats that act on their own, orange for the rest of the
+/// malware, yellow for unwanted or heuristic hits, grey for test files.
+fn category_color(ui: &egui::Ui, category: SignatureCategory) -> egui::Color32 {
+    match category {
+        SignatureCategory::Ransomware
+        | SignatureCategory::Exploit
+        | SignatureCategory::Backdoor
+        | SignatureCategory::Worm
+        | SignatureCategory::Virus => theme::danger(ui),
+        SignatureCategory::Trojan
+        | SignatureCategory::Downloader
+        | SignatureCategory::Coinminer
+        | SignatureCategory::Malware
+        | SignatureCategory::Phishing => theme::caution(ui),
+        SignatureCategory::Pua | SignatureCategory::Heuristic | SignatureCategory::Other => theme::warning(ui),
+        SignatureCategory::Test => theme::muted(ui),
+    }
+}
+
+fn usb_scan_mode_label(mode: UsbScanMode) -> &'static str {
+    match mode {
+        UsbScanMode::Off => "Do nothing",
+        UsbScanMode::Prompt => "Ask to scan it",
+        UsbScanMode::AutoScan => "Scan it automatically",
+    }
+}
+
+fn format_command_output(status: std::process::ExitStatus, stdout: &str, stderr: &str) -> String {
+    let mut result = format!("Command finished with status: {}\n", status);
+    if !stdout.is_empty() {
+        result.push_str("\n--- STDOUT ---\n");
+        result.push_str(stdout);
+    }
+    if !stderr.is_empty() {
+        result.push_str("\n--- STDERR ---\n");
+        result.push_str(stderr);
+    }
+    result
+}
+
+fn launch_failure_message(command_name: &str, e: &std::io::Error) -> String {
+    format!(
+        "❌ Failed to execute '{}': {}\n\nIs ClamAV installed and in your system's PATH? You can set the full path under 'Advanced ClamAV settings'.",
+        command_name, e
+    )
+}
+
+/// Starts the result of a command the user stopped.
+const CANCELLED_MESSAGE: &str = "Cancelled by user.";
+
+/// The live side of `run_command_streaming`: output lines as they are
+/// printed, and the process so it can be killed.
+struct StreamingCommand {
+    lines: mpsc::Receiver<String>,
+    child: Arc<Mutex<Child>>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl StreamingCommand {
+    /// Kills the process; its output so far still becomes the result,
+    /// after `CANCELLED_MESSAGE`.
+    fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+        if let Ok(mut child) = self.child.lock() {
+            let _ = child.kill();
+        }
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+}
+
+/// Runs a `Command` in a background thread. Returns a `Receiver` that will
+/// eventually contain the formatted output, and the live side, which gets
+/// each stdout/stderr line as it is printed (progress bars redrawn with `\r`
+/// count as lines too), or `None` if the command couldn't be started.
+/// `on_finish` is called with the output on the background thread, so it
+/// runs even while the window isn't repainting.
+fn run_command_streaming(
+    mut command: Command,
+    command_name: String,
+    on_finish: impl FnOnce(&str) + Send + 'static,
+) -> (mpsc::Receiver<String>, Option<StreamingCommand>) {
     let (sender, receiver) = mpsc::channel();
+    let mut child = match command.stdout(Stdio::piped()).stderr(Stdio::piped()).spawn() {
+        Ok(child) => child,
+        Err(e) => {
+            let result_str = launch_failure_message(&command_name, &e);
+            on_finish(&result_str);
+            let _ = sender.send(result_str);
+            return (receiver, None);
+        }
+    };
+
+    let (line_sender, lines) = mpsc::channel();
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+    let child = Arc::new(Mutex::new(child));
+    let waiter = Arc::clone(&child);
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let was_cancelled = Arc::clone(&cancelled);
 
     thread::spawn(move || {
-        let result_str = match command.stdout(Stdio::piped()).stderr(Stdio::piped()).output() {
-            Ok(output) => {
-                let stdout = String::from_utf8_lossy(&output.stdout);
-                let stderr = String::from_utf8_lossy(&output.stderr);
-                
-                let mut result = format!("Command finished with status: {}\n", output.status);
-                if !stdout.is_empty() {
-                    result.push_str("\n--- STDOUT ---\n");
-                    result.push_str(&stdout);
-                }
-                if !stderr.is_empty() {
-                    result.push_str("\n--- STDERR ---\n");
-                    result.push_str(&stderr);
-                }
-                result
-            }
-            Err(e) => format!(
-                "❌ Failed to execute '{}': {}\n\nIs ClamAV installed and in your system's PATH?",
-                command_name, e
-            ),
+        let stderr_sender = line_sender.clone();
+        let stderr_reader = thread::spawn(move || stream_lines(stderr, &stderr_sender));
+        let stdout_text = stream_lines(stdout, &line_sender);
+        let stderr_text = stderr_reader.join().unwrap_or_default();
+
+        // Both pipes are closed, so the process has exited and this won't block.
+        let mut result_str = match waiter.lock().map(|mut child| child.wait()) {
+            Ok(Ok(status)) => format_command_output(status, &stdout_text, &stderr_text),
+            _ => format!("❌ Lost track of '{}' while it was running.", command_name),
         };
+        if was_cancelled.load(Ordering::SeqCst) {
+            result_str = format!("{}\n\n{}", CANCELLED_MESSAGE, result_str);
+        }
+        on_finish(&result_str);
         // The receiver might be dropped if the app closes, so we ignore the send error.
         let _ = sender.send(result_str);
     });
 
-    receiver
+    (receiver, Some(StreamingCommand { lines, child, cancelled }))
+}
+
+/// Reads `reader` to the end, sending every `\n`- or `\r`-terminated line,
+/// and returns everything read.
+fn stream_lines(reader: Option<impl Read>, sender: &mpsc::Sender<String>) -> String {
+    let Some(mut reader) = reader else {
+        return String::new();
+    };
+    let mut all = Vec::new();
+    let mut line = Vec::new();
+    let mut buffer = [0u8; 4096];
+    while let Ok(read) = reader.read(&mut buffer) {
+        if read == 0 {
+            break;
+        }
+        for &byte in &buffer[..read] {
+            all.push(byte);
+            if byte == b'\n' || byte == b'\r' {
+                if !line.is_empty() {
+                    let _ = sender.send(String::from_utf8_lossy(&line).into_owned());
+                }
+                line.clear();
+            } else {
+                line.push(byte);
+            }
+        }
+    }
+    if !line.is_empty() {
+        let _ = sender.send(String::from_utf8_lossy(&line).into_owned());
+    }
+    String::from_utf8_lossy(&all).into_owned()
+}
+
+/// Records a finished ClamAV scan in the audit log and, if the window isn't
+/// in front, raises a summary notification that brings it back when clicked.
+fn report_scan_completion(ctx: &egui::Context, path: &str, output: &str, show_results: Arc<AtomicBool>) {
+    // Even a cancelled scan may have moved files already.
+    record_quarantined_files(output);
+    if output.starts_with(CANCELLED_MESSAGE) {
+        // The user is at the window, so there's nothing to notify them about.
+        let _ = audit::record("clamav", "scan-cancelled", path, CANCELLED_MESSAGE);
+        ctx.request_repaint();
+        return;
+    }
+    let summary = ClamScanSummary::parse(output).describe(path);
+    let _ = audit::record("clamav", "scan-complete", path, &summary);
+
+    let focused = ctx.input(|i| i.viewport().focused);
+    if focused != Some(true) {
+        let ctx = ctx.clone();
+        notify::send_desktop_notification_with_click("BigMan scan finished", &summary, move || {
+            show_results.store(true, Ordering::Relaxed);
+            ctx.send_viewport_cmd(egui::ViewportCommand::Minimized(false));
+            ctx.send_viewport_cmd(egui::ViewportCommand::Focus);
+            ctx.request_repaint();
+        });
+    }
+    ctx.request_repaint();
+}
+
+/// Adds files that `clamscan --move` quarantined to the quarantine index.
+fn record_quarantined_files(output: &str) {
+    let quarantined_at = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let entries: Vec<QuarantineEntry> = ClamScanReport::parse(output)
+        .detections
+        .into_iter()
+        .filter_map(|detection| {
+            Some(QuarantineEntry {
+                quarantined_path: detection.moved_to?,
+                original_path: detection.path,
+                quarantined_at,
+                reason: detection.signature,
+            })
+        })
+        .collect();
+    for entry in &entries {
+        let _ = audit::record("clamav", "quarantined", &entry.original_path, &format!("{}: moved to {}", entry.reason, entry.quarantined_path));
+    }
+    if let Err(e) = quarantine::add_to_index(entries) {
+        let _ = audit::record("clamav", "quarantine-index-failed", "", &e.to_string());
+    }
 }
 
 /// Entry point for the GUI application.
@@ -417,11 +2215,26 @@ pub fn run_gui() -> Result<(), eframe::Error> {
     eframe::run_native(
         "BigMan Antivirus",
         options,
-        Box::new(|_cc| {
+        Box::new(|cc| {
+            let state = GuiState::load();
+            if let Some(zoom) = state.pixels_per_point.filter(|zoom| (0.75..=3.0).contains(zoom)) {
+                cc.egui_ctx.set_pixels_per_point(zoom);
+            }
             // Start the IPC server when the GUI is created
-            let ipc_receiver = start_ipc_server();
-            let mut app = BigmanApp::default();
-            app.ipc_receiver = Some(ipc_receiver);
+            let (ipc_receiver, ipc_unavailable) = match start_ipc_server() {
+                Ok(receiver) => (Some(receiver), None),
+                Err(e) => (None, Some(e.to_string())),
+            };
+            let policy = Settings::load().retention;
+            thread::spawn(move || retention::enforce(&policy));
+            let mut app = BigmanApp {
+                ipc_receiver,
+                ipc_unavailable,
+                active_view: state.active_view,
+                theme: state.theme,
+                ..Default::default()
+            };
+            app.quarantine_entries = quarantine::load_index();
             Ok(Box::new(app))
         }),
     )
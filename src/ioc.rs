@@ -0,0 +1,366 @@
+// src/ioc.rs
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::fs;
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use crate::config::{data_path, write_data_file};
+
+const IOC_FILE: &str = "iocs.json";
+
+/// The kinds of indicators of compromise we understand.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum IocKind {
+    Sha256,
+    Md5,
+    Domain,
+    Ipv4,
+    Ipv6,
+    Url,
+}
+
+impl fmt::Display for IocKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            IocKind::Sha256 => "SHA-256",
+            IocKind::Md5 => "MD5",
+            IocKind::Domain => "Domain",
+            IocKind::Ipv4 => "IPv4",
+            IocKind::Ipv6 => "IPv6",
+            IocKind::Url => "URL",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// The active blocklist/hash-list consulted by the scanners.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IocList {
+    #[serde(default)]
+    pub sha256: BTreeSet<String>,
+    #[serde(default)]
+    pub md5: BTreeSet<String>,
+    #[serde(default)]
+    pub domains: BTreeSet<String>,
+    #[serde(default)]
+    pub ipv4: BTreeSet<String>,
+    #[serde(default)]
+    pub ipv6: BTreeSet<String>,
+    #[serde(default)]
+    pub urls: BTreeSet<String>,
+}
+
+/// A blocklisted network indicator found in scanned content.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IocMatch {
+    pub kind: IocKind,
+    pub indicator: String,
+}
+
+impl fmt::Display for IocMatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let what = match self.kind {
+            IocKind::Url => "URL",
+            IocKind::Domain => "domain",
+            IocKind::Ipv4 | IocKind::Ipv6 => "IP address",
+            IocKind::Sha256 | IocKind::Md5 => "hash",
+        };
+        write!(f, "References blocklisted {} {}", what, self.indicator)
+    }
+}
+
+/// The result of looking one URL up against the blocklist.
+#[derive(Debug, Clone)]
+pub struct UrlReputation {
+    /// Host name or IP the URL points at, if it could be parsed.
+    pub host: Option<String>,
+    /// Blocklist entries the URL or its host hit.
+    pub matches: Vec<IocMatch>,
+}
+
+/// What happened during one import of pasted indicators.
+#[derive(Debug, Clone, Default)]
+pub struct ImportSummary {
+    /// Newly added indicators per kind.
+    pub added: BTreeMap<IocKind, usize>,
+    /// Indicators that were valid but already on the list (or repeated in the paste).
+    pub duplicates: usize,
+    /// Non-empty lines in which no indicator could be recognised.
+    pub failed_lines: Vec<String>,
+}
+
+impl ImportSummary {
+    pub fn total_added(&self) -> usize {
+        self.added.values().sum()
+    }
+}
+
+/// Undoes the common report "defanging" (`hxxp://`, `evil[.]com`) so the
+/// indicator can be validated and matched.
+fn refang(token: &str) -> String {
+    token
+        .replace("[.]", ".")
+        .replace("(.)", ".")
+        .replace("[:]", ":")
+        .replace("hxxps://", "https://")
+        .replace("hxxp://", "http://")
+}
+
+fn is_hex(s: &str, len: usize) -> bool {
+    s.len() == len && s.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// File extensions that turn up in reports next to domains. None of them is
+/// a top-level domain, so `payload.exe` or `invoice.pdf` is a file name,
+/// never a host. (Extensions that are real TLDs, like `.zip`, are left out.)
+const FILE_EXTENSIONS: &[&str] = &[
+    "exe", "dll", "scr", "msi", "bat", "cmd", "vbs", "vbe", "js", "jse", "wsf", "hta", "lnk", "jar", "apk",
+    "elf", "bin", "dmg", "iso", "img", "pdf", "doc", "docx", "docm", "xls", "xlsx", "xlsm", "ppt", "pptx",
+    "rtf", "txt", "csv", "log", "json", "xml", "htm", "html", "php", "rar", "tar", "gz", "tgz", "png",
+    "jpg", "jpeg", "gif", "svg", "dat", "tmp",
+];
+
+fn is_valid_domain(s: &str) -> bool {
+    let labels: Vec<&str> = s.split('.').collect();
+    if labels.len() < 2 || s.len() > 253 {
+        return false;
+    }
+    let labels_ok = labels.iter().all(|label| {
+        !label.is_empty()
+            && label.len() <= 63
+            && !label.starts_with('-')
+            && !label.ends_with('-')
+            && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+    });
+    let tld = labels[labels.len() - 1];
+    let tld_ok = tld.starts_with("xn--")
+        || (tld.len() >= 2 && tld.chars().all(|c| c.is_ascii_alphabetic()) && !FILE_EXTENSIONS.contains(&tld));
+    labels_ok && tld_ok
+}
+
+/// Extracts the host part of an http(s) URL.
+pub fn url_host(url: &str) -> Option<&str> {
+    let rest = url
+        .strip_prefix("http://")
+        .or_else(|| url.strip_prefix("https://"))?;
+    let authority = rest.split(['/', '?', '#']).next()?;
+    let host = authority.rsplit('@').next()?;
+    // Bracketed IPv6 literals keep their colons; otherwise drop any port.
+    let host = if host.starts_with('[') {
+        host.trim_start_matches('[').split(']').next()?
+    } else {
+        host.split(':').next()?
+    };
+    (!host.is_empty()).then_some(host)
+}
+
+/// Pulls every http(s) URL out of free text, refanged and in order of appearance.
+pub fn find_urls(text: &str) -> Vec<String> {
+    let mut urls: Vec<String> = Vec::new();
+    for token in text.split(|c: char| c.is_whitespace() || c == '|' || c == ';') {
+        if let Some((IocKind::Url, url)) = parse_indicator(token) {
+            if !urls.contains(&url) {
+                urls.push(url);
+            }
+        }
+    }
+    urls
+}
+
+/// Recognises a single indicator, returning its kind and normalized form.
+pub fn parse_indicator(token: &str) -> Option<(IocKind, String)> {
+    let token = refang(token.trim_matches(|c: char| matches!(c, '"' | '\'' | ',' | ';' | '<' | '>' | '(' | ')')));
+    if token.is_empty() {
+        return None;
+    }
+    let lower = token.to_ascii_lowercase();
+
+    if is_hex(&lower, 64) {
+        return Some((IocKind::Sha256, lower));
+    }
+    if is_hex(&lower, 32) {
+        return Some((IocKind::Md5, lower));
+    }
+    if lower.parse::<Ipv4Addr>().is_ok() {
+        return Some((IocKind::Ipv4, lower));
+    }
+    if let Ok(ip) = lower.parse::<Ipv6Addr>() {
+        return Some((IocKind::Ipv6, ip.to_string()));
+    }
+    if lower.starts_with("http://") || lower.starts_with("https://") {
+        let host = url_host(&lower)?;
+        let host_ok = is_valid_domain(host) || host.parse::<Ipv4Addr>().is_ok() || host.parse::<Ipv6Addr>().is_ok();
+        return host_ok.then(|| (IocKind::Url, token.to_string()));
+    }
+    if is_valid_domain(&lower) {
+        return Some((IocKind::Domain, lower));
+    }
+    None
+}
+
+impl IocList {
+    /// Loads the saved list, or an empty one if none exists yet.
+    pub fn load() -> IocList {
+        fs::read_to_string(data_path(IOC_FILE))
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    /// Writes the list back to disk.
+    pub fn save(&self) -> std::io::Result<()> {
+        let json = serde_json::to_string_pretty(self).map_err(std::io::Error::other)?;
+        write_data_file(&data_path(IOC_FILE), json)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.counts().values().all(|&n| n == 0)
+    }
+
+    /// Number of indicators of each kind.
+    pub fn counts(&self) -> BTreeMap<IocKind, usize> {
+        BTreeMap::from([
+            (IocKind::Sha256, self.sha256.len()),
+            (IocKind::Md5, self.md5.len()),
+            (IocKind::Domain, self.domains.len()),
+            (IocKind::Ipv4, self.ipv4.len()),
+            (IocKind::Ipv6, self.ipv6.len()),
+            (IocKind::Url, self.urls.len()),
+        ])
+    }
+
+    fn set_for(&mut self, kind: IocKind) -> &mut BTreeSet<String> {
+        match kind {
+            IocKind::Sha256 => &mut self.sha256,
+            IocKind::Md5 => &mut self.md5,
+            IocKind::Domain => &mut self.domains,
+            IocKind::Ipv4 => &mut self.ipv4,
+            IocKind::Ipv6 => &mut self.ipv6,
+            IocKind::Url => &mut self.urls,
+        }
+    }
+
+    /// Parses a block of mixed indicators (one or more per line, as copied out
+    /// of a threat report), de-duplicates them and adds the valid ones.
+    pub fn import_text(&mut self, text: &str) -> ImportSummary {
+        let mut summary = ImportSummary::default();
+
+        for line in text.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut recognised = false;
+            for token in line.split(|c: char| c.is_whitespace() || c == ',' || c == ';') {
+                if let Some((kind, value)) = parse_indicator(token) {
+                    recognised = true;
+                    if self.set_for(kind).insert(value) {
+                        *summary.added.entry(kind).or_insert(0) += 1;
+                    } else {
+                        summary.duplicates += 1;
+                    }
+                }
+            }
+
+            if !recognised {
+                summary.failed_lines.push(line.to_string());
+            }
+        }
+
+        summary
+    }
+
+    /// Returns every network indicator referenced in `text`.
+    pub fn find_network_matches(&self, text: &str) -> Vec<IocMatch> {
+        let lower = text.to_ascii_lowercase();
+        let mut matches = Vec::new();
+
+        for url in &self.urls {
+            if lower.contains(&url.to_ascii_lowercase()) {
+                matches.push(IocMatch { kind: IocKind::Url, indicator: url.clone() });
+            }
+        }
+        for domain in &self.domains {
+            if contains_host(&lower, domain) {
+                matches.push(IocMatch { kind: IocKind::Domain, indicator: domain.clone() });
+            }
+        }
+        for (kind, ips) in [(IocKind::Ipv4, &self.ipv4), (IocKind::Ipv6, &self.ipv6)] {
+            for ip in ips {
+                if contains_host(&lower, ip) {
+                    matches.push(IocMatch { kind, indicator: ip.clone() });
+                }
+            }
+        }
+
+        matches
+    }
+
+    /// Looks up a single URL, by full URL and by host.
+    pub fn check_url(&self, url: &str) -> UrlReputation {
+        UrlReputation {
+            host: url_host(&url.to_ascii_lowercase()).map(str::to_string),
+            matches: self.find_network_matches(url),
+        }
+    }
+
+    /// Checks file contents against the hash lists.
+    pub fn find_hash_match(&self, content: &[u8]) -> Option<String> {
+        use md5::Md5;
+        use sha2::{Digest, Sha256};
+
+        // A list without hashes of a kind doesn't need that digest.
+        let sha256 = if self.sha256.is_empty() { String::new() } else { format!("{:x}", Sha256::digest(content)) };
+        let md5 = if self.md5.is_empty() { String::new() } else { format!("{:x}", Md5::digest(content)) };
+        self.find_digest_match(&sha256, &md5)
+    }
+
+    /// Like `find_hash_match`, for a file whose SHA-256 and MD5 (lowercase
+    /// hex) the caller has already computed.
+    pub fn find_digest_match(&self, sha256: &str, md5: &str) -> Option<String> {
+        if self.sha256.contains(sha256) {
+            return Some(format!("File matches blocklisted SHA-256 {}", sha256));
+        }
+        if self.md5.contains(md5) {
+            return Some(format!("File matches blocklisted MD5 {}", md5));
+        }
+        None
+    }
+}
+
+/// Finds `host` in `text` as a whole host name, so `evil.com` matches
+/// `https://evil.com/x` and `a.evil.com` but not `notevil.com` or `evil.community`.
+fn contains_host(text: &str, host: &str) -> bool {
+    let is_host_char = |c: char| c.is_ascii_alphanumeric() || c == '-' || c == '.';
+    text.match_indices(host).any(|(start, _)| {
+        let before_ok = match text[..start].chars().next_back() {
+            None | Some('.') => true,
+            Some(c) => !is_host_char(c),
+        };
+        let rest = &text[start + host.len()..];
+        let after_ok = match rest.chars().next() {
+            None => true,
+            // A trailing full stop ends a sentence, not the host name.
+            Some('.') => !rest[1..].starts_with(|c: char| c.is_ascii_alphanumeric()),
+            Some(c) => !is_host_char(c),
+        };
+        before_ok && after_ok
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pasted_file_names_are_not_imported_as_domains() {
+        let mut list = IocList::default();
+        let summary = list.import_text("payload.exe\nInvoice.PDF, evil.com\nhxxp://payload.exe/run\n");
+        assert_eq!(list.domains, BTreeSet::from(["evil.com".to_string()]));
+        assert!(list.urls.is_empty());
+        assert_eq!(summary.failed_lines, ["payload.exe", "hxxp://payload.exe/run"]);
+    }
+}
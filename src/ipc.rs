@@ -1,53 +1,405 @@
 // src/ipc.rs
 
-use std::io::{Read, Write};
-use std::net::{TcpListener, TcpStream};
-use std::sync::mpsc;
-use crate::pdf_scanner::{scan_pdf_for_actions, PdfScanResult};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::Shutdown;
+use std::panic::AssertUnwindSafe;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc};
+use std::time::Duration;
+use crate::pdf_scanner::{scan_pdf_for_actions, PdfScanResult, ScanError};
 
-const IPC_ADDRESS: &str = "127.0.0.1:56789"; // An unused port for local communication
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener as Listener, UnixStream as Stream};
+#[cfg(unix)]
+use std::path::PathBuf;
+#[cfg(not(unix))]
+use std::net::{TcpListener as Listener, TcpStream as Stream};
+
+/// Where the GUI listens on platforms without Unix domain sockets. Any local
+/// process can connect to it, so Unix builds use a private socket instead.
+#[cfg(not(unix))]
+const IPC_ADDRESS: &str = "127.0.0.1:56789";
+
+/// The GUI's socket, which only the current user can reach:
+/// `$XDG_RUNTIME_DIR/bigman.sock`, or `bigman.sock` in a private
+/// `bigman-<uid>` folder under the temp directory when that isn't set.
+#[cfg(unix)]
+fn socket_path() -> io::Result<PathBuf> {
+    use std::os::unix::fs::{DirBuilderExt, MetadataExt};
+
+    if let Some(dir) = std::env::var_os("XDG_RUNTIME_DIR").filter(|dir| !dir.is_empty()) {
+        return Ok(PathBuf::from(dir).join("bigman.sock"));
+    }
+    let uid = unsafe { libc::getuid() };
+    let dir = std::env::temp_dir().join(format!("bigman-{}", uid));
+    match std::fs::DirBuilder::new().mode(0o700).create(&dir) {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {}
+        Err(e) => return Err(e),
+    }
+    // The temp directory is shared, so someone else may have made it first.
+    let meta = std::fs::symlink_metadata(&dir)?;
+    if !meta.is_dir() || meta.uid() != uid || meta.mode() & 0o077 != 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            format!("{} is not a private folder owned by this user", dir.display()),
+        ));
+    }
+    Ok(dir.join("bigman.sock"))
+}
+
+#[cfg(unix)]
+fn connect() -> io::Result<Stream> {
+    Stream::connect(socket_path()?)
+}
+
+#[cfg(not(unix))]
+fn connect() -> io::Result<Stream> {
+    Stream::connect(IPC_ADDRESS)
+}
+
+/// Binds the socket readable and writable by this user only. A socket file
+/// left behind by a crashed GUI is replaced; one that still answers belongs
+/// to another running instance.
+#[cfg(unix)]
+fn bind() -> io::Result<Listener> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let path = socket_path()?;
+    let listener = match Listener::bind(&path) {
+        Err(e) if e.kind() == io::ErrorKind::AddrInUse && Stream::connect(&path).is_err() => {
+            std::fs::remove_file(&path)?;
+            Listener::bind(&path)?
+        }
+        result => result?,
+    };
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))?;
+    Ok(listener)
+}
+
+#[cfg(not(unix))]
+fn bind() -> io::Result<Listener> {
+    Listener::bind(IPC_ADDRESS)
+}
+
+/// Bumped whenever the frame layout or message meaning changes. Clients open
+/// every connection with a `BIGMAN/<version>` line; frames without one are
+/// from version 0, which sent a bare file path.
+pub const IPC_PROTOCOL_VERSION: u32 = 2;
+
+/// From this version on the path after the handshake line is a frame, as in
+/// native messaging: its length as 4 little-endian bytes, then that many
+/// bytes of UTF-8. Older clients sent the path up to the end of the stream.
+const FRAMED_SINCE_VERSION: u32 = 2;
+
+/// Longest path frame accepted, so a bogus length can't make the server
+/// allocate gigabytes. Well above `PATH_MAX` on any platform.
+const MAX_FRAME_BYTES: u32 = 32 * 1024;
+
+const HANDSHAKE_PREFIX: &str = "BIGMAN/";
+
+/// Longest request the server will read; paths are far shorter than this.
+const MAX_REQUEST_BYTES: u64 = 64 * 1024;
+
+/// Connections handled at once; more get an `ERR busy` reply.
+const MAX_CONNECTIONS: usize = 16;
+
+/// How long a connected client gets to send its request.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// How long a client waits for the scan itself; pdfid and pdfinfo can be
+/// slow on large documents.
+const RESULT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// The verdict the server writes back as one JSON line after `OK <version>`,
+/// once the scan is done.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum ScanReply {
+    Result(Box<PdfScanResult>),
+    /// The file couldn't be scanned; the `ScanError` as text.
+    Error(String),
+}
+
+/// A request read off the socket, after the version handshake.
+enum Request {
+    /// An empty connection, used to check the server is up.
+    Probe,
+    ScanPath(String),
+    /// A client that speaks a protocol version this build doesn't understand.
+    Unsupported(u32),
+}
+
+fn path_request(file_path: String) -> Request {
+    if file_path.is_empty() {
+        Request::Probe
+    } else {
+        Request::ScanPath(file_path)
+    }
+}
+
+/// Reads the optional `BIGMAN/<version>` line and then the path, framed or
+/// up to the end of the stream depending on the version. An oversized or
+/// non-UTF-8 frame is an `InvalidData` error.
+fn read_request(stream: &mut Stream) -> io::Result<Request> {
+    stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+    let mut reader = BufReader::new(stream.take(MAX_REQUEST_BYTES));
+    let mut first_line = Vec::new();
+    reader.read_until(b'\n', &mut first_line)?;
+
+    let Some(header) = first_line.strip_prefix(HANDSHAKE_PREFIX.as_bytes()) else {
+        // Version 0: the whole request is the path.
+        reader.read_to_end(&mut first_line)?;
+        return Ok(path_request(String::from_utf8_lossy(&first_line).trim().to_string()));
+    };
+    let Some(version) = std::str::from_utf8(header).ok().and_then(|header| header.trim().parse::<u32>().ok()) else {
+        return Ok(Request::Unsupported(u32::MAX));
+    };
+    if version > IPC_PROTOCOL_VERSION {
+        return Ok(Request::Unsupported(version));
+    }
+    if version < FRAMED_SINCE_VERSION {
+        let mut body = Vec::new();
+        reader.read_to_end(&mut body)?;
+        return Ok(path_request(String::from_utf8_lossy(&body).trim().to_string()));
+    }
+
+    let mut length = [0u8; 4];
+    reader.read_exact(&mut length)?;
+    let length = u32::from_le_bytes(length);
+    if length > MAX_FRAME_BYTES {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("frame of {} bytes is over the {} byte limit", length, MAX_FRAME_BYTES),
+        ));
+    }
+    let mut path = vec![0; length as usize];
+    reader.read_exact(&mut path)?;
+    let path = String::from_utf8(path).map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "path is not valid UTF-8"))?;
+    Ok(path_request(path))
+}
+
+/// The outcome of scanning one path sent over IPC, with the path so a
+/// failure can still say which file it was about.
+pub type IpcScan = (String, Result<PdfScanResult, ScanError>);
+
+/// Why the GUI can't receive downloads from the browser extension.
+#[derive(Debug)]
+pub enum IpcUnavailable {
+    /// Another GUI already listens; downloads show up there instead.
+    AlreadyRunning,
+    Io(io::Error),
+}
+
+impl fmt::Display for IpcUnavailable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IpcUnavailable::AlreadyRunning => write!(f, "another BigMan window is already receiving downloads"),
+            IpcUnavailable::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
 
 /// Starts the IPC server in a background thread to listen for scan requests.
-/// Returns a receiver that the GUI can use to get scan results.
-pub fn start_ipc_server() -> mpsc::Receiver<PdfScanResult> {
+/// Returns a receiver that the GUI can use to get scan results, or why the
+/// server couldn't start.
+pub fn start_ipc_server() -> Result<mpsc::Receiver<IpcScan>, IpcUnavailable> {
+    let listener = bind().map_err(|e| match e.kind() {
+        io::ErrorKind::AddrInUse => IpcUnavailable::AlreadyRunning,
+        _ => IpcUnavailable::Io(e),
+    })?;
     let (sender, receiver) = mpsc::channel();
+    std::thread::spawn(move || serve(listener, sender));
+    Ok(receiver)
+}
 
-    std::thread::spawn(move || {
-        let listener = match TcpListener::bind(IPC_ADDRESS) {
-            Ok(l) => l,
-            Err(_) => {
-                // Another instance might be running. For this example, we just exit the thread.
-                return;
+/// Accepts connections until the listener fails, handling each on its own
+/// thread so a slow scan doesn't hold up the others.
+fn serve(listener: Listener, sender: mpsc::Sender<IpcScan>) {
+    let active = Arc::new(AtomicUsize::new(0));
+    for mut stream in listener.incoming().flatten() {
+        if active.fetch_add(1, Ordering::SeqCst) >= MAX_CONNECTIONS {
+            active.fetch_sub(1, Ordering::SeqCst);
+            let _ = writeln!(stream, "ERR busy, {} scans already running", MAX_CONNECTIONS);
+            continue;
+        }
+        let sender = sender.clone();
+        let running = Arc::clone(&active);
+        let spawned = std::thread::Builder::new().name("ipc-connection".to_string()).spawn(move || {
+            // A panic while scanning one file is logged; the listener lives on.
+            if std::panic::catch_unwind(AssertUnwindSafe(|| handle_connection(stream, &sender))).is_err() {
+                log::error!("IPC connection failed while scanning; still listening for others");
             }
-        };
-
-        for stream in listener.incoming() {
-            if let Ok(mut stream) = stream {
-                let mut buffer = [0; 1024];
-                if let Ok(size) = stream.read(&mut buffer) {
-                    let file_path = String::from_utf8_lossy(&buffer[..size]).to_string();
-                    let result = scan_pdf_for_actions(&file_path);
-                    
-                    // Send the result back to the GUI thread
-                    let _ = sender.send(result);
-                }
+            running.fetch_sub(1, Ordering::SeqCst);
+        });
+        if let Err(e) = spawned {
+            log::error!("Could not start a thread for an IPC connection: {}", e);
+            active.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+}
+
+fn handle_connection(mut stream: Stream, sender: &mpsc::Sender<IpcScan>) {
+    let request = match read_request(&mut stream) {
+        Ok(request) => request,
+        Err(e) => {
+            if e.kind() == io::ErrorKind::InvalidData {
+                let _ = writeln!(stream, "ERR {}", e);
             }
+            return;
         }
-    });
+    };
+    match request {
+        // A liveness probe from `is_gui_running`, not a scan request.
+        Request::Probe => {}
+        Request::Unsupported(version) => {
+            log::warn!(
+                "Rejected IPC client speaking protocol version {} (this build speaks {}); update BigMan so both sides match",
+                version, IPC_PROTOCOL_VERSION
+            );
+            let _ = writeln!(stream, "ERR unsupported protocol version {}, expected {}", version, IPC_PROTOCOL_VERSION);
+        }
+        Request::ScanPath(file_path) => {
+            let _ = writeln!(stream, "OK {}", IPC_PROTOCOL_VERSION);
+            let result = scan_pdf_for_actions(&file_path);
 
-    receiver
-}
+            // Answer the client, which may have gone already
+            let reply = match &result {
+                Ok(scan) => ScanReply::Result(Box::new(scan.clone())),
+                Err(e) => ScanReply::Error(e.to_string()),
+            };
+            if let Ok(json) = serde_json::to_string(&reply) {
+                let _ = writeln!(stream, "{}", json);
+            }
 
-/// Called by the native messaging host to send a file path to the running GUI server.
-pub fn send_path_to_gui(file_path: &str) -> Result<(), std::io::Error> {
-    match TcpStream::connect(IPC_ADDRESS) {
-        Ok(mut stream) => {
-            stream.write_all(file_path.as_bytes())?;
-            stream.flush()
+            // Send the result back to the GUI thread
+            let _ = sender.send((file_path, result));
         }
+    }
+}
+
+/// Called by the native messaging host to have the running GUI scan a file,
+/// so it shows up there live. Returns the GUI's verdict, or the reason it
+/// couldn't scan the file. Fails with `InvalidData` if the GUI is running but
+/// speaks another protocol version or sent no usable verdict.
+pub fn send_path_to_gui(file_path: &str) -> io::Result<Result<PdfScanResult, String>> {
+    match connect() {
+        Ok(stream) => request_scan(stream, file_path),
         Err(e) => {
             // This error means the GUI is not running, which is okay.
             Err(e)
         }
     }
-} 
\ No newline at end of file
+}
+
+/// Sends one scan request on a connected stream and waits for the verdict.
+fn request_scan(mut stream: Stream, file_path: &str) -> io::Result<Result<PdfScanResult, String>> {
+    let length = u32::try_from(file_path.len())
+        .ok()
+        .filter(|&length| length <= MAX_FRAME_BYTES)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "path is too long to send to the GUI"))?;
+    writeln!(stream, "{}{}", HANDSHAKE_PREFIX, IPC_PROTOCOL_VERSION)?;
+    stream.write_all(&length.to_le_bytes())?;
+    stream.write_all(file_path.as_bytes())?;
+    stream.flush()?;
+    // Servers from before framing read up to the end of the stream
+    // before they answer, even if only to reject the version.
+    stream.shutdown(Shutdown::Write)?;
+
+    stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+    let mut reader = BufReader::new(stream);
+    let mut status = String::new();
+    reader.read_line(&mut status)?;
+    if let Some(reason) = status.trim().strip_prefix("ERR ") {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, format!("GUI rejected the request: {}", reason)));
+    }
+
+    reader.get_ref().set_read_timeout(Some(RESULT_TIMEOUT))?;
+    let mut reply = String::new();
+    reader.read_line(&mut reply)?;
+    match serde_json::from_str(&reply) {
+        Ok(ScanReply::Result(scan)) => Ok(Ok(*scan)),
+        Ok(ScanReply::Error(reason)) => Ok(Err(reason)),
+        Err(e) => Err(io::Error::new(io::ErrorKind::InvalidData, format!("GUI sent no verdict: {}", e))),
+    }
+}
+
+/// Whether a GUI instance is listening for scan results.
+#[cfg(unix)]
+pub fn is_gui_running() -> bool {
+    connect().is_ok()
+}
+
+/// Whether a GUI instance is listening for scan results.
+#[cfg(not(unix))]
+pub fn is_gui_running() -> bool {
+    IPC_ADDRESS
+        .parse()
+        .is_ok_and(|address| Stream::connect_timeout(&address, Duration::from_millis(200)).is_ok())
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    /// Starts a server on a fresh socket in the temp directory, so tests
+    /// don't collide with a running GUI or with each other.
+    fn start_test_server(name: &str) -> (std::path::PathBuf, mpsc::Receiver<IpcScan>) {
+        let path = std::env::temp_dir().join(format!("bigman-ipc-test-{}-{}.sock", std::process::id(), name));
+        let _ = std::fs::remove_file(&path);
+        let listener = Listener::bind(&path).unwrap();
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || serve(listener, sender));
+        (path, receiver)
+    }
+
+    #[test]
+    fn answers_concurrent_connections_while_one_is_stalled() {
+        let (path, receiver) = start_test_server("concurrent");
+
+        // Never sends anything, so a one-at-a-time server would wait out
+        // REQUEST_TIMEOUT before reading the others.
+        let _stalled = Stream::connect(&path).unwrap();
+
+        let started = Instant::now();
+        let clients: Vec<_> = (0..6)
+            .map(|i| {
+                let path = path.clone();
+                std::thread::spawn(move || {
+                    let missing = format!("/nonexistent/bigman-ipc-test-{}.pdf", i);
+                    request_scan(Stream::connect(&path).unwrap(), &missing).unwrap()
+                })
+            })
+            .collect();
+        for client in clients {
+            let reply = client.join().unwrap();
+            assert!(reply.is_err(), "a missing file should come back as a scan error");
+        }
+        assert!(started.elapsed() < REQUEST_TIMEOUT, "requests were held up by the stalled connection");
+
+        let mut scanned: Vec<String> = (0..6).map(|_| receiver.recv().unwrap().0).collect();
+        scanned.sort();
+        let expected: Vec<String> = (0..6).map(|i| format!("/nonexistent/bigman-ipc-test-{}.pdf", i)).collect();
+        assert_eq!(scanned, expected);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn rejects_oversized_frame_and_keeps_serving() {
+        let (path, _receiver) = start_test_server("oversized");
+
+        let mut stream = Stream::connect(&path).unwrap();
+        writeln!(stream, "{}{}", HANDSHAKE_PREFIX, IPC_PROTOCOL_VERSION).unwrap();
+        stream.write_all(&u32::MAX.to_le_bytes()).unwrap();
+        let mut status = String::new();
+        BufReader::new(stream).read_line(&mut status).unwrap();
+        assert!(status.starts_with("ERR "), "unexpected reply {:?}", status);
+
+        let reply = request_scan(Stream::connect(&path).unwrap(), "/nonexistent/after-bad-frame.pdf").unwrap();
+        assert!(reply.is_err());
+        let _ = std::fs::remove_file(&path);
+    }
+}
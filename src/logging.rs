@@ -0,0 +1,50 @@
+// src/logging.rs
+
+use env_logger::{Builder, Env, Target, WriteStyle};
+use log::LevelFilter;
+use std::fs::{self, OpenOptions};
+use std::path::PathBuf;
+
+use crate::config::user_data_dir;
+
+/// Used when neither `--log-level` nor `RUST_LOG` says otherwise.
+const DEFAULT_FILTER: &str = "warn";
+
+/// The native messaging host's log, in the per-user data directory.
+const NATIVE_HOST_LOG_FILE: &str = "native-host.log";
+
+/// Where the native messaging host writes its log.
+pub fn native_host_log_path() -> PathBuf {
+    user_data_dir().join(NATIVE_HOST_LOG_FILE)
+}
+
+/// A logger filtered by `RUST_LOG` (which also takes per-module filters,
+/// e.g. `bigman::ipc=debug`), with `level` overriding it when given.
+fn builder(level: Option<LevelFilter>) -> Builder {
+    let mut builder = Builder::from_env(Env::default().default_filter_or(DEFAULT_FILTER));
+    if let Some(level) = level {
+        builder.filter_level(level);
+    }
+    builder
+}
+
+/// Logs to stderr, for the GUI and the command line.
+pub fn init(level: Option<LevelFilter>) {
+    let _ = builder(level).target(Target::Stderr).try_init();
+}
+
+/// Logs to `native_host_log_path()`, never to stdout or stderr: stdout
+/// carries the browser protocol. When the file can't be opened nothing is
+/// logged at all.
+pub fn init_native_host() {
+    let path = native_host_log_path();
+    if let Some(dir) = path.parent() {
+        let _ = fs::create_dir_all(dir);
+    }
+    if let Ok(file) = OpenOptions::new().create(true).append(true).open(&path) {
+        let _ = builder(None)
+            .target(Target::Pipe(Box::new(file)))
+            .write_style(WriteStyle::Never)
+            .try_init();
+    }
+}
@@ -4,114 +4,827 @@
 mod analyzer;
 mod gui;
 mod pdf_scanner;
+mod pe_scanner;
 mod ipc;
+mod ioc;
+mod config;
+mod usb_watch;
+mod feedback;
+mod cli;
+mod report;
+mod scanner;
+mod archive;
+mod quarantine;
+mod audit;
+mod notify;
+mod status;
+mod filename;
+mod clamav;
+mod retention;
+mod scratch;
+mod theme;
+mod logging;
+#[cfg(feature = "yara")]
+mod yara;
+mod native_messaging;
 
-use std::io::{self, Read};
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
 use anyhow::{Result, Context};
+use colored::*;
 use serde::{Deserialize, Serialize};
 
 // Import structs and functions from our new files
 use ipc::send_path_to_gui;
+use pdf_scanner::{PDFID_PROGRAM, PDFINFO_PROGRAM};
+use cli::{AnalyzeArgs, Command, GitHookArgs, ScanArchiveArgs, ScanArgs, ScanPdfArgs, Verbosity};
+use report::Report;
 
 // Native messaging structs
+/// A request from the browser extension, tagged by its `type` field.
 #[derive(Deserialize, Debug)]
-struct ExtensionMessage { 
-    #[serde(rename = "filePath")]
-    file_path: String, 
+#[serde(tag = "type", rename_all = "camelCase")]
+enum ExtensionMessage {
+    /// Scan a downloaded PDF (or Windows executable) on disk, or the PDF
+    /// bytes sent inline as base64 `content` (which must fit in one message).
+    #[serde(alias = "scan")]
+    ScanPdf {
+        #[serde(rename = "filePath", default)]
+        file_path: Option<String>,
+        #[serde(default)]
+        content: Option<String>,
+    },
+    /// Check the host is installed and speaks a compatible protocol.
+    Ping,
+    #[serde(alias = "status")]
+    GetStatus,
+}
+
+/// Also accepts messages from extension versions that sent a bare
+/// `{"filePath": ...}` with no `type`.
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+enum IncomingMessage {
+    Tagged(ExtensionMessage),
+    Untagged {
+        #[serde(rename = "filePath")]
+        file_path: String,
+    },
+}
+
+#[derive(Serialize, Debug)]
+struct PingResponse {
+    status: &'static str,
+    version: &'static str,
+    protocol_version: u32,
+}
+
+/// The outcome of a scan for the extension to branch on, rather than
+/// parsing `status`.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum ScanVerdict {
+    Clean,
+    Suspicious,
+    /// The file couldn't be scanned; `reason` says why.
+    Error,
 }
 
 #[derive(Serialize, Debug)]
 struct ScanResponse { 
+    /// `CLEAN`, `SUSPICIOUS` or `ERROR`; kept for extension builds that
+    /// predate `verdict`.
     status: String, 
     reason: String, 
+    verdict: ScanVerdict,
+    /// Sum of the finding weights; 0 for clean files and errors.
+    risk_score: u32,
+    /// Each finding on its own, in the order found; empty for errors.
+    reasons: Vec<String>,
+    /// What was done locally about a SUSPICIOUS download, e.g. "quarantined".
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    actions_taken: Vec<String>,
+}
+
+/// What a download scan found, whichever scanner ran.
+struct DownloadVerdict {
+    is_suspicious: bool,
+    reason: String,
+    reasons: Vec<String>,
+    risk_score: u32,
+}
+
+impl From<pdf_scanner::PdfScanResult> for DownloadVerdict {
+    fn from(result: pdf_scanner::PdfScanResult) -> Self {
+        DownloadVerdict {
+            is_suspicious: result.is_suspicious,
+            reason: result.reason,
+            reasons: result.reasons,
+            risk_score: result.risk_score,
+        }
+    }
+}
+
+impl From<pe_scanner::PeScanResult> for DownloadVerdict {
+    fn from(result: pe_scanner::PeScanResult) -> Self {
+        DownloadVerdict {
+            is_suspicious: result.is_suspicious,
+            reason: result.reason,
+            reasons: result.findings.into_iter().map(|finding| finding.description).collect(),
+            risk_score: result.risk_score,
+        }
+    }
+}
+
+/// What `scan-pdf --report-out` writes: a result per scanned file and the
+/// files that couldn't be scanned.
+#[derive(Serialize)]
+struct ScanPdfReport<'a> {
+    results: &'a [pdf_scanner::PdfScanResult],
+    errors: Vec<scanner::UnscannedPath>,
 }
 
 fn main() -> Result<()> {
-    // Check if we have command line arguments (native messaging mode)
-    if std::env::args().len() > 1 {
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+
+    // Check if the browser launched us (native messaging mode)
+    if cli::is_native_messaging_launch(&args) {
+        logging::init_native_host();
         run_native_messaging_host();
         return Ok(());
     }
+    logging::init(cli::take_log_level(&mut args)?);
+
+    let analyze_args = match cli::parse_args(&args)? {
+        Command::Help => {
+            println!("{}", cli::USAGE);
+            return Ok(());
+        }
+        Command::Scan(scan_args) => return scan_files(&scan_args),
+        Command::ScanPdf(scan_args) => return scan_pdfs(&scan_args),
+        Command::ScanArchive(scan_args) => return scan_archive(&scan_args),
+        Command::ExplainRule(id) => return explain_rule(id.as_deref()),
+        Command::ValidateRules(path) => return validate_rules(path.as_deref()),
+        Command::GitHook(hook_args) => return git_hook(&hook_args),
+        Command::Default(analyze_args) => analyze_args,
+    };
 
     // Check if we're receiving piped input
     let is_piped = !is_tty();
 
     if is_piped {
-        analyze_stdin()
+        analyze_stdin(&analyze_args)
+    } else if analyze_args.report_out.is_some() || analyze_args.json {
+        anyhow::bail!("Nothing to analyze: pipe a script into bigman to use --report-out or --json\n\n{}", cli::USAGE)
     } else {
         gui::run_gui().map_err(|e| anyhow::anyhow!("GUI error: {}", e))
     }
 }
 
-fn analyze_stdin() -> Result<()> {
+fn analyze_stdin(args: &AnalyzeArgs) -> Result<()> {
+    let patterns = match args.rules {
+        Some(ref path) => analyzer::PatternSet::load(path, !args.no_builtins)?,
+        None => analyzer::PatternSet::builtin(),
+    }
+    .for_platform(args.platform);
+
     let mut content = String::new();
     io::stdin().read_to_string(&mut content)
         .context("Failed to read from stdin")?;
 
-    if content.trim().is_empty() {
-        return Ok(());
+    let analysis_result = if content.trim().is_empty() {
+        analyzer::AnalysisResult { is_safe: true, score: 0, threats: Vec::new(), secrets: Vec::new() }
+    } else {
+        analyzer::analyze_content_with(&content, &patterns, &ioc::IocList::load())?
+    };
+
+    if let Some(ref path) = args.report_out {
+        report::write_report_atomic(path, &Report::new("analyze", &analysis_result))?;
+    }
+
+    let exit_code = if analysis_result.is_safe { 0 } else { 1 };
+    let quiet = args.verbosity == Verbosity::Quiet;
+
+    if args.json {
+        println!("{}", serde_json::to_string(&analysis_result)?);
+        if args.summary && !quiet {
+            eprintln!("{}", analysis_result.summary());
+        }
+        std::process::exit(exit_code);
     }
 
-    let analysis_result = analyzer::analyze_content(&content)?;
+    if content.trim().is_empty() || quiet {
+        std::process::exit(exit_code);
+    }
+
+    if args.summary {
+        println!("{}", analysis_result.summary());
+        std::process::exit(exit_code);
+    }
+
+    analyzer::render_result(&analysis_result, &mut io::stdout().lock())?;
 
     if analysis_result.is_safe {
         println!("SAFE");
-        std::process::exit(0);
     } else {
         for threat in &analysis_result.threats {
-            eprintln!("THREAT: {}", threat);
+            eprintln!("THREAT[{}]: {}", threat.severity, threat);
+        }
+        for secret in &analysis_result.secrets {
+            eprintln!("SECRET[{}]: {}", secret.severity, secret);
         }
+        eprintln!("SCORE: {}", analysis_result.score);
+    }
+    std::process::exit(exit_code);
+}
+
+/// Scans each file given (or matched) on the command line with whichever
+/// scanner fits its content. Exits with 1 if any file was flagged.
+fn scan_files(args: &ScanArgs) -> Result<()> {
+    let report = scanner::scan_paths(&args.paths);
+
+    for outcome in &report.files {
+        let verdict = if outcome.is_suspicious { "SUSPICIOUS" } else { "CLEAN" };
+        let path = outcome.name.escape_debug();
+        if args.verbosity == Verbosity::Quiet {
+            println!("{}\t{}", verdict, path);
+        } else if outcome.is_suspicious || args.verbosity == Verbosity::Verbose || args.paths.len() == 1 {
+            let colored_verdict = if outcome.is_suspicious { verdict.red().bold() } else { verdict.green().bold() };
+            println!("{}: {} ({})", path, colored_verdict, outcome.kind);
+            for finding in &outcome.findings {
+                println!("  - {}", finding);
+            }
+        }
+    }
+    for unscanned in &report.unscanned {
+        if args.verbosity == Verbosity::Quiet {
+            println!("UNREADABLE\t{}", unscanned.path.escape_debug());
+        } else {
+            println!("{}: {} ({})", unscanned.path.escape_debug(), "UNREADABLE".yellow().bold(), unscanned.error);
+        }
+    }
+
+    if args.verbosity != Verbosity::Quiet {
+        let suspicious = report.files.iter().filter(|outcome| outcome.is_suspicious).count();
+        println!("{} file(s) scanned, {} suspicious, {} could not be read.", report.files.len(), suspicious, report.unscanned.len());
+    }
+
+    if let Some(ref path) = args.report_out {
+        report::write_report_atomic(path, &Report::new("scan", &report))?;
+    }
+
+    if report.is_suspicious() {
         std::process::exit(1);
     }
+    Ok(())
 }
 
-/// Native messaging host that scans PDFs and responds to the browser extension
-fn run_native_messaging_host() {
-    // Chrome Native Messaging protocol: Read 4-byte length prefix first
-    let mut length_bytes = [0u8; 4];
-    if std::io::stdin().read_exact(&mut length_bytes).is_err() {
-        return;
+/// Scans each PDF given on the command line (and with `--dir`, each one in
+/// that folder) and prints a verdict per file, then a summary table when
+/// there were several. Exits with 1 if any file was flagged.
+fn scan_pdfs(args: &ScanPdfArgs) -> Result<()> {
+    let mut paths = args.paths.clone();
+    let mut errors = Vec::new();
+    if let Some(ref dir) = args.dir {
+        let (files, unreadable) = pdf_scanner::find_pdf_files(dir, args.recursive);
+        paths.extend(files.iter().map(|path| path.to_string_lossy().into_owned()));
+        for (folder, e) in unreadable {
+            let path = folder.display().to_string();
+            print_pdf_error(&path, &e.to_string(), args.verbosity);
+            errors.push(scanner::UnscannedPath { path, error: e.to_string() });
+        }
+    }
+    let several_files = paths.len() > 1 || args.dir.is_some();
+
+    let mut results = Vec::new();
+    for_each_pdf_scan(&paths, args.jobs.max(1), |path, outcome| match outcome {
+        Ok(result) => {
+            print_pdf_result(&result, args.verbosity, several_files);
+            results.push(result);
+        }
+        Err(e) => {
+            print_pdf_error(path, &e.to_string(), args.verbosity);
+            errors.push(scanner::UnscannedPath { path: path.to_string(), error: e.to_string() });
+        }
+    });
+
+    if several_files && args.verbosity != Verbosity::Quiet {
+        print_pdf_summary(&results, &errors);
+    }
+
+    if let Some(ref path) = args.report_out {
+        report::write_report_atomic(path, &Report::new("scan-pdf", &ScanPdfReport { results: &results, errors }))?;
+    }
+
+    if results.iter().any(|r| r.is_suspicious) {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// Scans `paths` on `jobs` threads and hands each outcome to `handle` in the
+/// original order, as soon as every earlier path is done.
+fn for_each_pdf_scan<F>(paths: &[String], jobs: usize, mut handle: F)
+where
+    F: FnMut(&str, Result<pdf_scanner::PdfScanResult, pdf_scanner::ScanError>),
+{
+    let next = &AtomicUsize::new(0);
+    let context = &pdf_scanner::ScanContext::load();
+    let (sender, receiver) = mpsc::channel();
+    std::thread::scope(|scope| {
+        for _ in 0..jobs.min(paths.len()) {
+            let sender = sender.clone();
+            scope.spawn(move || {
+                loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(path) = paths.get(index) else { break };
+                    if sender.send((index, pdf_scanner::scan_pdf_with(path, context))).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(sender);
+
+        let mut finished = HashMap::new();
+        let mut printed = 0;
+        for (index, outcome) in receiver {
+            finished.insert(index, outcome);
+            while let Some(outcome) = finished.remove(&printed) {
+                handle(&paths[printed], outcome);
+                printed += 1;
+            }
+        }
+    });
+}
+
+fn print_pdf_error(path: &str, error: &str, verbosity: Verbosity) {
+    if verbosity == Verbosity::Quiet {
+        println!("ERROR\t{}", path);
+    } else {
+        println!("{}: {} ({})", path, "ERROR".yellow().bold(), error);
+    }
+}
+
+/// One row per file, highest risk first, then the totals.
+fn print_pdf_summary(results: &[pdf_scanner::PdfScanResult], errors: &[scanner::UnscannedPath]) {
+    let mut rows: Vec<_> = results.iter().collect();
+    rows.sort_by(|a, b| b.risk_score.cmp(&a.risk_score).then_with(|| a.file_path.cmp(&b.file_path)));
+
+    println!();
+    println!("{:>5}  {:<10}  FILE", "RISK", "VERDICT");
+    for result in rows {
+        let verdict = if result.is_suspicious { "SUSPICIOUS".red().bold() } else { "CLEAN".green() };
+        println!("{:>5}  {:<10}  {}", result.risk_score, verdict, result.file_path);
     }
-    let message_length = u32::from_le_bytes(length_bytes) as usize;
+    for error in errors {
+        println!("{:>5}  {:<10}  {}", "-", "ERROR".yellow(), error.path);
+    }
+
+    let suspicious = results.iter().filter(|r| r.is_suspicious).count();
+    println!(
+        "\n{} PDF(s) scanned: {} suspicious, {} clean, {} could not be scanned",
+        results.len(),
+        suspicious,
+        results.len() - suspicious,
+        errors.len()
+    );
+}
 
-    // Read the JSON message
-    let mut buffer = vec![0u8; message_length];
-    if std::io::stdin().read_exact(&mut buffer).is_err() {
+fn print_pdf_result(result: &pdf_scanner::PdfScanResult, verbosity: Verbosity, several_files: bool) {
+    let verdict = if result.is_suspicious { "SUSPICIOUS" } else { "CLEAN" };
+
+    if verbosity == Verbosity::Quiet {
+        if several_files {
+            println!("{}\t{}", verdict, result.file_path);
+        } else {
+            println!("{}", verdict);
+        }
         return;
     }
 
-    let input: ExtensionMessage = match serde_json::from_slice(&buffer) {
-        Ok(msg) => msg,
-        Err(_) => return, // Invalid input, exit silently
+    let colored_verdict = if result.is_suspicious { verdict.red().bold() } else { verdict.green().bold() };
+    println!("{}: {} (risk score {})", result.file_path, colored_verdict, result.risk_score);
+    for finding in &result.findings {
+        println!("  - {}", finding.description);
+    }
+
+    if verbosity == Verbosity::Verbose {
+        println!("  Scan ID: {}", result.scan_id);
+        for (program, output) in [(PDFID_PROGRAM, &result.pdfid_output), (PDFINFO_PROGRAM, &result.metadata)] {
+            match output.text() {
+                Some(text) => {
+                    println!("  --- {} ---", program);
+                    for line in text.lines() {
+                        println!("    {}", line);
+                    }
+                }
+                None => println!("  --- {} --- ({})", program, output.status(program)),
+            }
+        }
+    }
+}
+
+/// Scans every file inside an archive and prints a verdict per entry.
+/// Exits with 1 if any entry was flagged.
+fn scan_archive(args: &ScanArchiveArgs) -> Result<()> {
+    let report = if args.source == "-" {
+        archive::scan_archive_stream(io::stdin().lock())?
+    } else {
+        let file = std::fs::File::open(&args.source)
+            .with_context(|| format!("Failed to open {}", args.source))?;
+        archive::scan_archive_stream(file)?
+    };
+
+    let mut undecoded = 0;
+    for entry in &report.entries {
+        // Names can carry newlines or bidi overrides; print them escaped.
+        let path = entry.path.escape_debug();
+        match (&entry.outcome, &entry.error) {
+            (Some(outcome), _) => {
+                let verdict = if outcome.is_suspicious { "SUSPICIOUS" } else { "CLEAN" };
+                if args.verbosity == Verbosity::Quiet {
+                    if outcome.is_suspicious {
+                        println!("{}\t{}", verdict, path);
+                    }
+                } else if outcome.is_suspicious || args.verbosity == Verbosity::Verbose {
+                    let colored_verdict = if outcome.is_suspicious { verdict.red().bold() } else { verdict.green().bold() };
+                    println!("{}: {} ({}, {} bytes)", path, colored_verdict, outcome.kind, entry.size);
+                    for finding in &outcome.findings {
+                        println!("  - {}", finding);
+                    }
+                }
+            }
+            (None, error) => {
+                undecoded += 1;
+                let reason = error.as_deref().unwrap_or("unknown error");
+                if args.verbosity == Verbosity::Quiet {
+                    println!("UNREADABLE\t{}", path);
+                } else {
+                    println!("{}: {} ({})", path, "UNREADABLE".yellow().bold(), reason);
+                }
+            }
+        }
+    }
+
+    if let Some(ref reason) = report.stopped_early {
+        eprintln!("Stopped early: {}", reason);
+    }
+    if args.verbosity != Verbosity::Quiet {
+        let suspicious = report.entries.iter().filter(|e| e.outcome.as_ref().is_some_and(|o| o.is_suspicious)).count();
+        println!("{} entries scanned, {} suspicious, {} could not be decoded.", report.entries.len() - undecoded, suspicious, undecoded);
+    }
+
+    if let Some(ref path) = args.report_out {
+        report::write_report_atomic(path, &Report::new("scan-archive", &report))?;
+    }
+
+    if report.is_suspicious() {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// Prints the definition and documentation of an analyzer rule.
+fn explain_rule(id: Option<&str>) -> Result<()> {
+    let Some(id) = id else {
+        for rule in analyzer::RULES {
+            println!("{:<28} {:<9} {}", rule.id, rule.severity.to_string(), rule.description);
+        }
+        return Ok(());
     };
 
-    // First, try to send the path to the running GUI (if any)
-    let _ = send_path_to_gui(&input.file_path);
+    let Some(rule) = analyzer::find_rule(id) else {
+        anyhow::bail!("Unknown rule '{}'. Run `bigman explain-rule` to list the rule ids.", id);
+    };
+
+    println!("{}", rule.id.bold());
+    println!("  Category:    {}", rule.category);
+    println!("  Severity:    {}", rule.severity);
+    match rule.pattern {
+        Some(pattern) => println!("  Pattern:     {}", pattern),
+        None => println!("  Pattern:     (built-in check, not a single regex)"),
+    }
+    println!("  Detects:     {}", rule.description);
+    println!();
+    println!("Why it matters:\n  {}", rule.rationale);
+    println!();
+    println!("What to do:\n  {}", rule.remediation);
+    Ok(())
+}
+
+/// Checks a ruleset compiles and prints what loaded. Exits with 1 if any
+/// rule is unusable.
+fn validate_rules(path: Option<&std::path::Path>) -> Result<()> {
+    let (name, validation) = match path {
+        Some(path) => (path.display().to_string(), analyzer::validate_ruleset(path)?),
+        None => ("built-in rules".to_string(), analyzer::validate_builtin_rules()),
+    };
 
-    // Scan the PDF and send response back to browser extension
-    let scan_result = pdf_scanner::scan_pdf_for_actions(&input.file_path);
-    
-    let response = if scan_result.is_suspicious {
+    for error in &validation.errors {
+        println!("{} {}", "ERROR".red().bold(), error);
+    }
+    for warning in &validation.warnings {
+        println!("{} {}", "WARNING".yellow().bold(), warning);
+    }
+    println!(
+        "{}: {} rule(s) loaded, {} error(s), {} warning(s)",
+        name,
+        validation.loaded,
+        validation.errors.len(),
+        validation.warnings.len()
+    );
+
+    if !validation.errors.is_empty() {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// Lists the added, copied, modified and renamed files in the git index.
+fn staged_files() -> Result<Vec<String>> {
+    let output = std::process::Command::new("git")
+        .args(["diff", "--cached", "--name-only", "--diff-filter=ACMR", "-z"])
+        .output()
+        .context("Failed to run git")?;
+    if !output.status.success() {
+        anyhow::bail!("git diff --cached failed: {}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(output
+        .stdout
+        .split(|&b| b == 0)
+        .filter(|name| !name.is_empty())
+        .map(|name| String::from_utf8_lossy(name).into_owned())
+        .collect())
+}
+
+/// The staged (not working tree) content of `path`, which is what will be committed.
+fn staged_content(path: &str) -> Result<Vec<u8>> {
+    let output = std::process::Command::new("git")
+        .arg("show")
+        .arg(format!(":{}", path))
+        .output()
+        .context("Failed to run git")?;
+    if !output.status.success() {
+        anyhow::bail!("{}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(output.stdout)
+}
+
+/// Pre-commit check: analyzes each staged text file (or the files given)
+/// and exits with 1 if any threat at or above the minimum severity is found.
+/// Binary files and PDFs are skipped; lesser threats are printed but don't
+/// block the commit.
+fn git_hook(args: &GitHookArgs) -> Result<()> {
+    let from_index = args.paths.is_empty();
+    let paths = if from_index { staged_files()? } else { args.paths.clone() };
+
+    let patterns = analyzer::PatternSet::builtin();
+    let iocs = ioc::IocList::load();
+    let mut blocked = 0;
+    for path in &paths {
+        let content = if from_index {
+            staged_content(path)
+        } else {
+            std::fs::read(path).with_context(|| format!("Failed to read {}", path))
+        };
+        let content = match content {
+            Ok(content) => content,
+            Err(e) => {
+                eprintln!("{}: could not read ({:#})", path, e);
+                continue;
+            }
+        };
+        if scanner::detect_kind(&content) != scanner::ContentKind::Script {
+            continue;
+        }
+
+        let result = analyzer::analyze_content_with(&String::from_utf8_lossy(&content), &patterns, &iocs)?;
+        if result.is_safe {
+            continue;
+        }
+        let blocking = result.threats.iter().filter(|threat| threat.severity >= args.min_severity).count()
+            + result.secrets.iter().filter(|secret| secret.severity >= args.min_severity).count();
+        blocked += blocking;
+        let verdict = if blocking > 0 { "BLOCKED".red().bold() } else { "WARNING".yellow().bold() };
+        println!("{}: {}", path, verdict);
+        for threat in &result.threats {
+            println!("  [{}] {} {}", threat.severity, threat.rule, threat);
+        }
+        for secret in &result.secrets {
+            println!("  [{}] {} {}", secret.severity, secret.rule, secret);
+        }
+    }
+
+    if blocked > 0 {
+        eprintln!(
+            "bigman: {} threat(s) at or above {} severity; commit stopped. Use 'git commit --no-verify' to override.",
+            blocked, args.min_severity
+        );
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// Native messaging host that scans PDFs and Windows executables and responds to the browser extension
+fn run_native_messaging_host() {
+    let mut output = native_messaging::protocol_output();
+    serve_native_messages(&mut io::stdin().lock(), &mut output);
+}
+
+/// Answers messages from `input` until it ends. Only reply frames are
+/// written to `output`; diagnostics go to the log.
+fn serve_native_messages(input: &mut impl Read, output: &mut impl Write) {
+    // The browser keeps the port open for as many messages as it likes and
+    // closes stdin when it's done with us.
+    loop {
+        match native_messaging::read_message(input) {
+            Ok(Some(buffer)) => handle_native_message(&buffer, output),
+            Ok(None) => return,
+            Err(e) => {
+                // The framing is lost, so there's no next message to find.
+                log::error!("Could not read a message from the browser: {}", e);
+                return;
+            }
+        }
+    }
+}
+
+/// Answers one message from the extension. A message that can't be
+/// understood is skipped; the session carries on with the next one.
+fn handle_native_message(buffer: &[u8], output: &mut impl Write) {
+    let message = match serde_json::from_slice(buffer) {
+        Ok(IncomingMessage::Tagged(message)) => message,
+        Ok(IncomingMessage::Untagged { file_path }) => ExtensionMessage::ScanPdf { file_path: Some(file_path), content: None },
+        Err(e) => {
+            log::warn!("Ignoring a message from the browser that isn't valid: {}", e);
+            return;
+        }
+    };
+
+    match message {
+        ExtensionMessage::Ping => send_native_message(output, &PingResponse {
+            status: "OK",
+            version: env!("CARGO_PKG_VERSION"),
+            protocol_version: native_messaging::PROTOCOL_VERSION,
+        }),
+        ExtensionMessage::GetStatus => send_native_message(output, &status::collect_status()),
+        ExtensionMessage::ScanPdf { file_path, content } => scan_download(file_path, content, output),
+    }
+}
+
+/// Scans a finished download, from its inline content if sent or else from
+/// disk, and tells the extension the verdict.
+fn scan_download(file_path: Option<String>, content: Option<String>, output: &mut impl Write) {
+    let outcome = match (&file_path, content) {
+        (_, Some(encoded)) => {
+            let encoded: String = encoded.split_whitespace().collect();
+            match analyzer::decode_base64(&encoded) {
+                Some(bytes) => {
+                    // The extension names inline content; nothing on disk vouches for it.
+                    let name = file_path.as_deref().unwrap_or("download.pdf");
+                    let context = pdf_scanner::ScanContext::load();
+                    Ok(pdf_scanner::scan_pdf_bytes(name, filename::NameSource::Untrusted, &bytes, &context).into())
+                }
+                None => Err("content is not valid base64".to_string()),
+            }
+        }
+        (Some(path), None) => scan_downloaded_file(path),
+        (None, None) => Err("nothing to scan: send filePath or content".to_string()),
+    };
+
+    let found = match outcome {
+        Ok(found) => found,
+        Err(e) => {
+            log::warn!("Could not scan {}: {}", file_path.as_deref().unwrap_or("inline content"), e);
+            // Not a detection: tell the extension without raising an alarm.
+            send_native_message(output, &ScanResponse {
+                status: "ERROR".to_string(),
+                reason: e,
+                verdict: ScanVerdict::Error,
+                risk_score: 0,
+                reasons: Vec::new(),
+                actions_taken: Vec::new(),
+            });
+            return;
+        }
+    };
+
+    log::info!(
+        "Scanned {}: {} (risk score {})",
+        file_path.as_deref().unwrap_or("inline content"),
+        if found.is_suspicious { "suspicious" } else { "clean" },
+        found.risk_score
+    );
+    let response = if found.is_suspicious {
+        let actions = config::Settings::load().on_suspicious_download;
         ScanResponse {
             status: "SUSPICIOUS".to_string(),
-            reason: scan_result.reason.clone(),
+            // Inline content with no path leaves nothing on disk to act on
+            actions_taken: file_path
+                .map(|path| take_suspicious_download_actions(&path, &found.reason, actions))
+                .unwrap_or_default(),
+            reason: found.reason,
+            verdict: ScanVerdict::Suspicious,
+            risk_score: found.risk_score,
+            reasons: found.reasons,
         }
     } else {
         ScanResponse {
             status: "CLEAN".to_string(),
-            reason: scan_result.reason.clone(),
+            reason: found.reason,
+            verdict: ScanVerdict::Clean,
+            risk_score: found.risk_score,
+            reasons: found.reasons,
+            actions_taken: Vec::new(),
         }
     };
 
-    // Send response back to browser extension via stdout
-    if let Ok(json_response) = serde_json::to_string(&response) {
-        let message_length = json_response.len() as u32;
-        
-        // Native messaging protocol: 4 bytes for length, then JSON
-        let _ = std::io::Write::write_all(&mut std::io::stdout(), &message_length.to_le_bytes());
-        let _ = std::io::Write::write_all(&mut std::io::stdout(), json_response.as_bytes());
-        let _ = std::io::Write::flush(&mut std::io::stdout());
+    send_native_message(output, &response);
+
+    // After replying, so the extension isn't kept waiting.
+    let _ = retention::enforce(&config::Settings::load().retention);
+}
+
+/// Scans a download on disk: Windows executables get the PE checks,
+/// everything else is treated as a PDF.
+fn scan_downloaded_file(file_path: &str) -> Result<DownloadVerdict, String> {
+    let context = pdf_scanner::ScanContext::load();
+    if let Some(reason) = context.settings.trusted_path_reason(std::path::Path::new(file_path)) {
+        return Ok(DownloadVerdict {
+            is_suspicious: false,
+            reason,
+            reasons: Vec::new(),
+            risk_score: 0,
+        });
+    }
+    if pe_scanner::has_pe_extension(file_path) {
+        return pe_scanner::scan_pe_file(file_path, &context)
+            .map(DownloadVerdict::from)
+            .map_err(|e| e.to_string());
+    }
+
+    // A running GUI scans it (and shows it live); otherwise scan it here
+    let outcome = match send_path_to_gui(file_path) {
+        Ok(outcome) => outcome,
+        Err(e) => {
+            if e.kind() == std::io::ErrorKind::InvalidData {
+                log::warn!("{}", e);
+            }
+            pdf_scanner::scan_pdf_with(file_path, &context).map_err(|e| e.to_string())
+        }
+    };
+    outcome.map(DownloadVerdict::from)
+}
+
+/// Sends one reply back to the browser extension.
+fn send_native_message(output: &mut impl Write, response: &impl Serialize) {
+    if let Err(e) = native_messaging::write_message(output, response) {
+        log::error!("Could not reply to the browser: {}", e);
+    }
+}
+
+/// Runs the configured local actions for a flagged download and returns a
+/// short description of each one that succeeded.
+fn take_suspicious_download_actions(file_path: &str, reason: &str, actions: config::SuspiciousDownloadActions) -> Vec<String> {
+    let mut taken = Vec::new();
+
+    let mut quarantine_detail = None;
+    if actions.quarantine {
+        match quarantine::quarantine_file(std::path::Path::new(file_path), reason) {
+            Ok(target) => {
+                taken.push("quarantined".to_string());
+                quarantine_detail = Some(format!("Moved to {}", target.display()));
+            }
+            Err(e) => quarantine_detail = Some(format!("Quarantine failed: {}", e)),
+        }
     }
+
+    if actions.audit_log {
+        let logged = audit::record("native-messaging", "flagged", file_path, reason).is_ok();
+        if let Some(ref detail) = quarantine_detail {
+            let _ = audit::record("native-messaging", "quarantine", file_path, detail);
+        }
+        if logged {
+            taken.push("logged".to_string());
+        }
+    }
+
+    if actions.notify {
+        let name = std::path::Path::new(file_path)
+            .file_name()
+            .map_or_else(|| file_path.to_string(), |n| n.to_string_lossy().to_string());
+        let body = match quarantine_detail {
+            Some(detail) => format!("{}\n{}\n{}", name, reason, detail),
+            None => format!("{}\n{}", name, reason),
+        };
+        notify::send_desktop_notification("Suspicious download flagged", &body);
+        taken.push("notified".to_string());
+    }
+
+    taken
 }
 
 fn is_tty() -> bool {
@@ -123,4 +836,4 @@ fn is_tty() -> bool {
     {
         false
     }
-} 
\ No newline at end of file
+}
\ No newline at end of file
@@ -0,0 +1,141 @@
+// src/native_messaging.rs
+
+use serde::Serialize;
+use std::io::{self, Read, Write};
+
+/// Bumped when the extension and host stop understanding each other's
+/// messages; the extension reads it from the reply to `{"type":"ping"}`.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Chrome's limit for messages from the host. Messages from the browser are
+/// held to the same bound, so a bogus length can't allocate gigabytes.
+pub const MAX_MESSAGE_BYTES: usize = 1024 * 1024;
+
+/// Reads one message: its length as 4 bytes in native byte order (as the
+/// browsers send it), then that many bytes of JSON. Returns `Ok(None)` when
+/// the input ends cleanly before a new message.
+pub fn read_message(reader: &mut impl Read) -> io::Result<Option<Vec<u8>>> {
+    let mut length_bytes = [0u8; 4];
+    let mut filled = 0;
+    while filled < length_bytes.len() {
+        match reader.read(&mut length_bytes[filled..]) {
+            Ok(0) if filled == 0 => return Ok(None),
+            Ok(0) => return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "input ended inside a length prefix")),
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+
+    let length = u32::from_ne_bytes(length_bytes) as usize;
+    if length > MAX_MESSAGE_BYTES {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("message of {} bytes is over the {} byte limit", length, MAX_MESSAGE_BYTES),
+        ));
+    }
+    let mut message = vec![0u8; length];
+    reader.read_exact(&mut message)?;
+    Ok(Some(message))
+}
+
+/// Where replies to the browser go. On Unix the real stdout is moved to a
+/// new descriptor and descriptor 1 is pointed at `/dev/null`, so a stray
+/// `println!` or a helper program that inherits stdout can't write bytes the
+/// browser would read as a broken frame. Elsewhere, or if that fails, this
+/// is plain stdout.
+pub fn protocol_output() -> Box<dyn Write> {
+    #[cfg(unix)]
+    match isolate_stdout() {
+        Ok(output) => return Box::new(output),
+        Err(e) => log::warn!("Could not set stdout aside for the browser; writing to it directly: {}", e),
+    }
+    Box::new(io::stdout())
+}
+
+#[cfg(unix)]
+fn isolate_stdout() -> io::Result<std::fs::File> {
+    use std::os::unix::io::{AsRawFd, FromRawFd};
+
+    io::stdout().flush()?;
+    // Close-on-exec, so helper programs don't get a copy either.
+    let fd = unsafe { libc::fcntl(libc::STDOUT_FILENO, libc::F_DUPFD_CLOEXEC, 0) };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let output = unsafe { std::fs::File::from_raw_fd(fd) };
+    let null = std::fs::OpenOptions::new().write(true).open("/dev/null")?;
+    if unsafe { libc::dup2(null.as_raw_fd(), libc::STDOUT_FILENO) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(output)
+}
+
+/// Writes `message` as JSON with the same framing as `read_message`.
+pub fn write_message(writer: &mut impl Write, message: &impl Serialize) -> io::Result<()> {
+    let json = serde_json::to_vec(message).map_err(io::Error::other)?;
+    if json.len() > MAX_MESSAGE_BYTES {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("reply of {} bytes is over the {} byte limit", json.len(), MAX_MESSAGE_BYTES),
+        ));
+    }
+    writer.write_all(&(json.len() as u32).to_ne_bytes())?;
+    writer.write_all(&json)?;
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(length: u32, body: &[u8]) -> Vec<u8> {
+        let mut data = length.to_ne_bytes().to_vec();
+        data.extend_from_slice(body);
+        data
+    }
+
+    #[test]
+    fn round_trips_a_message() {
+        let mut data = Vec::new();
+        write_message(&mut data, &serde_json::json!({"filePath": "/tmp/a.pdf"})).unwrap();
+        let message = read_message(&mut data.as_slice()).unwrap().unwrap();
+        assert_eq!(message, br#"{"filePath":"/tmp/a.pdf"}"#);
+    }
+
+    #[test]
+    fn empty_input_is_a_clean_end() {
+        assert!(read_message(&mut [].as_slice()).unwrap().is_none());
+    }
+
+    #[test]
+    fn rejects_truncated_length_prefix() {
+        let err = read_message(&mut [7u8, 0].as_slice()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn rejects_truncated_body() {
+        let data = frame(10, b"{}");
+        let err = read_message(&mut data.as_slice()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn rejects_oversized_length_without_allocating() {
+        let data = frame(u32::MAX, b"{}");
+        let err = read_message(&mut data.as_slice()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        let data = frame(MAX_MESSAGE_BYTES as u32 + 1, b"");
+        assert_eq!(read_message(&mut data.as_slice()).unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn refuses_to_write_an_oversized_reply() {
+        let mut data = Vec::new();
+        let err = write_message(&mut data, &"x".repeat(MAX_MESSAGE_BYTES)).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(data.is_empty());
+    }
+}
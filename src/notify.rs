@@ -0,0 +1,62 @@
+// src/notify.rs
+
+use std::process::{Command, Stdio};
+
+/// Pops up a desktop notification without waiting for it to be shown.
+/// Uses `notify-send` on Linux and `osascript` on macOS; failures are ignored
+/// since a missing notification daemon shouldn't stop anything else.
+pub fn send_desktop_notification(summary: &str, body: &str) {
+    let _ = notification_command(summary, body)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn();
+}
+
+/// Like `send_desktop_notification`, but calls `on_click` if the user clicks
+/// the notification. Waits on a background thread. Clicks are only reported
+/// by `notify-send` 0.7.10 and later; older versions just show the message.
+pub fn send_desktop_notification_with_click(summary: &str, body: &str, on_click: impl FnOnce() + Send + 'static) {
+    if cfg!(target_os = "macos") {
+        send_desktop_notification(summary, body);
+        return;
+    }
+
+    let mut cmd = notification_command(summary, body);
+    cmd.args(["--wait", "--action=default=Show results"]);
+    let (summary, body) = (summary.to_string(), body.to_string());
+
+    std::thread::spawn(move || {
+        match cmd.stdin(Stdio::null()).stderr(Stdio::null()).output() {
+            Ok(output) if output.status.success() => {
+                // The chosen action's key is printed when the user clicks.
+                if String::from_utf8_lossy(&output.stdout).trim() == "default" {
+                    on_click();
+                }
+            }
+            // Unsupported flags: fall back to a plain notification.
+            Ok(_) => send_desktop_notification(&summary, &body),
+            Err(_) => {}
+        }
+    });
+}
+
+fn notification_command(summary: &str, body: &str) -> Command {
+    #[cfg(target_os = "macos")]
+    {
+        let script = format!(
+            "display notification {:?} with title {:?}",
+            body, summary
+        );
+        let mut cmd = Command::new("osascript");
+        cmd.arg("-e").arg(script);
+        cmd
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    {
+        let mut cmd = Command::new("notify-send");
+        cmd.args(["--app-name=BigMan AntiVirus", "--urgency=critical", summary, body]);
+        cmd
+    }
+}
@@ -1,10 +1,19 @@
 // src/pdf_scanner.rs
 
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use std::time::{SystemTime, UNIX_EPOCH};
 use std::process::Command;
 use std::fs;
-use std::path::Path;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use crate::config::{user_data_dir, Settings};
+use crate::feedback::FeedbackStore;
+use crate::ioc::IocList;
+use crate::filename::{check_file_name, NameSource};
+use crate::scratch::ScratchDir;
+use crate::scanner::{detect_kind, ContentKind};
 
 /// The result of a single PDF scan.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -12,61 +21,946 @@ pub struct PdfScanResult {
     pub file_path: String,
     pub timestamp: u64,
     pub is_suspicious: bool,
+    /// All of `reasons` in one sentence, for the scan list and the browser
+    /// extension's notification.
     pub reason: String,
+    /// One entry per trigger found, in the order they were found.
+    #[serde(default)]
+    pub reasons: Vec<String>,
     pub scan_id: String,
-    pub pdfid_output: Option<String>,
-    pub metadata: Option<String>,
+    /// SHA-256 of the file contents (hex), empty if it couldn't be read.
+    #[serde(default)]
+    pub sha256: String,
+    /// What the content turned out to be from its first bytes, e.g. `PDF`
+    /// or `executable`. Empty in results from older versions.
+    #[serde(default)]
+    pub detected_type: String,
+    #[serde(deserialize_with = "deserialize_tool_output")]
+    pub pdfid_output: ToolOutput,
+    /// pdfid's keyword counts, e.g. `/JS` → 2. Empty when pdfid didn't run.
+    #[serde(default)]
+    pub pdfid_counts: HashMap<String, u32>,
+    #[serde(deserialize_with = "deserialize_tool_output")]
+    pub metadata: ToolOutput,
+    /// Each individual detection, in the order it was found.
+    #[serde(default)]
+    pub findings: Vec<PdfFinding>,
+    /// Sum of the finding weights after the user's feedback adjustments.
+    #[serde(default)]
+    pub risk_score: u32,
+    /// The user's own investigation notes.
+    #[serde(default)]
+    pub notes: String,
 }
 
-/// Runs pdfid.py on the file if available
-fn run_pdfid(file_path: &str) -> Option<String> {
-    Command::new("pdfid.py")
-        .arg(file_path)
-        .output()
-        .ok()
-        .and_then(|output| {
-            if output.status.success() {
-                Some(String::from_utf8_lossy(&output.stdout).to_string())
-            } else {
-                None
+impl PdfScanResult {
+    /// Identifies the scanned content, so repeat scans of the same file
+    /// collapse into one entry. Older results without a hash fall back to
+    /// their scan id.
+    pub fn dedupe_key(&self) -> &str {
+        if self.sha256.is_empty() {
+            &self.scan_id
+        } else {
+            &self.sha256
+        }
+    }
+}
+
+/// What happened when an optional helper program was run on a file, so
+/// "not installed" and "found nothing" can be told apart.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ToolOutput {
+    /// The program isn't on PATH.
+    NotInstalled,
+    /// It couldn't be run or exited with an error; holds the reason.
+    Failed(String),
+    /// It was deliberately not run; holds why.
+    Skipped(String),
+    /// What it printed.
+    Ok(String),
+}
+
+impl ToolOutput {
+    /// The tool's output, if it ran successfully.
+    pub fn text(&self) -> Option<&str> {
+        match self {
+            ToolOutput::Ok(text) => Some(text),
+            _ => None,
+        }
+    }
+
+    /// A one-line status for display, e.g. `pdfid.py not found on PATH`.
+    pub fn status(&self, program: &str) -> String {
+        match self {
+            ToolOutput::NotInstalled => format!("{} not found on PATH", program),
+            ToolOutput::Failed(reason) => format!("{} failed: {}", program, reason),
+            ToolOutput::Skipped(reason) => format!("{} not run: {}", program, reason),
+            ToolOutput::Ok(text) if text.trim().is_empty() => format!("{} ran but printed nothing", program),
+            ToolOutput::Ok(_) => format!("{} ran", program),
+        }
+    }
+}
+
+/// Reads a `ToolOutput`, also accepting the plain output string (or `null`
+/// when the tool didn't run) that older saved results contain.
+fn deserialize_tool_output<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<ToolOutput, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Stored {
+        Current(ToolOutput),
+        Legacy(Option<String>),
+    }
+    Ok(match Stored::deserialize(deserializer)? {
+        Stored::Current(output) => output,
+        Stored::Legacy(Some(text)) => ToolOutput::Ok(text),
+        Stored::Legacy(None) => ToolOutput::Failed("not recorded by this older scan".to_string()),
+    })
+}
+
+/// The helper program names, as looked up on PATH.
+pub const PDFID_PROGRAM: &str = "pdfid.py";
+pub const PDFINFO_PROGRAM: &str = "pdfinfo";
+
+/// Why a PDF couldn't be scanned. These are not detections: the file may
+/// be perfectly fine, we just couldn't look at it.
+#[derive(Debug)]
+pub enum ScanError {
+    NotFound,
+    PermissionDenied,
+    /// The file is bigger than the scanner will read.
+    TooLarge { size: u64, limit: u64 },
+    Io(io::Error),
+}
+
+impl From<io::Error> for ScanError {
+    fn from(e: io::Error) -> ScanError {
+        match e.kind() {
+            io::ErrorKind::NotFound => ScanError::NotFound,
+            io::ErrorKind::PermissionDenied => ScanError::PermissionDenied,
+            _ => ScanError::Io(e),
+        }
+    }
+}
+
+impl std::fmt::Display for ScanError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScanError::NotFound => write!(f, "file not found"),
+            ScanError::PermissionDenied => write!(f, "permission denied"),
+            ScanError::TooLarge { size, limit } => {
+                write!(f, "file is {:.1} MiB, over the {} MiB scan limit", *size as f64 / MIB as f64, limit / MIB)
             }
-        })
+            ScanError::Io(e) => write!(f, "could not read file: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ScanError {}
+
+pub const MIB: u64 = 1024 * 1024;
+
+/// Bytes read at a time by the streaming tag search.
+const SCAN_BUFFER_BYTES: usize = 64 * 1024;
+
+/// The structural checks (compressed streams, XFA, the minimal-dropper
+/// heuristic) hold the document in memory, so they see at most this much
+/// of a file. The streaming tag search and the hash still cover all of it.
+const MAX_STRUCTURE_BYTES: u64 = 64 * MIB;
+
+/// One detection within a PDF scan.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PdfFinding {
+    /// Stable id used to key false-positive feedback, e.g. `pdf:/OpenAction`.
+    pub rule: String,
+    pub description: String,
+    pub weight: u32,
+}
+
+/// Default weights for each rule, before feedback adjustments.
+const RULE_WEIGHTS: &[(&str, u32)] = &[
+    ("pdf:/OpenAction", 30),
+    ("pdf:/AA", 30),
+    ("pdf:/JavaScript", 40),
+    ("pdf:/JS", 40),
+    ("pdf:/Launch", 60),
+    ("pdf:/EmbeddedFile", 30),
+    ("pdf:/URI", 10),
+    // Informational only: a weight of 0 doesn't make a file suspicious.
+    ("pdf:undecompressable", 0),
+    ("pdf:inflate-limit", 0),
+    ("pdf:structure-truncated", 0),
+    ("pdf:minimal-scripted", 50),
+    ("pdf:ioc-hash", 100),
+    ("pdf:ioc-network", 60),
+    ("pdf:xfa-script", 40),
+    ("pdf:xfa-call", 40),
+    ("pdf:xfa-submit", 20),
+    ("pdf:xfa-undecodable", 20),
+    ("pdf:file-name", 40),
+    ("pdf:type-mismatch", 60),
+    ("pdf:yara", 60),
+    ("pdf:yara-failed", 0),
+];
+
+/// Name tags that each add a finding. `true` also matches longer names
+/// that start with the tag (`/EmbeddedFiles`); `false` matches the whole
+/// name only, so `/JS` doesn't fire on `/JSON`.
+const TRIGGER_TAGS: &[(&str, bool, &str)] = &[
+    ("/OpenAction", true, "Found an /OpenAction tag."),
+    ("/AA", true, "Found an /AA (Additional-Actions) tag."),
+    ("/JavaScript", false, "Found a /JavaScript action; the document contains script."),
+    ("/JS", false, "Found a /JS entry with script code."),
+    ("/Launch", false, "Found a /Launch action, which can start other programs."),
+    ("/EmbeddedFile", true, "Found an /EmbeddedFile; the document carries an attached file."),
+    ("/URI", false, "Found a /URI action that opens a web address."),
+];
+
+/// Upper bound on the inflated size of a single stream, so a tiny
+/// decompression bomb can't exhaust memory.
+const MAX_INFLATED_STREAM_BYTES: u64 = 16 * 1024 * 1024;
+
+/// Upper bound on the inflated size of all streams in one file together,
+/// so hundreds of small streams can't add up to what one large one can't.
+const MAX_INFLATED_TOTAL_BYTES: u64 = 64 * 1024 * 1024;
+
+/// Script calls inside XFA that are rarely needed by legitimate forms.
+const XFA_SUSPICIOUS_CALLS: &[&str] = &[
+    "app.launchURL",
+    "xfa.host.gotoURL",
+    "app.openDoc",
+    "util.printf",
+    "eval(",
+    "unescape(",
+    "String.fromCharCode",
+];
+
+impl PdfFinding {
+    fn new(rule: &str, description: String, feedback: &FeedbackStore) -> PdfFinding {
+        let base_weight = RULE_WEIGHTS
+            .iter()
+            .find(|(id, _)| *id == rule)
+            .map_or(10, |(_, weight)| *weight);
+        PdfFinding {
+            rule: rule.to_string(),
+            description,
+            weight: feedback.weight(rule, base_weight),
+        }
+    }
+}
+
+/// Runs a helper program on the file and records how that went.
+fn run_tool(program: &str, file_path: &str) -> ToolOutput {
+    match Command::new(program).arg(file_path).output() {
+        Err(e) if e.kind() == io::ErrorKind::NotFound => ToolOutput::NotInstalled,
+        Err(e) => ToolOutput::Failed(e.to_string()),
+        Ok(output) if output.status.success() => ToolOutput::Ok(String::from_utf8_lossy(&output.stdout).to_string()),
+        Ok(output) => {
+            let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+            ToolOutput::Failed(if stderr.is_empty() { output.status.to_string() } else { stderr })
+        }
+    }
+}
+
+/// Runs pdfid.py on the file if available
+fn run_pdfid(file_path: &str) -> ToolOutput {
+    run_tool(PDFID_PROGRAM, file_path)
 }
 
 /// Extracts PDF metadata using pdfinfo if available
-fn get_pdf_metadata(file_path: &str) -> Option<String> {
-    Command::new("pdfinfo")
-        .arg(file_path)
-        .output()
-        .ok()
-        .and_then(|output| {
-            if output.status.success() {
-                Some(String::from_utf8_lossy(&output.stdout).to_string())
-            } else {
-                None
+fn get_pdf_metadata(file_path: &str) -> ToolOutput {
+    run_tool(PDFINFO_PROGRAM, file_path)
+}
+
+/// Overrides where scan results are kept.
+const SCAN_DIR_ENV: &str = "BIGMAN_SCAN_DIR";
+
+/// Where scan results are saved and loaded from. The browser starts the
+/// native messaging host in its own working directory, so this must not
+/// depend on the CWD: `$BIGMAN_SCAN_DIR` if set, otherwise `pdf_scans` in
+/// the per-user data directory.
+pub fn scan_output_dir() -> PathBuf {
+    match std::env::var_os(SCAN_DIR_ENV).filter(|value| !value.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => user_data_dir().join("pdf_scans"),
+    }
+}
+
+/// Older results are appended to this gzip'd JSON-lines file by
+/// `compact_scan_results` so the directory doesn't fill with small files.
+const SCAN_ARCHIVE_FILE: &str = "archive.jsonl.gz";
+
+/// How many of the newest results stay as loose `.json` files after compaction.
+const KEEP_LOOSE_SCANS: usize = 200;
+
+/// Once the archive grows past the retention limit it is renamed to
+/// `archive-<timestamp>.jsonl.gz` and a new one is started.
+const SEALED_ARCHIVE_PREFIX: &str = "archive-";
+
+/// Saves scan result to a file in the scan output directory
+fn save_scan_result(result: &PdfScanResult) {
+    let _ = write_loose_scan(result);
+
+    let scan_dir = &scan_output_dir();
+    // Compact in batches rather than on every save.
+    if loose_scan_files(scan_dir).len() > KEEP_LOOSE_SCANS * 2 {
+        let _ = compact_scan_results();
+    }
+}
+
+fn write_loose_scan(result: &PdfScanResult) -> std::io::Result<()> {
+    let scan_dir = &scan_output_dir();
+    fs::create_dir_all(scan_dir)?;
+    let json = serde_json::to_string_pretty(result).map_err(std::io::Error::other)?;
+    fs::write(scan_dir.join(format!("{}.json", result.scan_id)), json)
+}
+
+/// Re-saves a result after the user edited its notes. Archived results get
+/// a loose copy, which takes precedence over the archived one when loading.
+pub fn save_scan_notes(result: &PdfScanResult) -> std::io::Result<()> {
+    write_loose_scan(result)
+}
+
+fn loose_scan_files(scan_dir: &Path) -> Vec<std::path::PathBuf> {
+    fs::read_dir(scan_dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn read_loose_scan(path: &Path) -> Option<PdfScanResult> {
+    serde_json::from_str(&fs::read_to_string(path).ok()?).ok()
+}
+
+/// Moves all but the newest `KEEP_LOOSE_SCANS` loose results into the
+/// compressed archive and deletes them. Each run appends a new gzip member,
+/// so existing archive data is never rewritten. Returns how many results
+/// were archived.
+pub fn compact_scan_results() -> std::io::Result<usize> {
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+
+    let scan_dir = &scan_output_dir();
+    let mut loose: Vec<(std::path::PathBuf, PdfScanResult)> = loose_scan_files(scan_dir)
+        .into_iter()
+        .filter_map(|path| read_loose_scan(&path).map(|result| (path, result)))
+        .collect();
+    if loose.len() <= KEEP_LOOSE_SCANS {
+        return Ok(0);
+    }
+
+    // Newest first; everything past the keep limit gets archived.
+    loose.sort_by_key(|(_, result)| std::cmp::Reverse(result.timestamp));
+    let old = loose.split_off(KEEP_LOOSE_SCANS);
+
+    let mut lines = Vec::new();
+    for (_, result) in &old {
+        serde_json::to_writer(&mut lines, result).map_err(std::io::Error::other)?;
+        lines.push(b'\n');
+    }
+
+    let archive = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(scan_dir.join(SCAN_ARCHIVE_FILE))?;
+    let mut encoder = GzEncoder::new(archive, Compression::default());
+    encoder.write_all(&lines)?;
+    encoder.finish()?.sync_all()?;
+
+    // Only delete once the archive is safely on disk. A crash in between
+    // leaves duplicates, which loading de-duplicates by scan id.
+    for (path, _) in &old {
+        let _ = fs::remove_file(path);
+    }
+    Ok(old.len())
+}
+
+/// Seals the active archive once it reaches `limit` bytes so it can later be
+/// pruned as a whole. Returns the sealed file's path if it was rotated.
+pub fn seal_scan_archive(limit: u64) -> std::io::Result<Option<std::path::PathBuf>> {
+    let scan_dir = &scan_output_dir();
+    let active = scan_dir.join(SCAN_ARCHIVE_FILE);
+    if fs::metadata(&active).map_or(true, |meta| meta.len() < limit) {
+        return Ok(None);
+    }
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    let sealed = scan_dir.join(format!("{}{}.jsonl.gz", SEALED_ARCHIVE_PREFIX, timestamp));
+    fs::rename(&active, &sealed)?;
+    Ok(Some(sealed))
+}
+
+/// Sealed archives, oldest first. Only these are ever pruned; the active
+/// archive and loose results are left alone.
+pub fn sealed_scan_archives() -> Vec<std::path::PathBuf> {
+    let mut sealed: Vec<_> = fs::read_dir(scan_output_dir())
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| {
+                    path.file_name()
+                        .and_then(|name| name.to_str())
+                        .is_some_and(|name| name.starts_with(SEALED_ARCHIVE_PREFIX) && name.ends_with(".jsonl.gz"))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    // The timestamps have the same number of digits, so names sort by age.
+    sealed.sort();
+    sealed
+}
+
+/// Progress messages from `load_scan_results_in_background`.
+pub enum ScanLoadProgress {
+    /// Loading started; `total` is the number of files that will be read.
+    Started { total: usize },
+    /// Some results were loaded, completing `files_done` more files.
+    /// Archive results come in file order, so later ones are newer; loose
+    /// files are newer than anything in the archive.
+    Batch { results: Vec<PdfScanResult>, files_done: usize, from_archive: bool },
+    Finished,
+}
+
+/// Results are sent back in groups of this size so the list fills in
+/// gradually without flooding the channel.
+const LOAD_BATCH_SIZE: usize = 64;
+
+/// Loads every saved result, from both the archive and the loose files, on
+/// background threads and streams them back as they are parsed. Loose files
+/// are split across one reader per CPU. Results may arrive in any order and
+/// may contain duplicates (the archive and loose files overlap after an
+/// interrupted compaction or a notes edit), so the caller de-duplicates by
+/// scan id, preferring loose files.
+pub fn load_scan_results_in_background() -> mpsc::Receiver<ScanLoadProgress> {
+    use flate2::read::MultiGzDecoder;
+    use std::io::{BufRead, BufReader};
+
+    let (sender, receiver) = mpsc::channel();
+
+    std::thread::spawn(move || {
+        let scan_dir = &scan_output_dir();
+        let files = loose_scan_files(scan_dir);
+        let mut archives = sealed_scan_archives();
+        archives.push(scan_dir.join(SCAN_ARCHIVE_FILE));
+        archives.retain(|path| path.exists());
+        let _ = sender.send(ScanLoadProgress::Started { total: files.len() + archives.len() });
+
+        let workers = std::thread::available_parallelism().map_or(4, |n| n.get());
+        let chunk_size = files.len().div_ceil(workers).max(1);
+
+        std::thread::scope(|scope| {
+            if !archives.is_empty() {
+                let sender = sender.clone();
+                scope.spawn(move || {
+                    // Oldest archive first, so results keep arriving in file order.
+                    for archive_path in &archives {
+                        let mut batch = Vec::new();
+                        if let Ok(archive) = fs::File::open(archive_path) {
+                            for line in BufReader::new(MultiGzDecoder::new(archive)).lines() {
+                                let Ok(line) = line else { break };
+                                if let Ok(result) = serde_json::from_str::<PdfScanResult>(&line) {
+                                    batch.push(result);
+                                }
+                                if batch.len() >= LOAD_BATCH_SIZE {
+                                    let results = std::mem::take(&mut batch);
+                                    let _ = sender.send(ScanLoadProgress::Batch { results, files_done: 0, from_archive: true });
+                                }
+                            }
+                        }
+                        let _ = sender.send(ScanLoadProgress::Batch { results: batch, files_done: 1, from_archive: true });
+                    }
+                });
             }
+
+            for chunk in files.chunks(chunk_size) {
+                let sender = sender.clone();
+                scope.spawn(move || {
+                    let mut batch = Vec::new();
+                    let mut files_done = 0;
+                    for path in chunk {
+                        batch.extend(read_loose_scan(path));
+                        files_done += 1;
+                        if files_done == LOAD_BATCH_SIZE {
+                            let results = std::mem::take(&mut batch);
+                            let _ = sender.send(ScanLoadProgress::Batch { results, files_done, from_archive: false });
+                            files_done = 0;
+                        }
+                    }
+                    let _ = sender.send(ScanLoadProgress::Batch { results: batch, files_done, from_archive: false });
+                });
+            }
+        });
+
+        let _ = sender.send(ScanLoadProgress::Finished);
+    });
+
+    receiver
+}
+
+/// Files at or below this size that run script but carry no real content are
+/// treated as likely droppers.
+const MINIMAL_PDF_MAX_BYTES: usize = 16 * 1024;
+
+/// Returns true if `content` contains `needle` anywhere.
+fn contains_tag(content: &[u8], needle: &[u8]) -> bool {
+    content.windows(needle.len()).any(|window| window == needle)
+}
+
+/// Counts occurrences of a PDF name keyword such as `/Page`, ignoring longer
+/// names that merely share the prefix (`/Pages`), like pdfid does. A keyword
+/// that ends `content` counts too.
+fn count_keyword(content: &[u8], keyword: &[u8]) -> u32 {
+    count_keyword_before_end(content, keyword) + u32::from(content.ends_with(keyword))
+}
+
+/// `count_keyword` for a buffer that more bytes may follow: a keyword right
+/// at the end is left out, since the next byte could make it a longer name.
+fn count_keyword_before_end(content: &[u8], keyword: &[u8]) -> u32 {
+    content
+        .windows(keyword.len() + 1)
+        .filter(|window| window.starts_with(keyword) && !window[keyword.len()].is_ascii_alphanumeric())
+        .count() as u32
+}
+
+/// Parses pdfid's keyword table (lines like ` /JS      2`) into counts.
+fn parse_pdfid_counts(output: &str) -> HashMap<String, u32> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            let keyword = parts.next()?;
+            let count = parts.next()?.parse().ok()?;
+            keyword.starts_with('/').then(|| (keyword.to_string(), count))
         })
+        .collect()
 }
 
-/// Saves scan result to a file in pdf_scans directory
-fn save_scan_result(result: &PdfScanResult) {
-    let scan_dir = Path::new("pdf_scans");
-    if !scan_dir.exists() {
-        let _ = fs::create_dir_all(scan_dir);
+/// Reads the `Pages:` field from pdfinfo output.
+fn parse_page_count(metadata: &str) -> Option<u32> {
+    metadata
+        .lines()
+        .find_map(|line| line.strip_prefix("Pages:"))
+        .and_then(|pages| pages.trim().parse().ok())
+}
+
+/// Flags "minimal content + active scripting": a tiny document with at most one
+/// page and no fonts that still carries JavaScript. Each of those tags is
+/// harmless on its own, but together they describe a PDF whose only purpose is
+/// to run a script. Prefers pdfid/pdfinfo numbers and falls back to counting
+/// keywords in the raw bytes when the tools are unavailable.
+fn check_minimal_pdf(content: &[u8], pdfid_counts: &HashMap<String, u32>, metadata: Option<&str>) -> Option<String> {
+    let count = |keyword: &str| {
+        pdfid_counts
+            .get(keyword)
+            .copied()
+            .unwrap_or_else(|| count_keyword(content, keyword.as_bytes()))
+    };
+
+    let scripts = count("/JS") + count("/JavaScript");
+    let fonts = count("/Font");
+    let pages = metadata.and_then(parse_page_count).unwrap_or_else(|| count("/Page"));
+
+    if scripts > 0 && fonts == 0 && pages <= 1 && content.len() <= MINIMAL_PDF_MAX_BYTES {
+        Some(format!(
+            "Minimal PDF with active scripting: {} bytes, {} page(s), no fonts, but {} JavaScript reference(s). \
+             Documents with no real content that still run script are typical droppers.",
+            content.len(),
+            pages,
+            scripts
+        ))
+    } else {
+        None
+    }
+}
+
+/// Returns the position of the first occurrence of `needle` at or after `from`.
+fn find_from(content: &[u8], needle: &[u8], from: usize) -> Option<usize> {
+    content
+        .get(from..)?
+        .windows(needle.len())
+        .position(|window| window == needle)
+        .map(|pos| pos + from)
+}
+
+/// Parses a run of ASCII digits starting at `pos`.
+fn parse_number(content: &[u8], pos: usize) -> Option<(u32, usize)> {
+    let digits = content.get(pos..)?.iter().take_while(|b| b.is_ascii_digit()).count();
+    let number = std::str::from_utf8(&content[pos..pos + digits]).ok()?.parse().ok()?;
+    Some((number, pos + digits))
+}
+
+fn skip_whitespace(content: &[u8], mut pos: usize) -> usize {
+    while content.get(pos).is_some_and(|b| b.is_ascii_whitespace()) {
+        pos += 1;
+    }
+    pos
+}
+
+/// Parses an indirect reference (`12 0 R`) at `pos`.
+fn parse_reference(content: &[u8], pos: usize) -> Option<((u32, u32), usize)> {
+    let (number, pos) = parse_number(content, pos)?;
+    let (generation, pos) = parse_number(content, skip_whitespace(content, pos))?;
+    let pos = skip_whitespace(content, pos);
+    (content.get(pos) == Some(&b'R')).then_some(((number, generation), pos + 1))
+}
+
+/// Returns the body of indirect object `number generation obj ... endobj`.
+fn find_object(content: &[u8], (number, generation): (u32, u32)) -> Option<&[u8]> {
+    let header = format!("{} {} obj", number, generation);
+    let mut from = 0;
+    loop {
+        let start = find_from(content, header.as_bytes(), from)?;
+        // Don't mistake "112 0 obj" for "12 0 obj".
+        if start == 0 || !content[start - 1].is_ascii_digit() {
+            let body_start = start + header.len();
+            let end = find_from(content, b"endobj", body_start).unwrap_or(content.len());
+            return Some(&content[body_start..end]);
+        }
+        from = start + 1;
+    }
+}
+
+/// Extracts the data of a stream object, inflating it if it declares
+/// `/FlateDecode`. Returns `None` if the object has no stream or the data
+/// can't be decoded.
+fn object_stream_data(object: &[u8]) -> Option<Vec<u8>> {
+    let keyword = find_from(object, b"stream", 0)?;
+    let dictionary = &object[..keyword];
+    let mut start = keyword + b"stream".len();
+    if object.get(start) == Some(&b'\r') {
+        start += 1;
+    }
+    if object.get(start) == Some(&b'\n') {
+        start += 1;
+    }
+    let end = find_from(object, b"endstream", start).unwrap_or(object.len());
+    let raw = &object[start..end];
+
+    if contains_tag(dictionary, b"/FlateDecode") {
+        inflate_stream(raw, MAX_INFLATED_STREAM_BYTES)
+    } else if contains_tag(dictionary, b"/Filter") {
+        // Some other encoding we don't understand.
+        None
+    } else {
+        Some(raw.to_vec())
+    }
+}
+
+/// Inflates zlib-compressed stream data, keeping at most `limit` bytes.
+fn inflate_stream(raw: &[u8], limit: u64) -> Option<Vec<u8>> {
+    use flate2::read::ZlibDecoder;
+    use std::io::Read;
+
+    let mut inflated = Vec::new();
+    ZlibDecoder::new(raw)
+        .take(limit)
+        .read_to_end(&mut inflated)
+        .ok()?;
+    Some(inflated)
+}
+
+/// Whether `data` holds `tag`. With `more_follows`, a whole-name tag that
+/// ends `data` waits for the next buffer to show how the name ends.
+fn has_trigger_tag(data: &[u8], tag: &str, prefix: bool, more_follows: bool) -> bool {
+    if prefix {
+        contains_tag(data, tag.as_bytes())
+    } else if more_follows {
+        count_keyword_before_end(data, tag.as_bytes()) > 0
+    } else {
+        count_keyword(data, tag.as_bytes()) > 0
+    }
+}
+
+/// What one streaming pass over the raw file found.
+struct RawScan {
+    sha256: String,
+    md5: String,
+    /// Tags from `TRIGGER_TAGS` present in the raw bytes.
+    tags: HashSet<&'static str>,
+}
+
+/// Hashes `reader` (SHA-256 and MD5) and searches it for `TRIGGER_TAGS` one buffer at a time.
+/// The tail of each buffer is kept in front of the next one, long enough
+/// for the longest tag plus the byte after it, so a tag split across two
+/// reads is still found.
+fn scan_raw(mut reader: impl Read, buffer_size: usize) -> io::Result<RawScan> {
+    use md5::Md5;
+    use sha2::{Digest, Sha256};
+
+    let overlap = TRIGGER_TAGS.iter().map(|(tag, _, _)| tag.len()).max().unwrap_or(0) + 1;
+    let mut hasher = Sha256::new();
+    let mut md5 = Md5::new();
+    let mut tags = HashSet::new();
+    let mut buffer = vec![0u8; buffer_size];
+    let mut window = Vec::with_capacity(buffer_size + overlap);
+
+    loop {
+        let read = match reader.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        hasher.update(&buffer[..read]);
+        md5.update(&buffer[..read]);
+        window.extend_from_slice(&buffer[..read]);
+        for (tag, prefix, _) in TRIGGER_TAGS {
+            if !tags.contains(tag) && has_trigger_tag(&window, tag, *prefix, true) {
+                tags.insert(*tag);
+            }
+        }
+        window.drain(..window.len().saturating_sub(overlap));
+    }
+    // Nothing follows the last bytes, so a tag there is complete.
+    for (tag, prefix, _) in TRIGGER_TAGS {
+        if has_trigger_tag(&window, tag, *prefix, false) {
+            tags.insert(*tag);
+        }
+    }
+
+    Ok(RawScan {
+        sha256: format!("{:x}", hasher.finalize()),
+        md5: format!("{:x}", md5.finalize()),
+        tags,
+    })
+}
+
+/// What searching the compressed streams of a file found.
+struct StreamScan {
+    /// Tags from `TRIGGER_TAGS` present in an inflated stream.
+    tags: HashSet<&'static str>,
+    /// Streams that failed to decompress.
+    undecodable: usize,
+    /// Streams left compressed because `budget` ran out first.
+    over_budget: usize,
+    /// Total bytes inflated, never more than `budget`.
+    inflated: u64,
+}
+
+/// Inflates each `/FlateDecode` stream in the file in turn and searches it
+/// for `TRIGGER_TAGS`, so tags hidden in compressed object streams are
+/// found. Only one inflated stream is held at a time, and no more than
+/// `budget` bytes are inflated in total.
+fn scan_streams(content: &[u8], budget: u64) -> StreamScan {
+    let mut scan = StreamScan {
+        tags: HashSet::new(),
+        undecodable: 0,
+        over_budget: 0,
+        inflated: 0,
+    };
+    let mut from = 0;
+
+    while let Some(keyword) = find_from(content, b"stream", from) {
+        from = keyword + b"stream".len();
+        if content[..keyword].ends_with(b"end") {
+            continue;
+        }
+        // The stream dictionary sits between `N G obj` and `stream`
+        let object_start = content[..keyword].windows(3).rposition(|window| window == b"obj").unwrap_or(0);
+        if !contains_tag(&content[object_start..keyword], b"/FlateDecode") {
+            continue;
+        }
+        let mut start = from;
+        if content.get(start) == Some(&b'\r') {
+            start += 1;
+        }
+        if content.get(start) == Some(&b'\n') {
+            start += 1;
+        }
+        let end = find_from(content, b"endstream", start).unwrap_or(content.len());
+        from = end;
+
+        let remaining = budget - scan.inflated;
+        if remaining == 0 {
+            scan.over_budget += 1;
+            continue;
+        }
+        let Some(data) = inflate_stream(&content[start..end], remaining.min(MAX_INFLATED_STREAM_BYTES)) else {
+            scan.undecodable += 1;
+            continue;
+        };
+        scan.inflated += data.len() as u64;
+        for (tag, prefix, _) in TRIGGER_TAGS {
+            if !scan.tags.contains(tag) && has_trigger_tag(&data, tag, *prefix, false) {
+                scan.tags.insert(*tag);
+            }
+        }
+    }
+
+    scan
+}
+
+/// Finds the objects referenced by `/XFA` entries. The value is either a
+/// single stream reference or an array of `(packet-name) N G R` pairs.
+fn find_xfa_references(content: &[u8]) -> Vec<(u32, u32)> {
+    let mut references = Vec::new();
+    let mut from = 0;
+
+    while let Some(tag) = find_from(content, b"/XFA", from) {
+        let mut pos = skip_whitespace(content, tag + b"/XFA".len());
+        from = pos;
+
+        if content.get(pos) == Some(&b'[') {
+            let end = find_from(content, b"]", pos).unwrap_or(content.len());
+            while pos < end {
+                match parse_reference(content, pos) {
+                    Some((reference, next)) => {
+                        references.push(reference);
+                        pos = next;
+                    }
+                    None => pos += 1,
+                }
+            }
+        } else if let Some((reference, _)) = parse_reference(content, pos) {
+            references.push(reference);
+        }
     }
 
-    let scan_file = scan_dir.join(format!("{}.json", result.scan_id));
-    let _ = fs::write(scan_file, serde_json::to_string_pretty(result).unwrap_or_default());
+    references.sort_unstable();
+    references.dedup();
+    references
 }
 
-/// Scans a PDF file for suspicious auto-action tags.
-pub fn scan_pdf_for_actions(file_path: &str) -> PdfScanResult {
+/// Pulls the XFA packets out of the document and scans the form definitions
+/// for scripts and data submission, which a raw tag search never sees because
+/// the XML usually sits in a compressed stream.
+fn scan_xfa_forms(content: &[u8], feedback: &FeedbackStore) -> Vec<PdfFinding> {
+    let mut findings = Vec::new();
+    let references = find_xfa_references(content);
+    if references.is_empty() {
+        return findings;
+    }
+
+    let mut xml = String::new();
+    let mut undecodable = 0;
+    for reference in references {
+        match find_object(content, reference).and_then(object_stream_data) {
+            Some(data) => xml.push_str(&String::from_utf8_lossy(&data)),
+            None => undecodable += 1,
+        }
+    }
+
+    let scripts = xml.matches("<script").count();
+    if scripts > 0 {
+        findings.push(PdfFinding::new(
+            "pdf:xfa-script",
+            format!("XFA: form definition contains {} embedded script block(s).", scripts),
+            feedback,
+        ));
+    }
+
+    for call in XFA_SUSPICIOUS_CALLS {
+        if xml.contains(call) {
+            findings.push(PdfFinding::new(
+                "pdf:xfa-call",
+                format!("XFA: form script calls {}.", call.trim_end_matches('(')),
+                feedback,
+            ));
+        }
+    }
+
+    for submit in xml.match_indices("<submit").map(|(pos, _)| &xml[pos..]) {
+        let element = &submit[..submit.find('>').unwrap_or(submit.len())];
+        if let Some(target) = element.split("target=\"").nth(1).and_then(|rest| rest.split('"').next()) {
+            findings.push(PdfFinding::new(
+                "pdf:xfa-submit",
+                format!("XFA: form submits data to {}.", target),
+                feedback,
+            ));
+        }
+    }
+
+    if undecodable > 0 {
+        findings.push(PdfFinding::new(
+            "pdf:xfa-undecodable",
+            format!("XFA: could not decode {} form packet stream(s); hidden form content was not inspected.", undecodable),
+            feedback,
+        ));
+    }
+
+    findings
+}
+
+/// Runs every content check over the raw bytes of a PDF. The helper tool
+/// output, when available, sharpens the minimal-dropper check.
+fn pdf_findings(content: &[u8], raw: &RawScan, pdfid_output: Option<&str>, metadata: Option<&str>, context: &ScanContext) -> Vec<PdfFinding> {
+    let feedback = &context.feedback;
+    let iocs = &context.iocs;
+    // A file already known to be bad needs no further analysis. The digests
+    // come from the raw pass, which saw all of the file.
+    if let Some(finding) = iocs.find_digest_match(&raw.sha256, &raw.md5) {
+        return vec![PdfFinding::new("pdf:ioc-hash", format!("Matched known-bad hash ({}).", finding), feedback)];
+    }
+
+    let mut findings = Vec::new();
+    let pdfid_counts = pdfid_output.map(parse_pdfid_counts).unwrap_or_default();
+    let streams = scan_streams(content, MAX_INFLATED_TOTAL_BYTES);
+    for (tag, _, description) in TRIGGER_TAGS {
+        let rule = format!("pdf:{}", tag);
+        if raw.tags.contains(tag) {
+            findings.push(PdfFinding::new(&rule, description.to_string(), feedback));
+        } else if streams.tags.contains(tag) {
+            let description = format!("{} (inside a compressed stream)", description.trim_end_matches('.'));
+            findings.push(PdfFinding::new(&rule, description + ".", feedback));
+        } else if let Some(&count) = pdfid_counts.get(*tag).filter(|&&count| count > 0) {
+            // pdfid decodes escaped names like `/J#53`, which the byte search can't see
+            let description = format!("{} (pdfid counted {}, under an obfuscated name)", description.trim_end_matches('.'), count);
+            findings.push(PdfFinding::new(&rule, description + ".", feedback));
+        }
+    }
+    if streams.undecodable > 0 {
+        findings.push(PdfFinding::new(
+            "pdf:undecompressable",
+            format!("Could not decompress {} stream(s); only their raw bytes were scanned.", streams.undecodable),
+            feedback,
+        ));
+    }
+    if streams.over_budget > 0 {
+        findings.push(PdfFinding::new(
+            "pdf:inflate-limit",
+            format!(
+                "Stopped decompressing after {} MiB; {} more stream(s) were only scanned raw.",
+                MAX_INFLATED_TOTAL_BYTES / MIB,
+                streams.over_budget
+            ),
+            feedback,
+        ));
+    }
+    for finding in iocs.find_network_matches(&String::from_utf8_lossy(content)) {
+        findings.push(PdfFinding::new("pdf:ioc-network", format!("{}.", finding), feedback));
+    }
+    findings.extend(scan_xfa_forms(content, feedback));
+    if let Some(finding) = check_minimal_pdf(content, &pdfid_counts, metadata) {
+        findings.push(PdfFinding::new("pdf:minimal-scripted", finding, feedback));
+    }
+    findings
+}
+
+fn file_name_findings(file_path: &str, source: NameSource, feedback: &FeedbackStore) -> Vec<PdfFinding> {
+    check_file_name(file_path, source)
+        .into_iter()
+        .map(|issue| PdfFinding::new("pdf:file-name", format!("{}.", issue), feedback))
+        .collect()
+}
+
+fn build_scan_result(
+    file_path: &str,
+    sha256: String,
+    pdfid_output: ToolOutput,
+    metadata: ToolOutput,
+    findings: Vec<PdfFinding>,
+) -> PdfScanResult {
     let timestamp = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .unwrap_or_default()
         .as_secs();
 
-    let scan_id = format!("scan_{}_{}",
+    let mut scan_id = format!("scan_{}_{}",
         timestamp,
         Path::new(file_path)
             .file_name()
@@ -74,55 +968,411 @@ pub fn scan_pdf_for_actions(file_path: &str) -> PdfScanResult {
             .unwrap_or("unknown")
             .replace(' ', "_")
     );
+    // Same-named files from different folders, scanned in the same second,
+    // must not overwrite each other's saved result.
+    if let Some(prefix) = sha256.get(..8) {
+        scan_id = format!("{}_{}", scan_id, prefix);
+    }
 
-    let pdfid_output = run_pdfid(file_path);
-    let metadata = get_pdf_metadata(file_path);
-
-    let result = match std::fs::read(file_path) {
-        Ok(content) => {
-            if content.windows(b"/OpenAction".len()).any(|window| window == b"/OpenAction") {
-                PdfScanResult {
-                    file_path: file_path.to_string(),
-                    timestamp,
-                    is_suspicious: true,
-                    reason: "Found an /OpenAction tag.".to_string(),
-                    scan_id,
-                    pdfid_output,
-                    metadata,
-                }
-            } else if content.windows(b"/AA".len()).any(|window| window == b"/AA") {
-                PdfScanResult {
-                    file_path: file_path.to_string(),
-                    timestamp,
-                    is_suspicious: true,
-                    reason: "Found an /AA (Additional-Actions) tag.".to_string(),
-                    scan_id,
-                    pdfid_output,
-                    metadata,
-                }
-            } else {
-                PdfScanResult {
-                    file_path: file_path.to_string(),
-                    timestamp,
-                    is_suspicious: false,
-                    reason: "Clean.".to_string(),
-                    scan_id,
-                    pdfid_output,
-                    metadata,
+    let reasons: Vec<String> = findings.iter().map(|f| f.description.clone()).collect();
+    let reason = if reasons.is_empty() { "Clean.".to_string() } else { reasons.join(" ") };
+
+    PdfScanResult {
+        file_path: file_path.to_string(),
+        timestamp,
+        is_suspicious: findings.iter().any(|f| f.weight > 0),
+        reason,
+        reasons,
+        scan_id,
+        sha256,
+        pdfid_counts: pdfid_output.text().map(parse_pdfid_counts).unwrap_or_default(),
+        pdfid_output,
+        metadata,
+        risk_score: findings.iter().map(|f| f.weight).sum(),
+        findings,
+        notes: String::new(),
+        detected_type: String::new(),
+    }
+}
+
+/// The `.pdf` files in `dir` (and with `recursive`, in its subfolders),
+/// sorted. Symlinked folders aren't followed, so a link loop can't trap the
+/// walk. Folders that couldn't be read are returned with their error.
+pub fn find_pdf_files(dir: &Path, recursive: bool) -> (Vec<PathBuf>, Vec<(PathBuf, io::Error)>) {
+    let mut files = Vec::new();
+    let mut unreadable = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(folder) = pending.pop() {
+        let entries = match fs::read_dir(&folder) {
+            Ok(entries) => entries,
+            Err(e) => {
+                unreadable.push((folder, e));
+                continue;
+            }
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if entry.file_type().is_ok_and(|kind| kind.is_dir()) {
+                if recursive {
+                    pending.push(path);
                 }
+            } else if path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("pdf")) && path.is_file() {
+                files.push(path);
             }
         }
-        Err(e) => PdfScanResult {
-            file_path: file_path.to_string(),
-            timestamp,
-            is_suspicious: true,
-            reason: format!("Could not read file: {}", e),
-            scan_id,
-            pdfid_output,
-            metadata,
+    }
+    files.sort();
+    (files, unreadable)
+}
+
+/// Scans a PDF file for suspicious auto-action tags and saves the result to
+/// the scan history. A file that can't be read is an error, not a
+/// suspicious result. Files in a trusted folder are reported clean without
+/// being read, and aren't saved.
+pub fn scan_pdf_for_actions(file_path: &str) -> Result<PdfScanResult, ScanError> {
+    scan_pdf_with(file_path, &ScanContext::load())
+}
+
+/// The saved configuration a scan consults. Batches load it once and share
+/// it between every file.
+#[derive(Debug, Default)]
+pub struct ScanContext {
+    pub settings: Settings,
+    pub iocs: IocList,
+    pub feedback: FeedbackStore,
+}
+
+impl ScanContext {
+    pub fn load() -> ScanContext {
+        ScanContext {
+            settings: Settings::load(),
+            iocs: IocList::load(),
+            feedback: FeedbackStore::load(),
+        }
+    }
+}
+
+/// `scan_pdf_for_actions` with configuration that is already loaded.
+pub fn scan_pdf_with(file_path: &str, context: &ScanContext) -> Result<PdfScanResult, ScanError> {
+    let settings = &context.settings;
+    if let Some(reason) = settings.trusted_path_reason(Path::new(file_path)) {
+        let skipped = ToolOutput::Skipped("the file is in a trusted folder".to_string());
+        let mut result = build_scan_result(file_path, String::new(), skipped.clone(), skipped, Vec::new());
+        result.reason = reason;
+        return Ok(result);
+    }
+    let result = scan_pdf_file(file_path, u64::from(settings.max_pdf_scan_mb) * MIB, context)?;
+    save_scan_result(&result);
+    Ok(result)
+}
+
+/// The scan itself, without touching the scan history. `limit` is the
+/// largest file size in bytes that is read; 0 means no limit.
+fn scan_pdf_file(file_path: &str, limit: u64, context: &ScanContext) -> Result<PdfScanResult, ScanError> {
+    let size = fs::metadata(file_path)?.len();
+    if limit > 0 && size > limit {
+        return Err(ScanError::TooLarge { size, limit });
+    }
+    let raw = scan_raw(fs::File::open(file_path)?, SCAN_BUFFER_BYTES)?;
+    // Bounded by the read itself rather than the size above, in case the
+    // file grew in the meantime.
+    let mut content = Vec::new();
+    fs::File::open(file_path)?.take(MAX_STRUCTURE_BYTES + 1).read_to_end(&mut content)?;
+    let truncated = content.len() as u64 > MAX_STRUCTURE_BYTES;
+    content.truncate(MAX_STRUCTURE_BYTES as usize);
+
+    let mut notes = Vec::new();
+    if truncated {
+        let description = format!(
+            "Only the first {} MiB were checked for compressed streams, XFA forms and structure.",
+            MAX_STRUCTURE_BYTES / MIB
+        );
+        notes.push(PdfFinding::new("pdf:structure-truncated", description, &context.feedback));
+    }
+    let tools = (run_pdfid(file_path), get_pdf_metadata(file_path));
+    Ok(scan_loaded_pdf(file_path, NameSource::Local, &content, raw, tools, notes, context))
+}
+
+/// Neither helper can run when there's nowhere to put a copy of the file.
+fn scratch_failure(e: &io::Error) -> (ToolOutput, ToolOutput) {
+    let failed = ToolOutput::Failed(format!("could not write a scratch copy: {}", e));
+    (failed.clone(), failed)
+}
+
+/// Scans a PDF that only exists in memory (e.g. an archive entry). The
+/// pdfid/pdfinfo helpers need a file on disk, so the content is written to
+/// a private scratch directory for them; if that fails only the built-in
+/// checks run. The result is not saved to the scan history.
+pub fn scan_pdf_bytes(name: &str, source: NameSource, content: &[u8], context: &ScanContext) -> PdfScanResult {
+    let tools = match ScratchDir::new("pdf") {
+        Ok(scratch) => match scratch.write_file(name, content) {
+            Ok(path) => {
+                let path = path.to_string_lossy();
+                (run_pdfid(&path), get_pdf_metadata(&path))
+            }
+            Err(e) => scratch_failure(&e),
         },
+        Err(e) => scratch_failure(&e),
     };
 
-    save_scan_result(&result);
+    let raw = scan_raw(content, SCAN_BUFFER_BYTES).expect("reading from memory can't fail");
+    scan_loaded_pdf(name, source, content, raw, tools, Vec::new(), context)
+}
+
+/// A finding per configured YARA rule that matches. A ruleset that can't
+/// run is reported, but doesn't count towards the verdict.
+#[cfg(feature = "yara")]
+fn yara_findings(name: &str, content: &[u8], feedback: &FeedbackStore) -> Vec<PdfFinding> {
+    match crate::yara::scan_bytes(name, content) {
+        Ok(rules) => rules
+            .unwrap_or_default()
+            .into_iter()
+            .map(|rule| PdfFinding::new("pdf:yara", format!("YARA rule {} matched.", rule), feedback))
+            .collect(),
+        Err(e) => vec![PdfFinding::new("pdf:yara-failed", format!("YARA rules could not run: {}.", e), feedback)],
+    }
+}
+
+/// The checks shared by files on disk and in-memory documents, once the
+/// raw pass and the helper tools have run. `tools` holds the pdfid and
+/// pdfinfo output. `notes` are findings the caller already has, reported
+/// after the checks' own.
+fn scan_loaded_pdf(
+    name: &str,
+    source: NameSource,
+    content: &[u8],
+    raw: RawScan,
+    (pdfid_output, metadata): (ToolOutput, ToolOutput),
+    notes: Vec<PdfFinding>,
+    context: &ScanContext,
+) -> PdfScanResult {
+    let feedback = &context.feedback;
+    let mut findings = file_name_findings(name, source, feedback);
+    let kind = detect_kind(content);
+    findings.extend(type_mismatch_finding(name, kind, feedback));
+    findings.extend(pdf_findings(content, &raw, pdfid_output.text(), metadata.text(), context));
+    #[cfg(feature = "yara")]
+    findings.extend(yara_findings(name, content, feedback));
+    findings.extend(notes);
+    let mut result = build_scan_result(name, raw.sha256, pdfid_output, metadata, findings);
+    result.detected_type = kind.to_string();
     result
-} 
\ No newline at end of file
+}
+
+/// A file named `.pdf` whose header says it's something else, such as an
+/// executable renamed to pass as a document.
+fn type_mismatch_finding(name: &str, kind: ContentKind, feedback: &FeedbackStore) -> Option<PdfFinding> {
+    let claims_pdf = Path::new(name).extension().is_some_and(|ext| ext.eq_ignore_ascii_case("pdf"));
+    (claims_pdf && kind != ContentKind::Pdf).then(|| {
+        let description = match kind {
+            ContentKind::Executable => "Claims .pdf but the header is a PE executable.".to_string(),
+            _ => format!("Claims .pdf but has no %PDF- header; the content looks like a {}.", kind),
+        };
+        PdfFinding::new("pdf:type-mismatch", description, feedback)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BUFFER: usize = 32;
+
+    /// Pads `tag` so it starts `before_edge` bytes before the end of the
+    /// first buffer, i.e. it is split across two reads.
+    fn split_at_boundary(tag: &str, before_edge: usize) -> Vec<u8> {
+        let mut data = vec![b' '; BUFFER - before_edge];
+        data.extend_from_slice(tag.as_bytes());
+        data.extend_from_slice(b" >>\nendobj\n");
+        data
+    }
+
+    #[test]
+    fn finds_tag_split_across_buffer_boundary() {
+        for before_edge in 1.."/OpenAction".len() {
+            let data = split_at_boundary("/OpenAction", before_edge);
+            let raw = scan_raw(data.as_slice(), BUFFER).unwrap();
+            assert!(raw.tags.contains("/OpenAction"), "missed tag split {} bytes before the edge", before_edge);
+        }
+    }
+
+    #[test]
+    fn finds_whole_name_tag_ending_exactly_on_boundary() {
+        // `/JS` fills the end of the first buffer; the byte that proves it
+        // isn't `/JSON` only arrives with the second read.
+        let data = split_at_boundary("/JS", "/JS".len());
+        let raw = scan_raw(data.as_slice(), BUFFER).unwrap();
+        assert!(raw.tags.contains("/JS"));
+
+        let data = split_at_boundary("/JSON", "/JS".len());
+        let raw = scan_raw(data.as_slice(), BUFFER).unwrap();
+        assert!(!raw.tags.contains("/JS"));
+    }
+
+    #[test]
+    fn finds_whole_name_tag_at_the_very_end() {
+        let mut data = vec![b' '; BUFFER + 5];
+        data.extend_from_slice(b"/JS");
+        let raw = scan_raw(data.as_slice(), BUFFER).unwrap();
+        assert!(raw.tags.contains("/JS"));
+        assert_eq!(count_keyword(&data, b"/JS"), 1);
+        assert_eq!(count_keyword(b"/JSON", b"/JS"), 0);
+    }
+
+    /// A small, well-formed PDF with `extra` added to its catalog.
+    fn pdf_with_catalog(extra: &str) -> Vec<u8> {
+        format!(
+            "%PDF-1.4\n1 0 obj\n<< /Type /Catalog /Pages 2 0 R {} >>\nendobj\n\
+             2 0 obj\n<< /Type /Pages /Kids [3 0 R] /Count 1 >>\nendobj\n\
+             3 0 obj\n<< /Type /Page /Parent 2 0 R /MediaBox [0 0 612 792] >>\nendobj\n\
+             trailer\n<< /Root 1 0 R >>\n%%EOF\n",
+            extra
+        )
+        .into_bytes()
+    }
+
+    /// Writes `content` to a scratch file and scans it without saving.
+    fn scan_fixture(name: &str, content: &[u8]) -> PdfScanResult {
+        let scratch = ScratchDir::new_in(&std::env::temp_dir(), "test").unwrap();
+        let path = scratch.write_file(name, content).unwrap();
+        scan_pdf_file(&path.to_string_lossy(), 0, &ScanContext::default()).unwrap()
+    }
+
+    #[test]
+    fn clean_pdf_is_not_suspicious() {
+        let result = scan_fixture("clean.pdf", &pdf_with_catalog(""));
+        assert!(!result.is_suspicious, "unexpected findings: {:?}", result.reasons);
+        assert!(result.reasons.is_empty());
+        assert_eq!(result.reason, "Clean.");
+        assert_eq!(result.risk_score, 0);
+    }
+
+    #[test]
+    fn open_action_is_suspicious() {
+        let result = scan_fixture("open.pdf", &pdf_with_catalog("/OpenAction 3 0 R"));
+        assert!(result.is_suspicious);
+        assert!(result.reasons.contains(&"Found an /OpenAction tag.".to_string()), "{:?}", result.reasons);
+        assert!(result.findings.iter().any(|f| f.rule == "pdf:/OpenAction"));
+    }
+
+    #[test]
+    fn additional_actions_are_suspicious() {
+        let result = scan_fixture("aa.pdf", &pdf_with_catalog("/AA << /O 3 0 R >>"));
+        assert!(result.is_suspicious);
+        assert!(result.reasons.contains(&"Found an /AA (Additional-Actions) tag.".to_string()), "{:?}", result.reasons);
+        assert!(!result.findings.iter().any(|f| f.rule == "pdf:/OpenAction"));
+    }
+
+    #[test]
+    fn known_bad_hash_short_circuits_the_scan() {
+        use sha2::{Digest, Sha256};
+
+        let content = pdf_with_catalog("/OpenAction 3 0 R");
+        let mut context = ScanContext::default();
+        context.iocs.sha256.insert(format!("{:x}", Sha256::digest(&content)));
+
+        let scratch = ScratchDir::new_in(&std::env::temp_dir(), "test").unwrap();
+        let path = scratch.write_file("known.pdf", &content).unwrap();
+        let result = scan_pdf_file(&path.to_string_lossy(), 0, &context).unwrap();
+        let rules: Vec<&str> = result.findings.iter().map(|f| f.rule.as_str()).collect();
+        assert_eq!(rules, ["pdf:ioc-hash"]);
+        assert!(result.is_suspicious);
+    }
+
+    #[test]
+    fn known_bad_md5_is_matched_on_the_whole_file_not_the_structural_cut() {
+        use md5::{Digest, Md5};
+
+        let mut content = pdf_with_catalog("");
+        content.resize(4 * SCAN_BUFFER_BYTES, b' ');
+        let mut context = ScanContext::default();
+        context.iocs.md5.insert(format!("{:x}", Md5::digest(&content)));
+
+        // As `scan_pdf_file` does for a file past `MAX_STRUCTURE_BYTES`.
+        let raw = scan_raw(content.as_slice(), SCAN_BUFFER_BYTES).unwrap();
+        let findings = pdf_findings(&content[..SCAN_BUFFER_BYTES], &raw, None, None, &context);
+        let rules: Vec<&str> = findings.iter().map(|f| f.rule.as_str()).collect();
+        assert_eq!(rules, ["pdf:ioc-hash"]);
+    }
+
+    #[test]
+    fn missing_file_is_an_error_not_a_verdict() {
+        let scratch = ScratchDir::new_in(&std::env::temp_dir(), "test").unwrap();
+        let missing = scratch.write_file("gone.pdf", b"").unwrap();
+        fs::remove_file(&missing).unwrap();
+        let outcome = scan_pdf_file(&missing.to_string_lossy(), 0, &ScanContext::default());
+        assert!(matches!(outcome, Err(ScanError::NotFound)), "{:?}", outcome.map(|r| r.reason));
+    }
+
+    #[test]
+    fn file_over_the_limit_is_refused() {
+        let scratch = ScratchDir::new_in(&std::env::temp_dir(), "test").unwrap();
+        let path = scratch.write_file("big.pdf", &pdf_with_catalog("")).unwrap();
+        let err = scan_pdf_file(&path.to_string_lossy(), 16, &ScanContext::default()).unwrap_err();
+        assert!(matches!(err, ScanError::TooLarge { limit: 16, .. }));
+    }
+
+    #[test]
+    fn finds_tag_straddling_the_read_buffer_in_a_file() {
+        // Whitespace in the catalog pushes /OpenAction to a few bytes
+        // before the end of the first read.
+        let at = find_from(&pdf_with_catalog("/OpenAction 3 0 R"), b"/OpenAction", 0).unwrap();
+        let padding = " ".repeat(SCAN_BUFFER_BYTES - 4 - at);
+        let content = pdf_with_catalog(&format!("{}/OpenAction 3 0 R", padding));
+        assert_eq!(&content[SCAN_BUFFER_BYTES - 4..SCAN_BUFFER_BYTES + 7], b"/OpenAction");
+
+        let result = scan_fixture("straddle.pdf", &content);
+        assert!(result.is_suspicious);
+        assert!(result.reasons.contains(&"Found an /OpenAction tag.".to_string()), "{:?}", result.reasons);
+    }
+
+    /// A FlateDecode stream object holding `data`.
+    fn flate_object(number: usize, data: &[u8]) -> Vec<u8> {
+        use flate2::write::ZlibEncoder;
+        use std::io::Write;
+
+        let mut encoder = ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(data).unwrap();
+        let compressed = encoder.finish().unwrap();
+        let mut object = format!("{} 0 obj\n<< /Length {} /Filter /FlateDecode >>\nstream\n", number, compressed.len()).into_bytes();
+        object.extend(compressed);
+        object.extend_from_slice(b"\nendstream\nendobj\n");
+        object
+    }
+
+    #[test]
+    fn finds_tag_inside_a_compressed_stream() {
+        let mut content = b"%PDF-1.5\n".to_vec();
+        content.extend(flate_object(1, b"<< /Type /Catalog /OpenAction 2 0 R >>"));
+        let scan = scan_streams(&content, MAX_INFLATED_TOTAL_BYTES);
+        assert!(scan.tags.contains("/OpenAction"));
+        assert_eq!((scan.undecodable, scan.over_budget), (0, 0));
+    }
+
+    #[test]
+    fn many_streams_share_one_inflation_budget() {
+        // Each stream is a few hundred bytes on disk but 64 KiB inflated.
+        let padding = vec![b' '; 64 * 1024];
+        let mut content = b"%PDF-1.5\n".to_vec();
+        for number in 1..=300 {
+            content.extend(flate_object(number, &padding));
+        }
+        content.extend(flate_object(301, b"/JavaScript"));
+
+        let budget = 1024 * 1024;
+        let scan = scan_streams(&content, budget);
+        assert_eq!(scan.inflated, budget);
+        assert_eq!(scan.over_budget, 301 - 16);
+        assert!(!scan.tags.contains("/JavaScript"));
+        assert_eq!(scan.undecodable, 0);
+    }
+
+    #[test]
+    fn hash_matches_whole_file_digest() {
+        use sha2::{Digest, Sha256};
+
+        let data: Vec<u8> = (0..BUFFER * 3 + 7).map(|i| i as u8).collect();
+        let raw = scan_raw(data.as_slice(), BUFFER).unwrap();
+        assert_eq!(raw.sha256, format!("{:x}", Sha256::digest(&data)));
+    }
+}
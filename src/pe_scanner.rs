@@ -0,0 +1,210 @@
+// src/pe_scanner.rs
+
+use goblin::pe::section_table::{IMAGE_SCN_CNT_CODE, IMAGE_SCN_MEM_EXECUTE, IMAGE_SCN_MEM_WRITE};
+use goblin::pe::PE;
+use serde::Serialize;
+use crate::feedback::FeedbackStore;
+use crate::filename::{check_file_name, NameSource};
+use crate::pdf_scanner::{ScanContext, ScanError, MIB};
+use crate::scanner::read_file_within;
+
+/// Default weights for each rule, before feedback adjustments.
+const RULE_WEIGHTS: &[(&str, u32)] = &[
+    ("pe:unsigned", 10),
+    ("pe:injection-imports", 60),
+    ("pe:packed-section", 30),
+    ("pe:writable-code", 40),
+    ("pe:malformed", 50),
+    ("pe:ioc-hash", 100),
+    ("pe:file-name", 40),
+];
+
+/// A file is reported as suspicious once its findings add up to this much,
+/// so a lone missing signature (common for small projects) isn't enough.
+const SUSPICIOUS_SCORE: u32 = 40;
+
+/// Imports that together let a process write code into another one.
+const INJECTION_IMPORTS: &[&str] = &[
+    "VirtualAllocEx",
+    "WriteProcessMemory",
+    "CreateRemoteThread",
+    "NtWriteVirtualMemory",
+    "QueueUserAPC",
+];
+
+/// Compressed or encrypted data sits close to 8 bits of entropy per byte;
+/// ordinary code and data rarely go above 7.
+const PACKED_ENTROPY: f64 = 7.2;
+
+/// Sections smaller than this are too short for entropy to mean much.
+const MIN_ENTROPY_BYTES: usize = 1024;
+
+/// The result of scanning one Windows executable or DLL.
+#[derive(Debug, Clone, Serialize)]
+pub struct PeScanResult {
+    pub file_path: String,
+    pub is_suspicious: bool,
+    pub reason: String,
+    pub is_dll: bool,
+    pub is_64bit: bool,
+    /// Each individual detection, in the order it was found.
+    pub findings: Vec<PeFinding>,
+    /// Sum of the finding weights after the user's feedback adjustments.
+    pub risk_score: u32,
+}
+
+/// One detection within a PE scan.
+#[derive(Debug, Clone, Serialize)]
+pub struct PeFinding {
+    /// Stable id used to key false-positive feedback, e.g. `pe:writable-code`.
+    pub rule: String,
+    pub description: String,
+    pub weight: u32,
+}
+
+impl PeFinding {
+    fn new(rule: &str, description: String, feedback: &FeedbackStore) -> PeFinding {
+        let base_weight = RULE_WEIGHTS
+            .iter()
+            .find(|(id, _)| *id == rule)
+            .map_or(10, |(_, weight)| *weight);
+        PeFinding {
+            rule: rule.to_string(),
+            description,
+            weight: feedback.weight(rule, base_weight),
+        }
+    }
+}
+
+/// Whether the name says Windows executable, for callers that only have a path.
+pub fn has_pe_extension(path: &str) -> bool {
+    let lower = path.to_ascii_lowercase();
+    lower.ends_with(".exe") || lower.ends_with(".dll")
+}
+
+/// Checks for an `MZ` header whose `e_lfanew` points at a `PE\0\0` signature.
+pub fn is_pe(content: &[u8]) -> bool {
+    if content.len() < 0x40 || &content[..2] != b"MZ" {
+        return false;
+    }
+    let pe_offset = u32::from_le_bytes([content[0x3C], content[0x3D], content[0x3E], content[0x3F]]) as usize;
+    content.get(pe_offset..pe_offset + 4) == Some(b"PE\0\0")
+}
+
+/// Shannon entropy in bits per byte.
+fn entropy(data: &[u8]) -> f64 {
+    let mut counts = [0usize; 256];
+    for &byte in data {
+        counts[byte as usize] += 1;
+    }
+    let len = data.len() as f64;
+    counts
+        .iter()
+        .filter(|&&count| count > 0)
+        .map(|&count| {
+            let p = count as f64 / len;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+fn pe_findings(pe: &PE, content: &[u8], feedback: &FeedbackStore) -> Vec<PeFinding> {
+    let mut findings = Vec::new();
+
+    if pe.certificates.is_empty() {
+        findings.push(PeFinding::new("pe:unsigned", "No Authenticode signature.".to_string(), feedback));
+    }
+
+    let mut injection: Vec<&str> = INJECTION_IMPORTS
+        .iter()
+        .copied()
+        .filter(|name| pe.imports.iter().any(|import| import.name == *name))
+        .collect();
+    injection.dedup();
+    if !injection.is_empty() {
+        findings.push(PeFinding::new(
+            "pe:injection-imports",
+            format!("Imports process injection APIs: {}.", injection.join(", ")),
+            feedback,
+        ));
+    }
+
+    for section in &pe.sections {
+        let name = section.name().unwrap_or("?");
+        let start = section.pointer_to_raw_data as usize;
+        let end = start.saturating_add(section.size_of_raw_data as usize).min(content.len());
+        if let Some(data) = content.get(start..end).filter(|data| data.len() >= MIN_ENTROPY_BYTES) {
+            let bits = entropy(data);
+            if bits > PACKED_ENTROPY {
+                findings.push(PeFinding::new(
+                    "pe:packed-section",
+                    format!("Section {} looks packed or encrypted (entropy {:.2}).", name, bits),
+                    feedback,
+                ));
+            }
+        }
+
+        let is_code = section.characteristics & (IMAGE_SCN_CNT_CODE | IMAGE_SCN_MEM_EXECUTE) != 0;
+        if is_code && section.characteristics & IMAGE_SCN_MEM_WRITE != 0 {
+            findings.push(PeFinding::new(
+                "pe:writable-code",
+                format!("Section {} is both executable and writable.", name),
+                feedback,
+            ));
+        }
+    }
+
+    findings
+}
+
+/// Scans an in-memory Windows executable or DLL. Nothing is executed and no
+/// external tools are needed.
+pub fn scan_pe_bytes(name: &str, source: NameSource, content: &[u8], context: &ScanContext) -> PeScanResult {
+    let feedback = &context.feedback;
+    let mut is_dll = false;
+    let mut is_64bit = false;
+
+    let mut findings = match PE::parse(content) {
+        Ok(pe) => {
+            is_dll = pe.is_lib;
+            is_64bit = pe.is_64;
+            pe_findings(&pe, content, feedback)
+        }
+        Err(e) => vec![PeFinding::new("pe:malformed", format!("Malformed PE headers: {}.", e), feedback)],
+    };
+    if let Some(description) = context.iocs.find_hash_match(content) {
+        findings.push(PeFinding::new("pe:ioc-hash", description, feedback));
+    }
+    for issue in check_file_name(name, source) {
+        findings.push(PeFinding::new("pe:file-name", issue, feedback));
+    }
+
+    build_scan_result(name, is_dll, is_64bit, findings)
+}
+
+/// Reads and scans a Windows executable or DLL from disk, refusing one
+/// bigger than `max_scan_mb`. A file that can't be read is an error, not a
+/// clean result.
+pub fn scan_pe_file(file_path: &str, context: &ScanContext) -> Result<PeScanResult, ScanError> {
+    let content = read_file_within(file_path, u64::from(context.settings.max_scan_mb) * MIB)?;
+    Ok(scan_pe_bytes(file_path, NameSource::Local, &content, context))
+}
+
+fn build_scan_result(file_path: &str, is_dll: bool, is_64bit: bool, findings: Vec<PeFinding>) -> PeScanResult {
+    let risk_score = findings.iter().map(|f| f.weight).sum();
+    let reason = if findings.is_empty() {
+        "Clean.".to_string()
+    } else {
+        findings.iter().map(|f| f.description.as_str()).collect::<Vec<_>>().join(" ")
+    };
+
+    PeScanResult {
+        file_path: file_path.to_string(),
+        is_suspicious: risk_score >= SUSPICIOUS_SCORE,
+        reason,
+        is_dll,
+        is_64bit,
+        findings,
+        risk_score,
+    }
+}
@@ -0,0 +1,175 @@
+// src/quarantine.rs
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::config::data_path;
+
+const QUARANTINE_DIR: &str = "quarantine";
+
+/// Lists every quarantined file, wherever it was moved to, so it can be
+/// restored or deleted later. Kept in the default quarantine folder.
+const INDEX_FILE: &str = "index.json";
+
+/// The default quarantine folder, in the per-user data directory.
+pub fn quarantine_dir() -> PathBuf {
+    data_path(QUARANTINE_DIR)
+}
+
+fn index_path() -> PathBuf {
+    quarantine_dir().join(INDEX_FILE)
+}
+
+/// Written next to each quarantined file so it can be traced back (and later restored).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuarantineRecord {
+    pub original_path: String,
+    pub quarantined_at: u64,
+    pub reason: String,
+}
+
+/// Moves `path` into the quarantine folder, strips its permissions so it
+/// can't be opened or run by accident, and records where it came from.
+/// Returns the new location.
+pub fn quarantine_file(path: &Path, reason: &str) -> io::Result<PathBuf> {
+    let quarantined_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let name = path
+        .file_name()
+        .map_or_else(|| "unknown".to_string(), |n| n.to_string_lossy().replace(' ', "_"));
+
+    let dir = &quarantine_dir();
+    fs::create_dir_all(dir)?;
+    let target = dir.join(format!("{}_{}.quarantined", quarantined_at, name));
+
+    // A rename is instant; fall back to copying when the quarantine folder
+    // is on another filesystem.
+    if fs::rename(path, &target).is_err() {
+        fs::copy(path, &target)?;
+        fs::remove_file(path)?;
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let _ = fs::set_permissions(&target, fs::Permissions::from_mode(0o400));
+    }
+
+    let record = QuarantineRecord {
+        original_path: path.to_string_lossy().to_string(),
+        quarantined_at,
+        reason: reason.to_string(),
+    };
+    let json = serde_json::to_string_pretty(&record).map_err(io::Error::other)?;
+    fs::write(target.with_extension("json"), json)?;
+
+    add_to_index(vec![QuarantineEntry {
+        original_path: record.original_path,
+        quarantined_path: fs::canonicalize(&target).unwrap_or_else(|_| target.clone()).to_string_lossy().to_string(),
+        quarantined_at,
+        reason: record.reason,
+    }])?;
+    Ok(target)
+}
+
+/// One file in the quarantine index.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuarantineEntry {
+    pub original_path: String,
+    pub quarantined_path: String,
+    pub quarantined_at: u64,
+    pub reason: String,
+}
+
+/// Creates `dir` (the default quarantine folder when empty) for
+/// `clamscan --move` and returns its absolute path.
+pub fn prepare_dir(dir: &str) -> io::Result<PathBuf> {
+    let dir = if dir.trim().is_empty() { quarantine_dir() } else { PathBuf::from(dir.trim()) };
+    fs::create_dir_all(&dir)?;
+    fs::canonicalize(dir)
+}
+
+/// The quarantine index, oldest first. A missing or unreadable index is empty.
+pub fn load_index() -> Vec<QuarantineEntry> {
+    fs::read_to_string(index_path())
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+fn save_index(entries: &[QuarantineEntry]) -> io::Result<()> {
+    fs::create_dir_all(quarantine_dir())?;
+    let json = serde_json::to_string_pretty(entries).map_err(io::Error::other)?;
+    fs::write(index_path(), json)
+}
+
+/// Records files that were just quarantined, e.g. by `clamscan --move`.
+pub fn add_to_index(new_entries: Vec<QuarantineEntry>) -> io::Result<()> {
+    if new_entries.is_empty() {
+        return Ok(());
+    }
+    let mut entries = load_index();
+    entries.extend(new_entries);
+    save_index(&entries)
+}
+
+fn remove_from_index(entry: &QuarantineEntry) -> io::Result<()> {
+    let mut entries = load_index();
+    entries.retain(|e| e != entry);
+    save_index(&entries)
+}
+
+/// Removes the `.json` record `quarantine_file` writes next to a file.
+fn remove_record(quarantined: &Path) {
+    if quarantined.extension().is_some_and(|ext| ext == "quarantined") {
+        let _ = fs::remove_file(quarantined.with_extension("json"));
+    }
+}
+
+/// Moves a quarantined file back to where it came from. It comes back
+/// readable and writable by its owner only, and never executable. Fails
+/// rather than overwrite a file that has since appeared at that path.
+pub fn restore(entry: &QuarantineEntry) -> io::Result<()> {
+    let quarantined = Path::new(&entry.quarantined_path);
+    let original = Path::new(&entry.original_path);
+    if original.exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} already exists", original.display()),
+        ));
+    }
+    if let Some(parent) = original.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    if fs::rename(quarantined, original).is_err() {
+        fs::copy(quarantined, original)?;
+        fs::remove_file(quarantined)?;
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let _ = fs::set_permissions(original, fs::Permissions::from_mode(0o600));
+    }
+
+    remove_record(quarantined);
+    remove_from_index(entry)
+}
+
+/// Deletes a quarantined file for good. An entry whose file is already
+/// gone is just dropped from the index.
+pub fn delete(entry: &QuarantineEntry) -> io::Result<()> {
+    let quarantined = Path::new(&entry.quarantined_path);
+    match fs::remove_file(quarantined) {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e),
+    }
+    remove_record(quarantined);
+    remove_from_index(entry)
+}
@@ -0,0 +1,129 @@
+// src/report.rs
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Envelope for every machine-readable report, so consumers can tell which
+/// command and build produced it.
+#[derive(Serialize)]
+pub struct Report<'a, T: Serialize> {
+    pub tool_version: &'static str,
+    pub command: &'a str,
+    pub generated_at: u64,
+    pub result: &'a T,
+}
+
+impl<'a, T: Serialize> Report<'a, T> {
+    pub fn new(command: &'a str, result: &'a T) -> Self {
+        Self {
+            tool_version: env!("CARGO_PKG_VERSION"),
+            command,
+            generated_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            result,
+        }
+    }
+}
+
+/// Writes `report` as JSON to `path` atomically: the data goes to a temporary
+/// file in the same directory which is then renamed over the target, so a
+/// reader never sees a half-written report.
+pub fn write_report_atomic<T: Serialize>(path: &Path, report: &T) -> Result<()> {
+    let json = serde_json::to_vec_pretty(report).context("Failed to serialize report")?;
+
+    let file_name = path
+        .file_name()
+        .with_context(|| format!("{} is not a file path", path.display()))?;
+    let tmp_path = path.with_file_name(format!(
+        ".{}.tmp-{}",
+        file_name.to_string_lossy(),
+        std::process::id()
+    ));
+
+    let write_tmp = || -> std::io::Result<()> {
+        let mut file = fs::File::create(&tmp_path)?;
+        file.write_all(&json)?;
+        file.sync_all()
+    };
+    if let Err(e) = write_tmp().and_then(|_| fs::rename(&tmp_path, path)) {
+        let _ = fs::remove_file(&tmp_path);
+        return Err(e).with_context(|| format!("Failed to write report to {}", path.display()));
+    }
+    Ok(())
+}
+
+/// Quotes a CSV field when it contains a separator, quote or line break,
+/// doubling any quotes inside (RFC 4180).
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Writes a header and rows to `path` as CSV.
+pub fn write_csv(path: &Path, header: &[&str], rows: impl IntoIterator<Item = Vec<String>>) -> Result<()> {
+    let mut csv = header.iter().map(|name| csv_field(name)).collect::<Vec<_>>().join(",");
+    csv.push_str("\r\n");
+    for row in rows {
+        csv.push_str(&row.iter().map(|field| csv_field(field)).collect::<Vec<_>>().join(","));
+        csv.push_str("\r\n");
+    }
+    fs::write(path, csv).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Formats seconds since the epoch as `YYYY-MM-DD HH:MM:SS` in UTC, which
+/// spreadsheets parse as a date.
+pub fn format_utc_timestamp(secs: u64) -> String {
+    let days = (secs / 86_400) as i64;
+    let time = secs % 86_400;
+    // Days to a civil date, after Howard Hinnant's `civil_from_days`.
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+        year,
+        month,
+        day,
+        time / 3600,
+        time % 3600 / 60,
+        time % 60
+    )
+}
+
+/// Formats seconds since the epoch as `YYYY-MM-DD HH:MM:SS` in local time,
+/// for display. Falls back to UTC (and says so) where the local time zone
+/// can't be looked up.
+pub fn format_timestamp(secs: u64) -> String {
+    #[cfg(unix)]
+    {
+        let time = secs as libc::time_t;
+        let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+        if !unsafe { libc::localtime_r(&time, &mut tm) }.is_null() {
+            return format!(
+                "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+                tm.tm_year + 1900,
+                tm.tm_mon + 1,
+                tm.tm_mday,
+                tm.tm_hour,
+                tm.tm_min,
+                tm.tm_sec
+            );
+        }
+    }
+    format!("{} UTC", format_utc_timestamp(secs))
+}
@@ -0,0 +1,130 @@
+// src/retention.rs
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::audit::{self, audit_log_path, AUDIT_LOG_FILE};
+use crate::config::user_data_dir;
+use crate::config::RetentionPolicy;
+use crate::pdf_scanner::{seal_scan_archive, sealed_scan_archives};
+
+const MB: u64 = 1024 * 1024;
+
+/// Rotated audit logs are written as `audit.log.<timestamp>.gz`.
+fn rotated_prefix() -> String {
+    format!("{}.", AUDIT_LOG_FILE)
+}
+
+/// Rotated audit logs, oldest first. Uncompressed ones are left over from
+/// a rotation that was interrupted before compression finished.
+fn rotated_audit_logs() -> (Vec<PathBuf>, Vec<PathBuf>) {
+    let prefix = rotated_prefix();
+    let mut compressed = Vec::new();
+    let mut pending = Vec::new();
+    for entry in fs::read_dir(user_data_dir()).into_iter().flatten().flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        let Some(stamp) = name.strip_prefix(&prefix) else {
+            continue;
+        };
+        match stamp.strip_suffix(".gz") {
+            Some(stamp) if stamp.chars().all(|c| c.is_ascii_digit()) => compressed.push(entry.path()),
+            None if stamp.chars().all(|c| c.is_ascii_digit()) => pending.push(entry.path()),
+            _ => {}
+        }
+    }
+    compressed.sort();
+    pending.sort();
+    (compressed, pending)
+}
+
+/// Compresses a set-aside log next to itself and removes the original only
+/// once the compressed copy is on disk.
+fn compress(path: &Path) -> io::Result<PathBuf> {
+    let target = PathBuf::from(format!("{}.gz", path.display()));
+    let mut encoder = GzEncoder::new(fs::File::create(&target)?, Compression::default());
+    io::copy(&mut fs::File::open(path)?, &mut encoder)?;
+    encoder.finish()?.sync_all()?;
+    fs::remove_file(path)?;
+    Ok(target)
+}
+
+/// Sets the audit log aside once it reaches `limit` bytes. The rename is
+/// atomic, so the next event simply starts a fresh log.
+fn rotate_audit_log(limit: u64) -> io::Result<Option<PathBuf>> {
+    let active = audit_log_path();
+    if fs::metadata(&active).map_or(true, |meta| meta.len() < limit) {
+        return Ok(None);
+    }
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    let set_aside = user_data_dir().join(format!("{}{}", rotated_prefix(), timestamp));
+    fs::rename(&active, &set_aside)?;
+    compress(&set_aside).map(Some)
+}
+
+/// Deletes files (oldest first) that are past the age limit, then as many
+/// more as needed to bring the total under the size limit.
+fn prune(files: &[PathBuf], policy: &RetentionPolicy) -> Vec<PathBuf> {
+    let max_age = Duration::from_secs(u64::from(policy.max_age_days) * 24 * 60 * 60);
+    let mut total: u64 = files.iter().filter_map(|path| fs::metadata(path).ok()).map(|meta| meta.len()).sum();
+    let mut removed = Vec::new();
+
+    for path in files {
+        let Ok(meta) = fs::metadata(path) else {
+            continue;
+        };
+        let too_old = policy.max_age_days > 0
+            && meta.modified().ok().and_then(|time| time.elapsed().ok()).is_some_and(|age| age > max_age);
+        let too_big = policy.max_total_mb > 0 && total > u64::from(policy.max_total_mb) * MB;
+        if (too_old || too_big) && fs::remove_file(path).is_ok() {
+            total -= meta.len();
+            removed.push(path.clone());
+        }
+    }
+    removed
+}
+
+/// Applies the retention policy to the audit log and the saved PDF scan
+/// history. Every rotation and deletion is written to the (fresh) audit log
+/// and returned as a line of text for display.
+pub fn enforce(policy: &RetentionPolicy) -> Vec<String> {
+    let mut actions = Vec::new();
+
+    let (_, pending) = rotated_audit_logs();
+    for path in pending {
+        match compress(&path) {
+            Ok(target) => actions.push(("rotated", target.display().to_string(), "Compressed an interrupted rotation".to_string())),
+            Err(e) => actions.push(("rotate-failed", path.display().to_string(), e.to_string())),
+        }
+    }
+
+    if policy.rotate_at_mb > 0 {
+        let limit = u64::from(policy.rotate_at_mb) * MB;
+        match rotate_audit_log(limit) {
+            Ok(Some(target)) => actions.push(("rotated", target.display().to_string(), format!("{} reached {} MB", AUDIT_LOG_FILE, policy.rotate_at_mb))),
+            Ok(None) => {}
+            Err(e) => actions.push(("rotate-failed", AUDIT_LOG_FILE.to_string(), e.to_string())),
+        }
+        match seal_scan_archive(limit) {
+            Ok(Some(target)) => actions.push(("rotated", target.display().to_string(), format!("Scan archive reached {} MB", policy.rotate_at_mb))),
+            Ok(None) => {}
+            Err(e) => actions.push(("rotate-failed", "scan archive".to_string(), e.to_string())),
+        }
+    }
+
+    let (rotated_logs, _) = rotated_audit_logs();
+    for path in prune(&rotated_logs, policy).into_iter().chain(prune(&sealed_scan_archives(), policy)) {
+        actions.push(("pruned", path.display().to_string(), "Past the retention limit".to_string()));
+    }
+
+    actions
+        .into_iter()
+        .map(|(action, path, detail)| {
+            let _ = audit::record("retention", action, &path, &detail);
+            format!("{} {}: {}", action, path, detail)
+        })
+        .collect()
+}
@@ -0,0 +1,254 @@
+// src/scanner.rs
+
+use serde::Serialize;
+use std::fmt;
+use std::fs;
+use std::io::Read;
+use std::path::PathBuf;
+
+use crate::analyzer::{analyze_content_with, PatternSet};
+use crate::filename::{check_file_name, NameSource};
+use crate::pdf_scanner::{scan_pdf_bytes, scan_pdf_with, ScanContext, ScanError, MIB};
+use crate::pe_scanner::{is_pe, scan_pe_bytes};
+
+/// PDF readers accept the header anywhere in the first kilobyte.
+const PDF_HEADER_WINDOW: usize = 1024;
+
+/// How much of the content is sniffed to tell text from binary.
+const TEXT_SNIFF_BYTES: usize = 8192;
+
+/// Which scanner the dispatcher picked for a piece of content.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum ContentKind {
+    Pdf,
+    /// A Windows executable or DLL.
+    Executable,
+    Script,
+    Binary,
+}
+
+impl fmt::Display for ContentKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ContentKind::Pdf => "PDF",
+            ContentKind::Executable => "executable",
+            ContentKind::Script => "script",
+            ContentKind::Binary => "binary",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// The verdict for one file or buffer, whichever scanner produced it.
+#[derive(Debug, Clone, Serialize)]
+pub struct ScanOutcome {
+    pub name: String,
+    pub kind: ContentKind,
+    pub is_suspicious: bool,
+    pub findings: Vec<String>,
+}
+
+/// Guesses the content type from its bytes rather than trusting the name.
+pub fn detect_kind(content: &[u8]) -> ContentKind {
+    let head = &content[..content.len().min(PDF_HEADER_WINDOW)];
+    if head.windows(5).any(|w| w == b"%PDF-") {
+        return ContentKind::Pdf;
+    }
+    if is_pe(content) {
+        return ContentKind::Executable;
+    }
+
+    let sniff = &content[..content.len().min(TEXT_SNIFF_BYTES)];
+    let is_text = !sniff.contains(&0)
+        && match std::str::from_utf8(sniff) {
+            Ok(_) => true,
+            // The sniff window may cut a multi-byte character in half.
+            Err(e) => e.error_len().is_none(),
+        };
+    if is_text {
+        ContentKind::Script
+    } else {
+        ContentKind::Binary
+    }
+}
+
+/// Scans an in-memory buffer with the scanner that fits its content: PDFs
+/// get the PDF checks, Windows executables the PE checks, text gets the
+/// script analyzer, and everything else is checked against the IOC hash
+/// lists. The name itself is always checked, as `source` says to.
+pub fn scan_bytes(name: &str, source: NameSource, content: &[u8], context: &ScanContext) -> ScanOutcome {
+    let kind = detect_kind(content);
+    if kind == ContentKind::Executable {
+        // PE results carry their own score, name and hash checks.
+        let result = scan_pe_bytes(name, source, content, context);
+        let mut findings: Vec<String> = result.findings.into_iter().map(|finding| finding.description).collect();
+        let yara_matched = extend_with_yara_matches(&mut findings, name, content);
+        return ScanOutcome {
+            name: name.to_string(),
+            kind,
+            is_suspicious: result.is_suspicious || yara_matched,
+            findings,
+        };
+    }
+
+    let mut findings = match kind {
+        ContentKind::Pdf => scan_pdf_bytes(name, source, content, context)
+            .findings
+            .into_iter()
+            .map(|finding| finding.description)
+            .collect(),
+        ContentKind::Script => {
+            let mut findings: Vec<String> = context.iocs.find_hash_match(content).into_iter().collect();
+            match analyze_content_with(&String::from_utf8_lossy(content), &PatternSet::builtin(), &context.iocs) {
+                Ok(result) => {
+                    findings.extend(result.threats.iter().map(|threat| threat.to_string()));
+                    findings.extend(result.secrets.iter().map(|secret| format!("Possible secret on {}", secret)));
+                }
+                Err(e) => findings.push(format!("Analysis failed: {}", e)),
+            }
+            findings
+        }
+        ContentKind::Executable | ContentKind::Binary => context.iocs.find_hash_match(content).into_iter().collect(),
+    };
+    // PDF results already include the name and YARA checks.
+    if kind != ContentKind::Pdf {
+        findings.extend(check_file_name(name, source));
+        extend_with_yara_matches(&mut findings, name, content);
+    }
+
+    ScanOutcome {
+        name: name.to_string(),
+        kind,
+        is_suspicious: !findings.is_empty(),
+        findings,
+    }
+}
+
+/// Adds a finding per configured YARA rule matching `content`, and returns
+/// whether any did. Does nothing in builds without the `yara` feature.
+#[cfg(feature = "yara")]
+fn extend_with_yara_matches(findings: &mut Vec<String>, name: &str, content: &[u8]) -> bool {
+    match crate::yara::scan_bytes(name, content) {
+        Ok(rules) => {
+            let rules = rules.unwrap_or_default();
+            findings.extend(rules.iter().map(|rule| format!("YARA rule {} matched", rule)));
+            !rules.is_empty()
+        }
+        Err(e) => {
+            log::warn!("YARA rules could not run: {}", e);
+            false
+        }
+    }
+}
+
+#[cfg(not(feature = "yara"))]
+fn extend_with_yara_matches(_findings: &mut Vec<String>, _name: &str, _content: &[u8]) -> bool {
+    false
+}
+
+/// Reads all of a file, refusing one bigger than `limit` bytes (0 for no
+/// limit) rather than loading it into memory.
+pub fn read_file_within(path: &str, limit: u64) -> Result<Vec<u8>, ScanError> {
+    let size = fs::metadata(path)?.len();
+    if limit > 0 && size > limit {
+        return Err(ScanError::TooLarge { size, limit });
+    }
+    let mut file = fs::File::open(path)?;
+    let mut content = Vec::new();
+    if limit == 0 {
+        file.read_to_end(&mut content)?;
+    } else {
+        // The file may have grown since it was measured.
+        file.take(limit + 1).read_to_end(&mut content)?;
+        if content.len() as u64 > limit {
+            return Err(ScanError::TooLarge { size: content.len() as u64, limit });
+        }
+    }
+    Ok(content)
+}
+
+/// Scans a file on disk with the scanner that fits its content. PDFs go
+/// through the streaming PDF scan and its `max_pdf_scan_mb` limit; other
+/// files are read whole, so they're refused over `max_scan_mb`.
+pub fn scan_file(path: &str, context: &ScanContext) -> Result<ScanOutcome, ScanError> {
+    let mut head = Vec::new();
+    fs::File::open(path)?.take(PDF_HEADER_WINDOW as u64).read_to_end(&mut head)?;
+    if detect_kind(&head) == ContentKind::Pdf {
+        let result = scan_pdf_with(path, context)?;
+        return Ok(ScanOutcome {
+            name: path.to_string(),
+            kind: ContentKind::Pdf,
+            is_suspicious: result.is_suspicious,
+            findings: result.reasons,
+        });
+    }
+
+    let content = read_file_within(path, u64::from(context.settings.max_scan_mb) * MIB)?;
+    Ok(scan_bytes(path, NameSource::Local, &content, context))
+}
+
+/// A path given to `scan_paths` that couldn't be scanned.
+#[derive(Debug, Clone, Serialize)]
+pub struct UnscannedPath {
+    pub path: String,
+    pub error: String,
+}
+
+/// Verdicts for every file matched by a `scan` command line.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ScanPathsReport {
+    pub files: Vec<ScanOutcome>,
+    pub unscanned: Vec<UnscannedPath>,
+}
+
+impl ScanPathsReport {
+    pub fn is_suspicious(&self) -> bool {
+        self.files.iter().any(|outcome| outcome.is_suspicious)
+    }
+}
+
+/// Expands glob patterns (`*`, `?`, `[..]`, and `**` for any depth) so they
+/// work even when quoted or on shells that don't expand them; other
+/// arguments are taken literally.
+fn expand_path(pattern: &str) -> Result<Vec<PathBuf>, String> {
+    if !pattern.contains(['*', '?', '[']) {
+        return Ok(vec![PathBuf::from(pattern)]);
+    }
+    let matches: Vec<PathBuf> = glob::glob(pattern)
+        .map_err(|e| format!("invalid pattern: {}", e))?
+        .flatten()
+        .filter(|path| path.is_file())
+        .collect();
+    if matches.is_empty() {
+        return Err("no files match this pattern".to_string());
+    }
+    Ok(matches)
+}
+
+/// Scans every file named or matched by `patterns`, in order.
+pub fn scan_paths(patterns: &[String]) -> ScanPathsReport {
+    let context = &ScanContext::load();
+    let mut report = ScanPathsReport::default();
+    for pattern in patterns {
+        let paths = match expand_path(pattern) {
+            Ok(paths) => paths,
+            Err(error) => {
+                report.unscanned.push(UnscannedPath { path: pattern.clone(), error });
+                continue;
+            }
+        };
+        for path in paths {
+            let path = path.to_string_lossy().into_owned();
+            if fs::metadata(&path).is_ok_and(|meta| meta.is_dir()) {
+                let error = format!("is a directory (use a pattern such as '{}/**/*')", path.trim_end_matches('/'));
+                report.unscanned.push(UnscannedPath { path, error });
+                continue;
+            }
+            match scan_file(&path, context) {
+                Ok(outcome) => report.files.push(outcome),
+                Err(e) => report.unscanned.push(UnscannedPath { path, error: e.to_string() }),
+            }
+        }
+    }
+    report
+}
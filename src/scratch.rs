@@ -0,0 +1,79 @@
+// src/scratch.rs
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::config::Settings;
+
+/// Distinguishes directories created by the same process in the same instant.
+static COUNTER: AtomicU32 = AtomicU32::new(0);
+
+/// A private scratch directory for files extracted or decoded during a scan.
+/// It is readable only by the current user and is deleted, with everything
+/// in it, when this value is dropped, including while unwinding a panic.
+pub struct ScratchDir {
+    path: PathBuf,
+}
+
+impl ScratchDir {
+    /// Creates a scratch directory under the configured temp directory (or
+    /// the system one). `purpose` is only used to make the name readable.
+    pub fn new(purpose: &str) -> io::Result<ScratchDir> {
+        let configured = Settings::load().temp_dir;
+        let base = if configured.trim().is_empty() {
+            std::env::temp_dir()
+        } else {
+            PathBuf::from(configured.trim())
+        };
+        ScratchDir::new_in(&base, purpose)
+    }
+
+    /// Creates a scratch directory under `base`, which must already exist.
+    pub fn new_in(base: &Path, purpose: &str) -> io::Result<ScratchDir> {
+        loop {
+            let nanos = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos();
+            let path = base.join(format!(
+                "bigman-{}-{}-{}-{}",
+                purpose,
+                std::process::id(),
+                nanos,
+                COUNTER.fetch_add(1, Ordering::Relaxed)
+            ));
+            // `create` rather than `create_all`: an existing directory (or a
+            // symlink planted at the same name) must not be reused.
+            match private_dir_builder().create(&path) {
+                Ok(()) => return Ok(ScratchDir { path }),
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Writes `content` to a new file in the scratch directory. Only the
+    /// final component of `name` is used, so entry names can't escape it.
+    pub fn write_file(&self, name: &str, content: &[u8]) -> io::Result<PathBuf> {
+        let file_name = name.rsplit(['/', '\\']).find(|part| !matches!(*part, "" | "." | "..")).unwrap_or("file");
+        let path = self.path.join(file_name);
+        fs::write(&path, content)?;
+        Ok(path)
+    }
+}
+
+impl Drop for ScratchDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
+
+fn private_dir_builder() -> fs::DirBuilder {
+    let mut builder = fs::DirBuilder::new();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+        builder.mode(0o700);
+    }
+    builder
+}
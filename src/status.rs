@@ -0,0 +1,85 @@
+// src/status.rs
+
+use serde::Serialize;
+use std::path::Path;
+use std::time::SystemTime;
+
+use crate::config::{ClamAvSettings, Settings};
+use crate::ipc::is_gui_running;
+use crate::pdf_scanner::{PDFID_PROGRAM, PDFINFO_PROGRAM};
+
+/// Where distributions and Homebrew put the ClamAV signature databases.
+const CLAMAV_DATABASE_DIRS: &[&str] = &[
+    "/var/lib/clamav",
+    "/var/clamav",
+    "/usr/local/share/clamav",
+    "/opt/homebrew/var/lib/clamav",
+    "/usr/local/var/lib/clamav",
+];
+
+/// Which external helper programs can be found.
+#[derive(Debug, Clone, Serialize)]
+pub struct ScannerAvailability {
+    pub clamscan: bool,
+    pub freshclam: bool,
+    pub pdfid: bool,
+    pub pdfinfo: bool,
+}
+
+/// Answer to a native messaging `status` request.
+#[derive(Debug, Serialize)]
+pub struct HealthStatus {
+    pub status: &'static str,
+    pub version: &'static str,
+    /// Whether the GUI is running and receiving downloads for live display.
+    pub gui_running: bool,
+    /// Seconds since the ClamAV daily signatures were last updated, if found.
+    pub database_age_seconds: Option<u64>,
+    pub scanners: ScannerAvailability,
+}
+
+/// Gathers the status without running any scanner, so it answers quickly.
+pub fn collect_status() -> HealthStatus {
+    let clamav = Settings::load().clamav;
+    HealthStatus {
+        status: "OK",
+        version: env!("CARGO_PKG_VERSION"),
+        gui_running: is_gui_running(),
+        database_age_seconds: clamav_database_age(),
+        scanners: scanner_availability(&clamav),
+    }
+}
+
+/// Checks which optional helper programs are installed, without running them.
+pub fn scanner_availability(clamav: &ClamAvSettings) -> ScannerAvailability {
+    ScannerAvailability {
+        clamscan: program_exists(&clamav.clamscan_path),
+        freshclam: program_exists(&clamav.freshclam_path),
+        pdfid: program_exists(PDFID_PROGRAM),
+        pdfinfo: program_exists(PDFINFO_PROGRAM),
+    }
+}
+
+/// Looks a program up the same way `Command::new` would: as a path if it
+/// contains a separator, otherwise on `PATH`.
+fn program_exists(program: &str) -> bool {
+    if program.contains(['/', '\\']) {
+        return Path::new(program).is_file();
+    }
+    std::env::var_os("PATH").is_some_and(|paths| {
+        std::env::split_paths(&paths).any(|dir| {
+            dir.join(program).is_file() || (cfg!(windows) && dir.join(format!("{}.exe", program)).is_file())
+        })
+    })
+}
+
+/// Age of the newest `daily` database (`daily.cvd` or the incrementally
+/// updated `daily.cld`), which freshclam touches on every update.
+fn clamav_database_age() -> Option<u64> {
+    let newest = CLAMAV_DATABASE_DIRS
+        .iter()
+        .flat_map(|dir| ["daily.cvd", "daily.cld"].map(|name| Path::new(dir).join(name)))
+        .filter_map(|path| path.metadata().and_then(|meta| meta.modified()).ok())
+        .max()?;
+    Some(SystemTime::now().duration_since(newest).map_or(0, |age| age.as_secs()))
+}
@@ -0,0 +1,71 @@
+// src/theme.rs
+
+use eframe::egui::{self, Color32};
+use serde::{Deserialize, Serialize};
+
+/// Which color scheme the window uses.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ThemePreference {
+    /// Follow the desktop's light/dark setting (dark when it isn't known).
+    #[default]
+    System,
+    Light,
+    Dark,
+}
+
+impl ThemePreference {
+    pub fn label(self) -> &'static str {
+        match self {
+            ThemePreference::System => "System",
+            ThemePreference::Light => "Light",
+            ThemePreference::Dark => "Dark",
+        }
+    }
+
+    /// Switches the window to this theme if it isn't showing it already.
+    /// `system` is what the desktop reports, if anything.
+    pub fn apply(self, ctx: &egui::Context, system: Option<eframe::Theme>) {
+        let dark = match self {
+            ThemePreference::System => system != Some(eframe::Theme::Light),
+            ThemePreference::Light => false,
+            ThemePreference::Dark => true,
+        };
+        if ctx.style().visuals.dark_mode != dark {
+            ctx.set_visuals(if dark { egui::Visuals::dark() } else { egui::Visuals::light() });
+        }
+    }
+}
+
+/// Picks the dark- or light-background variant of a color.
+fn pick(ui: &egui::Ui, dark: Color32, light: Color32) -> Color32 {
+    if ui.visuals().dark_mode {
+        dark
+    } else {
+        light
+    }
+}
+
+/// Threats, failures and anything destructive.
+pub fn danger(ui: &egui::Ui) -> Color32 {
+    pick(ui, Color32::from_rgb(255, 90, 90), Color32::from_rgb(190, 20, 20))
+}
+
+/// Serious, but a step below `danger`.
+pub fn caution(ui: &egui::Ui) -> Color32 {
+    pick(ui, Color32::from_rgb(230, 140, 30), Color32::from_rgb(180, 80, 0))
+}
+
+/// Warnings and status messages that need a look.
+pub fn warning(ui: &egui::Ui) -> Color32 {
+    pick(ui, Color32::from_rgb(230, 200, 60), Color32::from_rgb(150, 105, 0))
+}
+
+/// Clean results and things that worked.
+pub fn safe(ui: &egui::Ui) -> Color32 {
+    pick(ui, Color32::from_rgb(90, 210, 110), Color32::from_rgb(0, 125, 45))
+}
+
+/// Secondary detail that shouldn't draw the eye.
+pub fn muted(ui: &egui::Ui) -> Color32 {
+    ui.visuals().weak_text_color()
+}
@@ -0,0 +1,159 @@
+// src/usb_watch.rs
+
+use std::sync::mpsc;
+
+/// A removable filesystem that was mounted while the watcher was running.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemovableMount {
+    pub device: String,
+    pub mount_point: String,
+}
+
+/// Starts a background thread that reports newly mounted removable media
+/// (USB sticks, SD cards) as udisks2 or the user mounts them. Media that was
+/// already mounted when the watcher started is not reported.
+///
+/// Only Linux is supported; elsewhere the receiver simply never yields.
+pub fn start_usb_watcher() -> mpsc::Receiver<RemovableMount> {
+    let (sender, receiver) = mpsc::channel();
+
+    #[cfg(target_os = "linux")]
+    std::thread::spawn(move || linux::watch_mounts(sender));
+
+    #[cfg(not(target_os = "linux"))]
+    drop(sender);
+
+    receiver
+}
+
+/// Every mounted filesystem as `(mount point, filesystem type)`. Empty on
+/// platforms without `/proc/self/mounts`.
+pub fn mounted_filesystems() -> Vec<(String, String)> {
+    #[cfg(target_os = "linux")]
+    {
+        std::fs::read_to_string("/proc/self/mounts")
+            .map(|content| {
+                content
+                    .lines()
+                    .filter_map(|line| {
+                        let mut fields = line.split_whitespace().skip(1);
+                        let mount_point = linux::unescape_mount_field(fields.next()?);
+                        Some((mount_point, fields.next()?.to_string()))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    Vec::new()
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use super::RemovableMount;
+    use std::fs::{self, File};
+    use std::io::{Read, Seek, SeekFrom};
+    use std::os::unix::io::AsRawFd;
+    use std::path::Path;
+    use std::sync::mpsc;
+
+    /// Blocks on `/proc/self/mounts`, which the kernel marks with POLLPRI
+    /// whenever the mount table changes, and diffs the removable mounts.
+    pub fn watch_mounts(sender: mpsc::Sender<RemovableMount>) {
+        let Ok(mut file) = File::open("/proc/self/mounts") else {
+            return;
+        };
+        let mut known = read_removable_mounts(&mut file);
+
+        loop {
+            let mut poll_fd = libc::pollfd {
+                fd: file.as_raw_fd(),
+                events: libc::POLLPRI,
+                revents: 0,
+            };
+            let ready = unsafe { libc::poll(&mut poll_fd, 1, -1) };
+            if ready < 0 {
+                if std::io::Error::last_os_error().kind() == std::io::ErrorKind::Interrupted {
+                    continue;
+                }
+                return;
+            }
+
+            // Re-reading through the same descriptor clears the change flag.
+            let current = read_removable_mounts(&mut file);
+            for mount in current.iter().filter(|m| !known.contains(m)) {
+                if sender.send(mount.clone()).is_err() {
+                    // The GUI stopped listening.
+                    return;
+                }
+            }
+            known = current;
+        }
+    }
+
+    fn read_removable_mounts(file: &mut File) -> Vec<RemovableMount> {
+        let mut content = String::new();
+        if file.seek(SeekFrom::Start(0)).is_err() || file.read_to_string(&mut content).is_err() {
+            return Vec::new();
+        }
+
+        content
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.split_whitespace();
+                let device = fields.next()?;
+                let mount_point = unescape_mount_field(fields.next()?);
+                (device.starts_with("/dev/") && is_removable(device)).then(|| RemovableMount {
+                    device: device.to_string(),
+                    mount_point,
+                })
+            })
+            .collect()
+    }
+
+    /// The mount table escapes spaces and friends as octal (`\040`).
+    pub(super) fn unescape_mount_field(field: &str) -> String {
+        let bytes = field.as_bytes();
+        let mut out = Vec::with_capacity(bytes.len());
+        let mut i = 0;
+        while i < bytes.len() {
+            let escaped = bytes.get(i + 1..i + 4).filter(|digits| {
+                bytes[i] == b'\\' && digits.iter().all(|b| (b'0'..=b'7').contains(b))
+            });
+            if let Some(digits) = escaped {
+                let value = digits.iter().fold(0u32, |acc, b| acc * 8 + u32::from(b - b'0'));
+                out.push(value as u8);
+                i += 4;
+            } else {
+                out.push(bytes[i]);
+                i += 1;
+            }
+        }
+        String::from_utf8_lossy(&out).into_owned()
+    }
+
+    /// A block device counts as removable if sysfs marks it (or its parent
+    /// disk) removable, or if it hangs off a USB bus. Many USB hard drives
+    /// report `removable = 0`, hence the second check.
+    fn is_removable(device: &str) -> bool {
+        let device = fs::canonicalize(device).unwrap_or_else(|_| Path::new(device).to_path_buf());
+        let Some(name) = device.file_name() else {
+            return false;
+        };
+        let Ok(sys_path) = fs::canonicalize(Path::new("/sys/class/block").join(name)) else {
+            return false;
+        };
+
+        if sys_path.to_string_lossy().contains("/usb") {
+            return true;
+        }
+
+        let removable_flag = |dir: &Path| {
+            fs::read_to_string(dir.join("removable"))
+                .map(|flag| flag.trim() == "1")
+                .unwrap_or(false)
+        };
+        removable_flag(&sys_path) || sys_path.parent().is_some_and(removable_flag)
+    }
+}
@@ -0,0 +1,133 @@
+// src/yara.rs
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::LazyLock;
+
+use anyhow::{bail, Result};
+use regex::Regex;
+
+use crate::analyzer::RuleValidation;
+use crate::config::Settings;
+use crate::scratch::ScratchDir;
+
+/// The YARA command-line scanner, looked up on PATH. It compiles the rules
+/// on every run, so edits to a ruleset take effect on the next scan.
+pub const YARA_PROGRAM: &str = "yara";
+
+/// One rule matching one file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct YaraMatch {
+    pub rule: String,
+    pub path: String,
+}
+
+/// The configured rules directory, if one is set.
+pub fn configured_rules_dir() -> Option<PathBuf> {
+    let dir = Settings::load().yara_rules_dir;
+    let dir = dir.trim();
+    (!dir.is_empty()).then(|| PathBuf::from(dir))
+}
+
+/// The `.yar` and `.yara` files directly inside `dir`, sorted.
+pub fn rule_files(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.is_file()
+                && path
+                    .extension()
+                    .is_some_and(|ext| ext.eq_ignore_ascii_case("yar") || ext.eq_ignore_ascii_case("yara"))
+        })
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+/// Runs every ruleset in `rules_dir` over `target`, a file or (recursively)
+/// a folder. Errors cover a missing `yara`, an empty rules directory and
+/// rules that don't compile.
+pub fn scan_path(rules_dir: &Path, target: &Path) -> Result<Vec<YaraMatch>, String> {
+    let rules = rule_files(rules_dir).map_err(|e| format!("could not read {}: {}", rules_dir.display(), e))?;
+    if rules.is_empty() {
+        return Err(format!("no .yar or .yara files in {}", rules_dir.display()));
+    }
+
+    let output = Command::new(YARA_PROGRAM)
+        .args(["--recursive", "--no-warnings"])
+        .args(&rules)
+        .arg(target)
+        .output()
+        .map_err(|e| match e.kind() {
+            io::ErrorKind::NotFound => format!("{} is not installed", YARA_PROGRAM),
+            _ => format!("could not run {}: {}", YARA_PROGRAM, e),
+        })?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("{} failed: {}", YARA_PROGRAM, stderr.trim()));
+    }
+
+    // Each match is printed as `<rule> <path>`; rule names have no spaces.
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let (rule, path) = line.split_once(' ')?;
+            Some(YaraMatch {
+                rule: rule.to_string(),
+                path: path.to_string(),
+            })
+        })
+        .collect())
+}
+
+/// A rule declaration, e.g. `private rule Name`.
+static RULE_DECLARATION: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?m)^\s*((private|global)\s+)*rule\s+\w+").expect("valid pattern"));
+
+/// Compiles a `.yar`/`.yara` file by running it over an empty scratch file.
+/// Each problem `yara` reports is an error or a warning; `--fail-on-warnings`
+/// makes it report both.
+pub fn validate_rules_file(path: &Path) -> Result<RuleValidation> {
+    let source = fs::read_to_string(path)?;
+    let scratch = ScratchDir::new("yara-check")?;
+    let target = scratch.write_file("empty", b"")?;
+
+    let output = match Command::new(YARA_PROGRAM).arg("--fail-on-warnings").arg(path).arg(&target).output() {
+        Ok(output) => output,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => bail!("{} is not installed or not on PATH", YARA_PROGRAM),
+        Err(e) => bail!("could not run {}: {}", YARA_PROGRAM, e),
+    };
+
+    let mut validation = RuleValidation::default();
+    for line in String::from_utf8_lossy(&output.stderr).lines().map(str::trim).filter(|line| !line.is_empty()) {
+        if line.contains("warning:") {
+            validation.warnings.push(line.to_string());
+        } else {
+            validation.errors.push(line.to_string());
+        }
+    }
+    if !output.status.success() && validation.errors.is_empty() && validation.warnings.is_empty() {
+        validation.errors.push(format!("{} failed with {}", YARA_PROGRAM, output.status));
+    }
+    if validation.errors.is_empty() {
+        validation.loaded = RULE_DECLARATION.find_iter(&source).count();
+    }
+    Ok(validation)
+}
+
+/// Runs the configured rules over in-memory content, via a scratch copy.
+/// `Ok(None)` means no rules directory is configured.
+pub fn scan_bytes(name: &str, content: &[u8]) -> Result<Option<Vec<String>>, String> {
+    let Some(rules_dir) = configured_rules_dir() else {
+        return Ok(None);
+    };
+    let scratch = ScratchDir::new("yara").map_err(|e| format!("could not write a scratch copy: {}", e))?;
+    let path = scratch
+        .write_file(name, content)
+        .map_err(|e| format!("could not write a scratch copy: {}", e))?;
+    let matches = scan_path(&rules_dir, &path)?;
+    Ok(Some(matches.into_iter().map(|m| m.rule).collect()))
+}
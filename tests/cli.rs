@@ -0,0 +1,62 @@
+// tests/cli.rs
+
+//! Runs the built binary's scan commands with their own home directory, so
+//! the user's settings and scan history aren't touched.
+
+use std::fs;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+/// A private directory tree for one run, removed when dropped.
+struct Workspace {
+    root: PathBuf,
+}
+
+impl Workspace {
+    fn new(name: &str) -> Workspace {
+        let root = std::env::temp_dir().join(format!("bigman-cli-test-{}-{}", std::process::id(), name));
+        for dir in ["home", "bin", "files/cwd"] {
+            fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        Workspace { root }
+    }
+
+    /// Writes `content` next to the directory the commands run from.
+    fn write_beside_cwd(&self, name: &str, content: &[u8]) {
+        fs::write(self.root.join("files").join(name), content).unwrap();
+    }
+
+    fn bigman(&self, args: &[&str]) -> Output {
+        Command::new(env!("CARGO_BIN_EXE_bigman"))
+            .args(args)
+            .current_dir(self.root.join("files").join("cwd"))
+            .env_clear()
+            .env("HOME", self.root.join("home"))
+            .env("PATH", self.root.join("bin"))
+            .output()
+            .unwrap()
+    }
+}
+
+impl Drop for Workspace {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.root);
+    }
+}
+
+const CLEAN_PDF: &[u8] = b"%PDF-1.4\n1 0 obj << /Type /Catalog /Pages 2 0 R >> endobj\n\
+2 0 obj << /Type /Pages /Kids [] /Count 0 >> endobj\ntrailer << /Root 1 0 R >>\n%%EOF\n";
+
+#[test]
+fn relative_paths_up_a_directory_are_not_traversal() {
+    let workspace = Workspace::new("parent-dir");
+    workspace.write_beside_cwd("clean.pdf", CLEAN_PDF);
+    workspace.write_beside_cwd("hello.sh", b"echo hello\n");
+
+    for args in [&["scan-pdf", "../clean.pdf"][..], &["scan", "../clean.pdf", "../hello.sh"]] {
+        let output = workspace.bigman(args);
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(output.status.success(), "{:?} exited with {}: {}", args, output.status, stdout);
+        assert!(!stdout.contains("traversal"), "{:?}: {}", args, stdout);
+    }
+}
@@ -0,0 +1,190 @@
+// tests/native_host.rs
+
+//! Runs the built binary the way a browser does, as a native messaging
+//! host, with its own home directory and started from an unrelated working
+//! directory.
+
+use serde_json::{json, Value};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicU32, Ordering};
+
+static COUNTER: AtomicU32 = AtomicU32::new(0);
+
+/// A private directory tree for one host, removed when dropped.
+struct Host {
+    root: PathBuf,
+}
+
+impl Host {
+    fn new() -> Host {
+        let root = std::env::temp_dir().join(format!(
+            "bigman-host-test-{}-{}",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        for dir in ["home", "cwd", "run", "bin", "tmp", "downloads"] {
+            fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        Host { root }
+    }
+
+    /// Where the GUI keeps its files for this host's user.
+    fn data_dir(&self) -> PathBuf {
+        self.root.join("home").join(".local").join("share").join("bigman")
+    }
+
+    /// The working directory the host is started from.
+    fn cwd(&self) -> PathBuf {
+        self.root.join("cwd")
+    }
+
+    fn downloads(&self) -> PathBuf {
+        self.root.join("downloads")
+    }
+
+    /// Saves settings where the GUI would.
+    fn write_settings(&self, settings: Value) {
+        fs::create_dir_all(self.data_dir()).unwrap();
+        fs::write(self.data_dir().join("settings.json"), settings.to_string()).unwrap();
+    }
+
+    /// Writes a PDF that opens with an action into the downloads folder.
+    fn download_suspicious_pdf(&self, name: &str) -> PathBuf {
+        let path = self.downloads().join(name);
+        fs::write(&path, SUSPICIOUS_PDF).unwrap();
+        path
+    }
+
+    /// Sends `messages` to a fresh host and returns everything it wrote to
+    /// stdout. The environment is cleared so no helper programs, running
+    /// GUI or real user data can change the outcome.
+    fn run(&self, messages: &[Value]) -> Vec<u8> {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_bigman"))
+            .arg("chrome-extension://test/")
+            .current_dir(self.cwd())
+            .env_clear()
+            .env("HOME", self.root.join("home"))
+            .env("PATH", self.root.join("bin"))
+            .env("XDG_RUNTIME_DIR", self.root.join("run"))
+            .env("TMPDIR", self.root.join("tmp"))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .unwrap();
+        let mut stdin = child.stdin.take().unwrap();
+        for message in messages {
+            stdin.write_all(&frame(message)).unwrap();
+        }
+        drop(stdin);
+        let output = child.wait_with_output().unwrap();
+        assert!(output.status.success(), "host exited with {}", output.status);
+        output.stdout
+    }
+}
+
+impl Drop for Host {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.root);
+    }
+}
+
+const SUSPICIOUS_PDF: &[u8] = b"%PDF-1.4\n1 0 obj << /OpenAction 2 0 R >> endobj\n%%EOF\n";
+
+fn frame(message: &Value) -> Vec<u8> {
+    let json = serde_json::to_vec(message).unwrap();
+    let mut data = (json.len() as u32).to_ne_bytes().to_vec();
+    data.extend(json);
+    data
+}
+
+/// Splits host output into replies, failing on anything that isn't a
+/// complete length-prefixed JSON frame.
+fn replies(mut stdout: &[u8]) -> Vec<Value> {
+    let mut replies = Vec::new();
+    while !stdout.is_empty() {
+        assert!(stdout.len() >= 4, "stray bytes after the last frame: {:?}", String::from_utf8_lossy(stdout));
+        let length = u32::from_ne_bytes(stdout[..4].try_into().unwrap()) as usize;
+        assert!(stdout.len() >= 4 + length, "frame of {} bytes is cut short", length);
+        let body = &stdout[4..4 + length];
+        let reply = serde_json::from_slice(body)
+            .unwrap_or_else(|e| panic!("frame is not JSON ({}): {:?}", e, String::from_utf8_lossy(body)));
+        replies.push(reply);
+        stdout = &stdout[4 + length..];
+    }
+    replies
+}
+
+fn scan(path: &Path) -> Value {
+    json!({"type": "scanPdf", "filePath": path})
+}
+
+#[test]
+fn host_uses_the_settings_and_audit_log_in_the_data_directory() {
+    let host = Host::new();
+    host.write_settings(json!({"on_suspicious_download": {"audit_log": true}}));
+    let download = host.download_suspicious_pdf("invoice.pdf");
+
+    let replies = replies(&host.run(&[scan(&download)]));
+    assert_eq!(replies.len(), 1);
+    assert_eq!(replies[0]["verdict"], "suspicious");
+    assert_eq!(replies[0]["actions_taken"], json!(["logged"]));
+
+    let audit = fs::read_to_string(host.data_dir().join("audit.log")).unwrap();
+    assert!(audit.contains("invoice.pdf"), "{}", audit);
+    assert_eq!(fs::read_dir(host.cwd()).unwrap().count(), 0, "the host wrote to its working directory");
+}
+
+#[test]
+fn downloads_in_a_trusted_folder_are_not_scanned() {
+    let host = Host::new();
+    host.write_settings(json!({
+        "trusted_paths": [host.downloads()],
+        "on_suspicious_download": {"audit_log": true},
+    }));
+    let download = host.download_suspicious_pdf("invoice.pdf");
+
+    let replies = replies(&host.run(&[scan(&download)]));
+    assert_eq!(replies.len(), 1);
+    assert_eq!(replies[0]["verdict"], "clean");
+    assert!(replies[0]["reason"].as_str().unwrap().contains("allowlisted"), "{}", replies[0]);
+    assert!(!host.data_dir().join("audit.log").exists());
+    assert!(!host.data_dir().join("pdf_scans").exists(), "a trusted download was saved to the scan history");
+}
+
+#[test]
+fn native_host_output_is_only_reply_frames() {
+    let host = Host::new();
+    let stdout = host.run(&[
+        json!({"type": "ping"}),
+        // Skipped without a reply, and without writing anything else either.
+        json!("not a request"),
+        json!({
+            "type": "scanPdf",
+            "filePath": "inline.pdf",
+            "content": "JVBERi0xLjQKMSAwIG9iaiA8PCAvT3BlbkFjdGlvbiAyIDAgUiA+PiBlbmRvYmoKJSVFT0YK",
+        }),
+        json!({"type": "scanPdf", "content": "%%%"}),
+        scan(&host.downloads().join("missing.pdf")),
+        json!({"type": "getStatus"}),
+    ]);
+
+    let replies = replies(&stdout);
+    let statuses: Vec<&str> = replies.iter().map(|reply| reply["status"].as_str().unwrap()).collect();
+    assert_eq!(statuses, ["OK", "SUSPICIOUS", "ERROR", "ERROR", "OK"]);
+    assert_eq!(replies[1]["verdict"], "suspicious");
+    assert_eq!(replies[2]["verdict"], "error");
+    assert_eq!(replies[3]["verdict"], "error");
+}
+
+#[test]
+fn unreadable_executable_is_an_error_not_clean() {
+    let host = Host::new();
+    let replies = replies(&host.run(&[scan(&host.downloads().join("setup.exe"))]));
+    assert_eq!(replies.len(), 1);
+    assert_eq!(replies[0]["status"], "ERROR");
+    assert_eq!(replies[0]["verdict"], "error");
+}